@@ -0,0 +1,283 @@
+//! Register an existing branch or worktree as a task.
+//!
+//! The branch keeps its name. A branch that isn't checked out anywhere gets a
+//! worktree under the worktree root (see [`worktrees`]); one that is already checked out in
+//! another worktree is adopted together with that worktree. Deleting an
+//! adopted task never deletes its branch.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::task::{self, CreateOpts, Task};
+use super::worktrees;
+
+/// A worktree from `git worktree list`.
+#[derive(Debug, Clone, PartialEq)]
+struct Worktree {
+    path: PathBuf,
+    /// Checked-out branch; `None` for a detached HEAD.
+    branch: Option<String>,
+}
+
+/// Adopt `branch`, the branch checked out in `worktree`, or both (they must
+/// agree). An empty `opts.name` is derived from the branch name.
+pub fn adopt(
+    db: &Connection,
+    repo_root: &Path,
+    branch: Option<&str>,
+    worktree: Option<&Path>,
+    opts: &CreateOpts,
+) -> Result<Task> {
+    let all = worktrees(repo_root)?;
+    let (branch, existing) = match (branch, worktree) {
+        (_, Some(path)) => {
+            let path = path
+                .canonicalize()
+                .with_context(|| format!("no such directory: {}", path.display()))?;
+            let Some(pos) = all.iter().position(|w| same_path(&w.path, &path)) else {
+                bail!("{} is not a worktree of this repository", path.display());
+            };
+            if pos == 0 {
+                bail!("the main checkout can't be adopted — adopt its branch into a new worktree instead");
+            }
+            let Some(checked_out) = all[pos].branch.clone() else {
+                bail!(
+                    "{} has no branch checked out (detached HEAD)",
+                    path.display()
+                );
+            };
+            if let Some(b) = branch.filter(|b| *b != checked_out) {
+                bail!(
+                    "{} has '{}' checked out, not '{}'",
+                    path.display(),
+                    checked_out,
+                    b
+                );
+            }
+            (checked_out, Some(all[pos].path.clone()))
+        }
+        (Some(b), None) => {
+            if git(
+                repo_root,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/heads/{}", b),
+                ],
+            )
+            .is_err()
+            {
+                bail!("no local branch '{}'", b);
+            }
+            match all.iter().position(|w| w.branch.as_deref() == Some(b)) {
+                Some(0) => bail!(
+                    "'{}' is checked out in the main checkout — switch it to another branch first",
+                    b
+                ),
+                Some(pos) => (b.to_string(), Some(all[pos].path.clone())),
+                None => (b.to_string(), None),
+            }
+        }
+        (None, None) => bail!("give a branch to adopt, or --worktree"),
+    };
+
+    let name = match opts.name {
+        "" => default_name(&branch),
+        n => n.to_string(),
+    };
+    task::validate_name(&name)?;
+    if task::get_by_name(db, &name)?.is_some() {
+        bail!(
+            "task '{}' already exists — pick another name with --name",
+            name
+        );
+    }
+    if let Some(t) = task::list(db)?.into_iter().find(|t| t.branch == branch) {
+        bail!("branch '{}' already belongs to task '{}'", branch, t.name);
+    }
+
+    let worktree = match existing {
+        Some(path) => path,
+        None => {
+            let path = worktrees::path(repo_root, &name);
+            let path_str = path.to_str().context("worktree path is not valid UTF-8")?;
+            git(repo_root, &["worktree", "add", path_str, &branch])?;
+            path
+        }
+    };
+    let worktree = worktree
+        .to_str()
+        .context("worktree path is not valid UTF-8")?;
+    task::insert(
+        db,
+        &CreateOpts {
+            name: &name,
+            ..*opts
+        },
+        &branch,
+        worktree,
+        true,
+    )
+}
+
+/// `feature/login-form` → `login-form`.
+fn default_name(branch: &str) -> String {
+    let last = branch.rsplit('/').next().unwrap_or(branch);
+    last.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn worktrees(repo_root: &Path) -> Result<Vec<Worktree>> {
+    let mut out: Vec<Worktree> = Vec::new();
+    for line in git(repo_root, &["worktree", "list", "--porcelain"])?.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            out.push(Worktree {
+                path: PathBuf::from(path),
+                branch: None,
+            });
+        } else if let (Some(b), Some(w)) = (line.strip_prefix("branch refs/heads/"), out.last_mut())
+        {
+            w.branch = Some(b.to_string());
+        }
+    }
+    Ok(out)
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    a.canonicalize().map(|a| a == b).unwrap_or(false)
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (tempfile::TempDir, Connection) {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@test.com"],
+            &["config", "user.name", "Test"],
+            &["commit", "--allow-empty", "-m", "init"],
+            &["branch", "-M", "main"],
+            &["branch", "feature/login"],
+        ] {
+            git(dir.path(), args).unwrap();
+        }
+        (dir, crate::db::open_memory().unwrap())
+    }
+
+    #[test]
+    fn adopts_a_branch_into_a_new_worktree_and_keeps_it_on_delete() {
+        let (repo, db) = setup();
+        let t = adopt(
+            &db,
+            repo.path(),
+            Some("feature/login"),
+            None,
+            &CreateOpts::default(),
+        )
+        .unwrap();
+        assert_eq!(t.name, "login");
+        assert_eq!(t.branch, "feature/login");
+        assert!(t.adopted);
+        let wt = Path::new(&t.worktree);
+        assert!(wt.ends_with(".pit/worktrees/login"));
+        assert_eq!(
+            git(wt, &["branch", "--show-current"]).unwrap(),
+            "feature/login"
+        );
+
+        let again = adopt(
+            &db,
+            repo.path(),
+            Some("feature/login"),
+            None,
+            &CreateOpts {
+                name: "other",
+                ..Default::default()
+            },
+        );
+        assert!(again
+            .unwrap_err()
+            .to_string()
+            .contains("belongs to task 'login'"));
+        assert!(adopt(&db, repo.path(), Some("main"), None, &CreateOpts::default()).is_err());
+        assert!(adopt(&db, repo.path(), Some("nope"), None, &CreateOpts::default()).is_err());
+
+        task::delete(&db, repo.path(), t.id).unwrap();
+        assert!(!wt.exists());
+        assert!(git(repo.path(), &["rev-parse", "--verify", "feature/login"]).is_ok());
+    }
+
+    #[test]
+    fn adopts_an_existing_worktree_in_place() {
+        let (repo, db) = setup();
+        let outside = tempfile::tempdir().unwrap();
+        let wt = outside.path().join("wip");
+        git(
+            repo.path(),
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "human-wip",
+                wt.to_str().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        let t = adopt(
+            &db,
+            repo.path(),
+            None,
+            Some(&wt),
+            &CreateOpts {
+                name: "wip",
+                agent: "codex",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(t.branch, "human-wip");
+        assert_eq!(t.agent, "codex");
+        assert!(same_path(&wt, Path::new(&t.worktree)));
+        assert!(adopt(
+            &db,
+            repo.path(),
+            None,
+            Some(repo.path()),
+            &CreateOpts::default()
+        )
+        .is_err());
+
+        // Neither the worktree nor the branch belong to pit
+        task::delete(&db, repo.path(), t.id).unwrap();
+        assert!(wt.exists());
+        assert!(git(repo.path(), &["rev-parse", "--verify", "human-wip"]).is_ok());
+    }
+}
@@ -0,0 +1,298 @@
+//! Archive tasks instead of deleting them.
+//!
+//! Archiving removes the worktree and the `pit/<name>` branch, but the branch
+//! tip is kept at `refs/pit/archive/<name>`. Uncommitted work is saved as a
+//! snapshot next to the task's checkpoints under `refs/pit/<id>/`, and the DB
+//! row stays with `archived_at` set, so a restore brings the task back as it
+//! was. Archived tasks are left out of [`task::list`].
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::path::Path;
+use std::process::Command;
+
+use super::checkpoint;
+use super::queue;
+use super::task::{self, Status, Task};
+use super::worktrees;
+
+/// Where an archived task's branch tip is kept.
+pub fn archive_ref(name: &str) -> String {
+    format!("refs/pit/archive/{}", name)
+}
+
+/// Snapshot of the uncommitted work the worktree had when it was archived.
+fn work_ref(task_id: i64) -> String {
+    format!("{}archived-work", checkpoint::task_refs(task_id))
+}
+
+/// Archive a task. Like delete, an adopted task's branch and its own
+/// worktree outside the worktree root are left alone.
+pub fn archive(db: &Connection, repo_root: &Path, task: &Task) -> Result<()> {
+    if !task.archived_at.is_empty() {
+        bail!("task '{}' is already archived", task.name);
+    }
+    if task.status == Status::Running {
+        bail!("cannot archive a running task — stop it first");
+    }
+    let tip = git(
+        repo_root,
+        &[
+            "rev-parse",
+            "--verify",
+            &format!("refs/heads/{}^{{commit}}", task.branch),
+        ],
+    )
+    .with_context(|| format!("branch '{}' not found", task.branch))?;
+
+    let worktree = Path::new(&task.worktree);
+    if worktree.exists() {
+        if let Some(commit) = checkpoint::snapshot(worktree, &task.name)? {
+            git(repo_root, &["update-ref", &work_ref(task.id), &commit])
+                .context("failed to save uncommitted work")?;
+        }
+    }
+    git(repo_root, &["update-ref", &archive_ref(&task.name), &tip])
+        .context("failed to save the archived branch")?;
+
+    let managed = worktrees::is_managed(repo_root, worktree);
+    if !task.adopted || managed {
+        let _ = git(
+            repo_root,
+            &["worktree", "remove", "--force", &task.worktree],
+        );
+    }
+    if !task.adopted {
+        let _ = git(repo_root, &["branch", "-D", &task.branch]);
+    }
+
+    queue::remove(db, task.id)?;
+    task::set_archived(db, task.id, true)
+}
+
+/// Bring an archived task back: recreate its branch and worktree, and put
+/// back any uncommitted work it had.
+pub fn restore(db: &Connection, repo_root: &Path, task: &Task) -> Result<Task> {
+    if task.archived_at.is_empty() {
+        bail!("task '{}' is not archived", task.name);
+    }
+    let archived = archive_ref(&task.name);
+    let tip = git(repo_root, &["rev-parse", "--verify", &archived])
+        .with_context(|| format!("archived branch {} is missing", archived))?;
+
+    let branch_ref = format!("refs/heads/{}", task.branch);
+    let created_branch = if git(
+        repo_root,
+        &["rev-parse", "--verify", "--quiet", &branch_ref],
+    )
+    .is_ok()
+    {
+        if !task.adopted {
+            bail!(
+                "branch '{}' already exists — rename or delete it first",
+                task.branch
+            );
+        }
+        false
+    } else {
+        git(repo_root, &["update-ref", &branch_ref, &tip, ""])
+            .context("failed to recreate the branch")?;
+        true
+    };
+
+    let worktree = Path::new(&task.worktree);
+    if !worktree.exists() {
+        let _ = git(repo_root, &["worktree", "prune"]);
+        if let Err(e) = git(
+            repo_root,
+            &["worktree", "add", &task.worktree, &task.branch],
+        ) {
+            if created_branch {
+                let _ = git(repo_root, &["update-ref", "-d", &branch_ref]);
+            }
+            return Err(e);
+        }
+    }
+
+    let work = work_ref(task.id);
+    if git(repo_root, &["rev-parse", "--verify", "--quiet", &work]).is_ok() {
+        checkpoint::restore(worktree, &work).context("failed to restore uncommitted work")?;
+        git(repo_root, &["update-ref", "-d", &work])?;
+    }
+    git(repo_root, &["update-ref", "-d", &archived])?;
+    task::set_archived(db, task.id, false)?;
+    task::get(db, task.id)?.context("task disappeared after restore")
+}
+
+/// Delete an archived task for good: its archived branch, checkpoints and
+/// DB row.
+pub fn delete(db: &Connection, repo_root: &Path, task: &Task) -> Result<()> {
+    let _ = git(repo_root, &["update-ref", "-d", &archive_ref(&task.name)]);
+    task::delete(db, repo_root, task.id)
+}
+
+/// Delete the tasks archived more than `days` days ago. Returns their names.
+pub fn purge(db: &Connection, repo_root: &Path, days: u32) -> Result<Vec<String>> {
+    let cutoff: String = db.query_row(
+        "SELECT datetime('now', ?1)",
+        [format!("-{} days", days)],
+        |r| r.get(0),
+    )?;
+    let mut purged = Vec::new();
+    for t in task::list_archived(db)? {
+        if t.archived_at <= cutoff {
+            delete(db, repo_root, &t)?;
+            purged.push(t.name);
+        }
+    }
+    Ok(purged)
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use task::CreateOpts;
+
+    fn setup() -> (tempfile::TempDir, Connection) {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@test.com"],
+            &["config", "user.name", "Test"],
+            &["commit", "--allow-empty", "-m", "init"],
+            &["branch", "-M", "main"],
+        ] {
+            git(dir.path(), args).unwrap();
+        }
+        (dir, crate::db::open_memory().unwrap())
+    }
+
+    fn new_task(db: &Connection, repo: &Path, name: &str) -> Task {
+        task::create(
+            db,
+            repo,
+            &CreateOpts {
+                name,
+                prompt: "do it",
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn archive_and_restore_keep_commits_checkpoints_and_uncommitted_work() {
+        let (dir, db) = setup();
+        let repo = dir.path();
+        let t = new_task(&db, repo, "parser");
+        let wt = Path::new(&t.worktree);
+        std::fs::write(wt.join("a.txt"), "committed").unwrap();
+        git(wt, &["add", "."]).unwrap();
+        git(wt, &["commit", "-q", "-m", "work"]).unwrap();
+        checkpoint::create(repo, t.id, &t.name, &t.branch, wt, None).unwrap();
+        std::fs::write(wt.join("b.txt"), "wip").unwrap();
+
+        archive(&db, repo, &t).unwrap();
+        assert!(!wt.exists());
+        assert!(git(repo, &["rev-parse", "--verify", "--quiet", "pit/parser"]).is_err());
+        assert!(task::list(&db).unwrap().is_empty());
+        assert!(task::get_by_name(&db, "parser").unwrap().is_none());
+        let archived = task::get_archived(&db, "parser").unwrap().unwrap();
+        assert!(!archived.archived_at.is_empty());
+        assert!(archive(&db, repo, &archived).is_err());
+        assert!(task::create(
+            &db,
+            repo,
+            &CreateOpts {
+                name: "parser",
+                ..Default::default()
+            }
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("archived"));
+
+        let restored = restore(&db, repo, &archived).unwrap();
+        assert!(restored.archived_at.is_empty());
+        assert_eq!(restored.prompt, "do it");
+        assert_eq!(git(wt, &["log", "-1", "--format=%s"]).unwrap(), "work");
+        assert_eq!(std::fs::read_to_string(wt.join("b.txt")).unwrap(), "wip");
+        assert_eq!(checkpoint::list(repo, t.id).unwrap().len(), 1);
+        assert!(git(
+            repo,
+            &["rev-parse", "--verify", "--quiet", &archive_ref("parser")]
+        )
+        .is_err());
+        assert_eq!(task::list(&db).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn archived_tasks_keep_their_metadata_in_sync() {
+        let (dir, _) = setup();
+        let repo = dir.path();
+        let project = crate::core::project::Project::init(repo).unwrap();
+        let db = &project.db;
+        let t = new_task(db, repo, "shelved");
+        archive(db, repo, &t).unwrap();
+        task::set_worktree(db, t.id, "/elsewhere/shelved").unwrap();
+
+        let metas = crate::core::meta::load_all(repo).unwrap();
+        let m = metas.iter().find(|m| m.id == t.id).unwrap();
+        assert!(!m.archived_at.is_empty());
+        assert_eq!(m.worktree, "/elsewhere/shelved");
+    }
+
+    #[test]
+    fn purge_deletes_old_archived_tasks() {
+        let (dir, db) = setup();
+        let repo = dir.path();
+        let old = new_task(&db, repo, "old");
+        let kept = new_task(&db, repo, "kept");
+        checkpoint::create(
+            repo,
+            old.id,
+            &old.name,
+            &old.branch,
+            Path::new(&old.worktree),
+            None,
+        )
+        .unwrap();
+        archive(&db, repo, &old).unwrap();
+        archive(&db, repo, &kept).unwrap();
+        db.execute(
+            "UPDATE tasks SET archived_at = datetime('now', '-40 days') WHERE id = ?1",
+            [old.id],
+        )
+        .unwrap();
+
+        assert_eq!(purge(&db, repo, 30).unwrap(), vec!["old"]);
+        assert!(task::get(&db, old.id).unwrap().is_none());
+        assert!(checkpoint::list(repo, old.id).unwrap().is_empty());
+        assert!(git(
+            repo,
+            &["rev-parse", "--verify", "--quiet", &archive_ref("old")]
+        )
+        .is_err());
+        let left: Vec<String> = task::list_archived(&db)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(left, vec!["kept"]);
+        assert_eq!(purge(&db, repo, 0).unwrap(), vec!["kept"]);
+    }
+}
@@ -0,0 +1,267 @@
+//! Periodic checkpoints while an agent runs.
+//!
+//! The dashboard calls [`run`] on its refresh tick. A running task gets an
+//! automatic checkpoint when the configured interval has passed since its
+//! last checkpoint (or since it was started), or when enough lines changed,
+//! but never when nothing changed at all. Old automatic checkpoints are then
+//! thinned out; manual ones are never touched.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::checkpoint;
+use super::task::{self, Status, Task};
+use super::tmux;
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+/// When to take automatic checkpoints and how many to keep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    /// Minutes between checkpoints; 0 disables the timer.
+    pub interval: u64,
+    /// Changed lines that trigger a checkpoint early; 0 disables.
+    pub lines: usize,
+    /// Newest automatic checkpoints kept as they are. Older ones are thinned
+    /// to one per hour for the last day and one per day before that.
+    pub keep: usize,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            interval: 15,
+            lines: 0,
+            keep: 10,
+        }
+    }
+}
+
+impl Policy {
+    /// Read the policy from config (`checkpoints.interval`,
+    /// `checkpoints.lines`, `checkpoints.keep`).
+    pub fn from_config() -> Self {
+        let defaults = Policy::default();
+        let read = |key: &str, default: usize| {
+            super::config::get(key)
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .unwrap_or(default)
+        };
+        Policy {
+            interval: read("checkpoints.interval", defaults.interval as usize) as u64,
+            lines: read("checkpoints.lines", defaults.lines),
+            keep: read("checkpoints.keep", defaults.keep),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.interval > 0 || self.lines > 0
+    }
+}
+
+/// Check every running task and checkpoint the ones that are due.
+/// Returns the number of checkpoints taken.
+pub fn run(db: &Connection, repo_root: &Path, policy: &Policy, now: i64) -> Result<usize> {
+    if !policy.enabled() {
+        return Ok(0);
+    }
+    let mut taken = 0;
+    for t in task::list(db)? {
+        if t.status != Status::Running {
+            continue;
+        }
+        if tick(repo_root, &t, policy, now)?.is_some() {
+            taken += 1;
+        }
+    }
+    Ok(taken)
+}
+
+/// Take an automatic checkpoint of `task` if one is due, then thin its old
+/// automatic checkpoints. Returns the new checkpoint's index, if any.
+pub fn tick(repo_root: &Path, task: &Task, policy: &Policy, now: i64) -> Result<Option<usize>> {
+    let checkpoints = checkpoint::list(repo_root, task.id)?;
+    let since = checkpoints
+        .last()
+        .map(|c| c.created)
+        .unwrap_or(0)
+        .max(unix_time(&task.updated_at));
+    let interval_due = policy.interval > 0 && now - since >= policy.interval as i64 * 60;
+    if !interval_due && policy.lines == 0 {
+        return Ok(None);
+    }
+
+    let worktree = Path::new(&task.worktree);
+    let changes = checkpoint::changes_since_last(repo_root, task.id, worktree)?;
+    if changes.files == 0 {
+        return Ok(None);
+    }
+    let lines_due = policy.lines > 0 && changes.lines >= policy.lines;
+    if !interval_due && !lines_due {
+        return Ok(None);
+    }
+
+    let agent_output = task
+        .tmux_session
+        .as_deref()
+        .and_then(|name| tmux::capture_pane(name, 50).ok());
+    let index = checkpoint::create_auto(
+        repo_root,
+        task.id,
+        &task.name,
+        &task.branch,
+        worktree,
+        agent_output.as_deref(),
+    )?;
+    prune(repo_root, task.id, policy, now)?;
+    Ok(Some(index))
+}
+
+/// Thin a task's automatic checkpoints according to `policy`.
+/// Returns the number of checkpoints deleted.
+pub fn prune(repo_root: &Path, task_id: i64, policy: &Policy, now: i64) -> Result<usize> {
+    let checkpoints = checkpoint::list(repo_root, task_id)?;
+    let mut buckets = HashSet::new();
+    let mut removed = 0;
+    // Newest first, so the newest checkpoint in each hour/day survives
+    for cp in checkpoints
+        .iter()
+        .rev()
+        .filter(|c| c.auto)
+        .skip(policy.keep)
+    {
+        let bucket = if now - cp.created < DAY {
+            (HOUR, cp.created / HOUR)
+        } else {
+            (DAY, cp.created / DAY)
+        };
+        if !buckets.insert(bucket) {
+            checkpoint::remove(repo_root, cp)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Seconds since the epoch for an SQLite `datetime('now')` value (UTC).
+fn unix_time(datetime: &str) -> i64 {
+    chrono::NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc().timestamp())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+    }
+
+    fn setup() -> (tempfile::TempDir, Task) {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        git(dir.path(), &["config", "user.email", "test@test.com"]);
+        git(dir.path(), &["config", "user.name", "Test"]);
+        git(dir.path(), &["commit", "--allow-empty", "-m", "init"]);
+        git(dir.path(), &["branch", "-M", "main"]);
+        let db = crate::db::open_memory().unwrap();
+        let t = task::create(
+            &db,
+            dir.path(),
+            &task::CreateOpts {
+                name: "auto",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (dir, t)
+    }
+
+    fn now() -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+
+    #[test]
+    fn interval_checkpoint_skips_when_nothing_changed() {
+        let (repo, t) = setup();
+        let policy = Policy {
+            interval: 5,
+            ..Default::default()
+        };
+        let later = now() + 10 * 60;
+        assert_eq!(tick(repo.path(), &t, &policy, later).unwrap(), None);
+
+        std::fs::write(Path::new(&t.worktree).join("a.txt"), "a\n").unwrap();
+        assert_eq!(tick(repo.path(), &t, &policy, now()).unwrap(), None);
+        assert_eq!(tick(repo.path(), &t, &policy, later).unwrap(), Some(1));
+        let cp = &checkpoint::list(repo.path(), t.id).unwrap()[0];
+        assert!(cp.auto && cp.dirty);
+
+        // Nothing new since the checkpoint
+        assert_eq!(tick(repo.path(), &t, &policy, later + 3600).unwrap(), None);
+    }
+
+    #[test]
+    fn line_threshold_triggers_early() {
+        let (repo, t) = setup();
+        let policy = Policy {
+            interval: 0,
+            lines: 3,
+            keep: 10,
+        };
+        let wt = Path::new(&t.worktree);
+        std::fs::write(wt.join("a.txt"), "1\n2\n").unwrap();
+        assert_eq!(tick(repo.path(), &t, &policy, now()).unwrap(), None);
+        std::fs::write(wt.join("a.txt"), "1\n2\n3\n").unwrap();
+        assert_eq!(tick(repo.path(), &t, &policy, now()).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn prune_thins_old_auto_checkpoints_only() {
+        let (repo, t) = setup();
+        let wt = Path::new(&t.worktree);
+        let take = |auto: bool, i: usize| {
+            std::fs::write(wt.join("a.txt"), format!("{}\n", i)).unwrap();
+            if auto {
+                checkpoint::create_auto(repo.path(), t.id, &t.name, &t.branch, wt, None).unwrap();
+            } else {
+                checkpoint::create(repo.path(), t.id, &t.name, &t.branch, wt, None).unwrap();
+            }
+        };
+        take(false, 0);
+        for i in 1..=4 {
+            take(true, i);
+        }
+
+        // All taken within the same hour: keep 2 as they are, one for the hour
+        let policy = Policy {
+            keep: 2,
+            ..Default::default()
+        };
+        assert_eq!(prune(repo.path(), t.id, &policy, now()).unwrap(), 1);
+        let left: Vec<usize> = checkpoint::list(repo.path(), t.id)
+            .unwrap()
+            .iter()
+            .map(|c| c.index)
+            .collect();
+        assert_eq!(left, vec![1, 3, 4, 5]);
+    }
+
+    #[test]
+    fn unix_time_parses_sqlite_datetime() {
+        assert_eq!(unix_time("1970-01-02 00:00:00"), DAY);
+        assert_eq!(unix_time("garbage"), 0);
+    }
+}
@@ -1,88 +1,220 @@
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::io::Write;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
-/// A checkpoint is an annotated git tag capturing the full worktree state
-/// plus context about what was done and what the agent's last output was.
+use super::task::Task;
+
+/// A checkpoint is an annotated git tag object capturing the full worktree
+/// state plus context about what was done and what the agent's last output was.
+///
+/// Checkpoints live outside `refs/tags` so `git push --tags` never publishes
+/// them, and are keyed by task id so a new task can't inherit the checkpoints
+/// of a deleted one with the same name:
+///
+///   refs/pit/<task-id>/checkpoints/<N>   annotated tag object
+///   refs/pit/<task-id>/pre-rollback      state before the last rollback
 ///
-/// Tag format: pit/checkpoint/<task-name>/<N>
 /// Tag message contains structured sections: Done, Agent Context, Files Changed.
+///
+/// A clean worktree is tagged at the branch tip. Uncommitted work is captured
+/// the way `git stash` does it, without adding commits to the branch: a
+/// detached snapshot commit whose tree is the full worktree (untracked files
+/// included), with the branch tip as first parent and a commit of the staged
+/// index as second parent.
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Checkpoint {
     pub index: usize,
-    pub tag: String,
+    /// Full ref name, e.g. `refs/pit/3/checkpoints/1`.
+    #[serde(rename = "ref")]
+    pub refname: String,
     pub commit_hash: String,
     pub message: String,
     pub timestamp: String,
     /// The full annotated tag message (Done + Context + Files).
     pub annotation: String,
+    /// Whether the checkpoint includes uncommitted changes.
+    pub dirty: bool,
+    /// Whether the checkpoint was taken automatically while the agent ran.
+    pub auto: bool,
+    /// When the checkpoint was taken, as a unix timestamp.
+    pub created: i64,
+}
+
+/// What changed in a worktree since its last checkpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Changes {
+    pub files: usize,
+    /// Lines added plus lines removed. Binary files count as one line.
+    pub lines: usize,
+}
+
+/// Subject line of snapshot commits, which never land on a branch.
+const SNAPSHOT_SUBJECT: &str = "[pit checkpoint] snapshot";
+
+/// Where a task's refs live.
+pub fn task_refs(task_id: i64) -> String {
+    format!("refs/pit/{}/", task_id)
+}
+
+/// The highest task ID with refs under `refs/pit/`; 0 if none.
+pub fn highest_task_id(repo_root: &Path) -> Result<i64> {
+    let refs = git(
+        repo_root,
+        &["for-each-ref", "--format=%(refname)", "refs/pit/"],
+    )?;
+    Ok(refs
+        .lines()
+        .filter_map(|r| r.strip_prefix("refs/pit/")?.split('/').next()?.parse().ok())
+        .max()
+        .unwrap_or(0))
 }
 
-/// Create a checkpoint for a task. Commits uncommitted work first.
+fn checkpoint_refs(task_id: i64) -> String {
+    format!("{}checkpoints/", task_refs(task_id))
+}
+
+/// Title-line suffix marking checkpoints taken automatically.
+const AUTO_SUFFIX: &str = " (auto)";
+
+/// Create a checkpoint for a task, including any uncommitted work.
 /// `agent_output` is optional captured terminal output from the agent.
 pub fn create(
     repo_root: &Path,
+    task_id: i64,
     task_name: &str,
     branch: &str,
     worktree: &Path,
     agent_output: Option<&str>,
 ) -> Result<usize> {
-    // Auto-commit any uncommitted changes
-    auto_commit_if_dirty(worktree, task_name)?;
+    create_with(
+        repo_root,
+        task_id,
+        task_name,
+        branch,
+        worktree,
+        agent_output,
+        false,
+    )
+}
 
-    let existing = list(repo_root, task_name)?;
+/// Like [`create`], but marks the checkpoint as automatic so retention may
+/// thin it out later.
+pub fn create_auto(
+    repo_root: &Path,
+    task_id: i64,
+    task_name: &str,
+    branch: &str,
+    worktree: &Path,
+    agent_output: Option<&str>,
+) -> Result<usize> {
+    create_with(
+        repo_root,
+        task_id,
+        task_name,
+        branch,
+        worktree,
+        agent_output,
+        true,
+    )
+}
+
+fn create_with(
+    repo_root: &Path,
+    task_id: i64,
+    task_name: &str,
+    branch: &str,
+    worktree: &Path,
+    agent_output: Option<&str>,
+    auto: bool,
+) -> Result<usize> {
+    let snapshot = snapshot(worktree, task_name)?;
+
+    let existing = list(repo_root, task_id)?;
     let next_idx = existing.last().map(|c| c.index + 1).unwrap_or(1);
-    let tag = format!("pit/checkpoint/{}/{}", task_name, next_idx);
 
-    // Get current HEAD
-    let output = Command::new("git")
-        .args(["rev-parse", branch])
-        .current_dir(repo_root)
-        .output()
-        .context("failed to run git rev-parse")?;
+    let commit = match &snapshot {
+        Some(c) => c.clone(),
+        None => {
+            let output = Command::new("git")
+                .args(["rev-parse", branch])
+                .current_dir(repo_root)
+                .output()
+                .context("failed to run git rev-parse")?;
+
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                bail!("failed to resolve branch '{}': {}", branch, stderr.trim());
+            }
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+    };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("failed to resolve branch '{}': {}", branch, stderr.trim());
+    // Build the annotated tag message
+    let mut annotation =
+        build_annotation(repo_root, task_id, task_name, branch, &commit, agent_output);
+    if auto {
+        let title_end = annotation.find('\n').unwrap_or(annotation.len());
+        annotation.insert_str(title_end, AUTO_SUFFIX);
     }
-    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
-    // Build the annotated tag message
-    let annotation = build_annotation(repo_root, task_name, branch, worktree, agent_output);
+    let tag_name = format!("pit/checkpoint/{}/{}", task_name, next_idx);
+    let tag = make_tag(repo_root, &commit, &tag_name, &annotation)?;
+    let refname = format!("{}{}", checkpoint_refs(task_id), next_idx);
+    git(repo_root, &["update-ref", &refname, &tag]).context("failed to save checkpoint")?;
 
-    // Create annotated tag (--cleanup=verbatim preserves ## headers)
-    let output = Command::new("git")
-        .args([
-            "tag",
-            "-a",
-            &tag,
-            &commit,
-            "-m",
-            &annotation,
-            "--cleanup=verbatim",
-        ])
+    Ok(next_idx)
+}
+
+/// Write an annotated tag object for `commit` without creating a tag ref.
+/// The message is kept verbatim so `## ` headers survive.
+fn make_tag(repo_root: &Path, commit: &str, name: &str, message: &str) -> Result<String> {
+    let tagger = git(repo_root, &["var", "GIT_COMMITTER_IDENT"])?;
+    let content = format!(
+        "object {}\ntype commit\ntag {}\ntagger {}\n\n{}\n",
+        commit,
+        name,
+        tagger,
+        message.trim_end()
+    );
+
+    let mut child = Command::new("git")
+        .arg("mktag")
         .current_dir(repo_root)
-        .output()
-        .context("failed to create git tag")?;
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run git mktag")?;
+    child
+        .stdin
+        .take()
+        .context("failed to open git mktag stdin")?
+        .write_all(content.as_bytes())?;
+    let output = child.wait_with_output()?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("git tag failed: {}", stderr.trim());
+        bail!("git mktag failed: {}", stderr.trim());
     }
-
-    Ok(next_idx)
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 /// List all checkpoints for a task, sorted by index.
-pub fn list(repo_root: &Path, task_name: &str) -> Result<Vec<Checkpoint>> {
-    let prefix = format!("pit/checkpoint/{}/", task_name);
+pub fn list(repo_root: &Path, task_id: i64) -> Result<Vec<Checkpoint>> {
+    let prefix = checkpoint_refs(task_id);
 
     let output = Command::new("git")
-        .args(["tag", "--list", &format!("{}*", prefix)])
+        .args([
+            "for-each-ref",
+            "--format=%(refname) %(creatordate:unix)",
+            &prefix,
+        ])
         .current_dir(repo_root)
         .output()
-        .context("failed to list git tags")?;
+        .context("failed to list checkpoint refs")?;
 
     if !output.status.success() {
         return Ok(vec![]);
@@ -91,13 +223,17 @@ pub fn list(repo_root: &Path, task_name: &str) -> Result<Vec<Checkpoint>> {
     let stdout = String::from_utf8_lossy(&output.stdout);
     let mut checkpoints: Vec<Checkpoint> = Vec::new();
 
-    for tag_name in stdout.lines().filter(|l| !l.is_empty()) {
-        let idx_str = tag_name.strip_prefix(&prefix).unwrap_or("0");
+    for line in stdout.lines().filter(|l| !l.is_empty()) {
+        let (refname, created) = line.split_once(' ').unwrap_or((line, ""));
+        let created = created.parse().unwrap_or(0);
+        let idx_str = refname.strip_prefix(&prefix).unwrap_or("0");
         let index: usize = idx_str.parse().unwrap_or(0);
 
-        // Get commit info
+        // Describe the branch tip, not the snapshot commit on top of it
+        let parents = snapshot_parents(repo_root, refname);
+        let base = parents.as_ref().map(|(b, _)| b.as_str()).unwrap_or(refname);
         let output = Command::new("git")
-            .args(["log", "-1", "--format=%h|%s|%cr", tag_name])
+            .args(["log", "-1", "--format=%h|%s|%cr", base])
             .current_dir(repo_root)
             .output();
 
@@ -115,15 +251,22 @@ pub fn list(repo_root: &Path, task_name: &str) -> Result<Vec<Checkpoint>> {
         };
 
         // Read the annotated tag message
-        let annotation = read_tag_message(repo_root, tag_name);
+        let annotation = read_tag_message(repo_root, refname);
+        let auto = annotation
+            .lines()
+            .next()
+            .is_some_and(|title| title.ends_with(AUTO_SUFFIX));
 
         checkpoints.push(Checkpoint {
             index,
-            tag: tag_name.to_string(),
+            refname: refname.to_string(),
             commit_hash,
             message,
             timestamp,
             annotation,
+            dirty: parents.is_some(),
+            auto,
+            created,
         });
     }
 
@@ -131,15 +274,17 @@ pub fn list(repo_root: &Path, task_name: &str) -> Result<Vec<Checkpoint>> {
     Ok(checkpoints)
 }
 
-/// Rollback a task's worktree to a checkpoint.
-/// Creates a safety tag before resetting.
+/// Rollback a task's worktree to a checkpoint, restoring its commits,
+/// staged and unstaged changes and untracked files. The current state
+/// (uncommitted work included) is saved to the pre-rollback ref first.
 pub fn rollback(
     repo_root: &Path,
+    task_id: i64,
     task_name: &str,
     worktree: &Path,
     target: Option<usize>,
 ) -> Result<usize> {
-    let checkpoints = list(repo_root, task_name)?;
+    let checkpoints = list(repo_root, task_id)?;
     if checkpoints.is_empty() {
         bail!("no checkpoints for task '{}'", task_name);
     }
@@ -152,26 +297,188 @@ pub fn rollback(
         None => checkpoints.last().unwrap(),
     };
 
-    auto_commit_if_dirty(worktree, task_name)?;
-    save_pre_rollback_tag(repo_root, task_name, worktree)?;
+    save_pre_rollback(repo_root, task_id, task_name, worktree)?;
+    restore(worktree, &checkpoint.refname)?;
+
+    Ok(checkpoint.index)
+}
+
+/// Undo the last rollback: put back the state saved in the pre-rollback ref.
+/// The state being replaced is saved in its place, so undoing twice redoes.
+pub fn undo_rollback(
+    repo_root: &Path,
+    task_id: i64,
+    task_name: &str,
+    worktree: &Path,
+) -> Result<()> {
+    let refname = format!("{}pre-rollback", task_refs(task_id));
+    let saved = git(repo_root, &["rev-parse", "--verify", "-q", &refname])
+        .map_err(|_| anyhow::anyhow!("no rollback to undo for task '{}'", task_name))?;
+
+    save_pre_rollback(repo_root, task_id, task_name, worktree)?;
+    restore(worktree, &saved)
+}
 
+/// Whether there is a rollback that [`undo_rollback`] can undo.
+pub fn can_undo(repo_root: &Path, task_id: i64) -> bool {
+    let refname = format!("{}pre-rollback", task_refs(task_id));
+    git(repo_root, &["rev-parse", "--verify", "-q", &refname]).is_ok()
+}
+
+/// Find a task's checkpoint by index.
+pub fn get(repo_root: &Path, task_id: i64, index: usize) -> Result<Checkpoint> {
+    list(repo_root, task_id)?
+        .into_iter()
+        .find(|c| c.index == index)
+        .ok_or_else(|| anyhow::anyhow!("checkpoint {} not found", index))
+}
+
+/// Unified diff (with a stat summary) between two checkpoint refs.
+/// With no `from`, diffs against where the task branched off main.
+pub fn diff(repo_root: &Path, from: Option<&str>, to: &str) -> Result<String> {
+    git_diff(repo_root, from, to, &["--stat", "--patch"])
+}
+
+/// Just the `--stat` summary of [`diff`].
+pub fn diff_stat(repo_root: &Path, from: Option<&str>, to: &str) -> Result<String> {
+    git_diff(repo_root, from, to, &["--stat"])
+}
+
+fn git_diff(repo_root: &Path, from: Option<&str>, to: &str, flags: &[&str]) -> Result<String> {
+    let range = match from {
+        Some(f) => format!("{}..{}", f, to),
+        None => {
+            let main = super::git_info::detect_main_branch(repo_root)?;
+            format!("{}...{}", main, to)
+        }
+    };
     let output = Command::new("git")
-        .args(["reset", "--hard", &checkpoint.tag])
-        .current_dir(worktree)
+        .arg("diff")
+        .args(flags)
+        .arg(&range)
+        .current_dir(repo_root)
         .output()
-        .context("failed to git reset")?;
-
+        .context("failed to run git diff")?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("git reset failed: {}", stderr.trim());
+        bail!("git diff failed: {}", stderr.trim());
     }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
 
-    Ok(checkpoint.index)
+/// Capture uncommitted work (staged, unstaged and untracked) as a detached
+/// snapshot commit, leaving the branch, index and files untouched.
+/// Returns `None` when the worktree is clean.
+pub fn snapshot(worktree: &Path, task_name: &str) -> Result<Option<String>> {
+    if git(worktree, &["status", "--porcelain"])?.is_empty() {
+        return Ok(None);
+    }
+    let head = git(worktree, &["rev-parse", "HEAD"])?;
+
+    // The staged state, so rollback can put the index back as it was
+    let index_tree = git(worktree, &["write-tree"])?;
+    let index_commit = git(
+        worktree,
+        &[
+            "commit-tree",
+            &index_tree,
+            "-p",
+            &head,
+            "-m",
+            &format!("{} index for {}", SNAPSHOT_SUBJECT, task_name),
+        ],
+    )?;
+
+    let tree = worktree_tree(worktree, &index_tree)?;
+
+    let commit = git(
+        worktree,
+        &[
+            "commit-tree",
+            &tree,
+            "-p",
+            &head,
+            "-p",
+            &index_commit,
+            "-m",
+            &format!("{} for {}", SNAPSHOT_SUBJECT, task_name),
+        ],
+    )?;
+    Ok(Some(commit))
+}
+
+/// Tree-ish of the full worktree, uncommitted work included; `HEAD` when
+/// it is clean.
+pub fn current_tree(worktree: &Path) -> Result<String> {
+    if git(worktree, &["status", "--porcelain"])?.is_empty() {
+        return Ok("HEAD".to_string());
+    }
+    let index_tree = git(worktree, &["write-tree"])?;
+    worktree_tree(worktree, &index_tree)
+}
+
+/// Tree object of the full worktree (untracked files included), staged on
+/// top of `index_tree` through a throwaway index so the real one is untouched.
+fn worktree_tree(worktree: &Path, index_tree: &str) -> Result<String> {
+    let tmp_index = git(worktree, &["rev-parse", "--git-path", "pit-snapshot-index"])?;
+    let tmp_index = worktree.join(tmp_index);
+    let with_tmp = |args: &[&str]| -> Result<String> {
+        let output = Command::new("git")
+            .args(args)
+            .env("GIT_INDEX_FILE", &tmp_index)
+            .current_dir(worktree)
+            .output()
+            .context("failed to run git")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("git {} failed: {}", args[0], stderr.trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let tree = with_tmp(&["read-tree", index_tree])
+        .and_then(|_| with_tmp(&["add", "-A"]))
+        .and_then(|_| with_tmp(&["write-tree"]));
+    let _ = std::fs::remove_file(&tmp_index);
+    tree
+}
+
+/// Put the worktree back to exactly the state recorded at `rev`: the branch
+/// is reset to its tip and any snapshot's files and index are laid on top.
+pub fn restore(worktree: &Path, rev: &str) -> Result<()> {
+    let (base, index) = match snapshot_parents(worktree, rev) {
+        Some((base, index)) => (base, Some(index)),
+        None => (rev.to_string(), None),
+    };
+
+    git(worktree, &["reset", "--hard", &base]).context("git reset failed")?;
+    // Untracked files that weren't there at checkpoint time (they are kept
+    // in the pre-rollback snapshot)
+    git(worktree, &["clean", "-fdq"]).context("git clean failed")?;
+
+    if let Some(index) = index {
+        git(worktree, &["read-tree", "-u", "--reset", rev])
+            .context("failed to restore checkpoint files")?;
+        git(worktree, &["read-tree", &index]).context("failed to restore checkpoint index")?;
+    }
+    Ok(())
+}
+
+/// If `rev` is a snapshot commit, its (branch tip, index commit) parents.
+fn snapshot_parents(dir: &Path, rev: &str) -> Option<(String, String)> {
+    let out = git(dir, &["log", "-1", "--format=%P|%s", rev]).ok()?;
+    let (parents, subject) = out.split_once('|')?;
+    if !subject.starts_with(SNAPSHOT_SUBJECT) {
+        return None;
+    }
+    match parents.split_whitespace().collect::<Vec<_>>()[..] {
+        [base, index] => Some((base.to_string(), index.to_string())),
+        _ => None,
+    }
 }
 
 /// Check if the branch has new commits since the last checkpoint.
-pub fn has_new_commits(repo_root: &Path, task_name: &str, branch: &str) -> bool {
-    let checkpoints = match list(repo_root, task_name) {
+pub fn has_new_commits(repo_root: &Path, task_id: i64, branch: &str) -> bool {
+    let checkpoints = match list(repo_root, task_id) {
         Ok(c) => c,
         Err(_) => return false,
     };
@@ -180,10 +487,10 @@ pub fn has_new_commits(repo_root: &Path, task_name: &str, branch: &str) -> bool
         return has_commits_beyond_main(repo_root, branch);
     }
 
-    let last_tag = &checkpoints.last().unwrap().tag;
+    let last = &checkpoints.last().unwrap().refname;
 
     let output = Command::new("git")
-        .args(["rev-list", &format!("{}..{}", last_tag, branch), "--count"])
+        .args(["rev-list", &format!("{}..{}", last, branch), "--count"])
         .current_dir(repo_root)
         .output();
 
@@ -199,14 +506,241 @@ pub fn has_new_commits(repo_root: &Path, task_name: &str, branch: &str) -> bool
     }
 }
 
+/// Count what changed in `worktree` (commits, staged, unstaged and
+/// untracked files) since the task's last checkpoint, or since the branch
+/// point when there is none. Nothing is written to the branch or index.
+pub fn changes_since_last(repo_root: &Path, task_id: i64, worktree: &Path) -> Result<Changes> {
+    let base = match list(repo_root, task_id)?.pop() {
+        Some(cp) => cp.refname,
+        None => {
+            let main = super::git_info::detect_main_branch(repo_root)?;
+            git(worktree, &["merge-base", &main, "HEAD"])?
+        }
+    };
+    let current = current_tree(worktree)?;
+    let numstat = git(worktree, &["diff", "--numstat", &base, &current])?;
+    let mut changes = Changes::default();
+    for line in numstat.lines().filter(|l| !l.is_empty()) {
+        let mut cols = line.split('\t');
+        let added = cols.next().and_then(|n| n.parse::<usize>().ok());
+        let removed = cols.next().and_then(|n| n.parse::<usize>().ok());
+        changes.files += 1;
+        changes.lines += match (added, removed) {
+            (Some(a), Some(r)) => a + r,
+            _ => 1,
+        };
+    }
+    Ok(changes)
+}
+
+/// Paths whose content in the worktree (uncommitted work included) differs
+/// from the checkpoint `rev`.
+pub fn changed_paths(worktree: &Path, rev: &str) -> Result<Vec<String>> {
+    let current = current_tree(worktree)?;
+    let names = git(worktree, &["diff", "--name-only", rev, &current])?;
+    Ok(names.lines().map(|l| l.to_string()).collect())
+}
+
+/// Restore only `paths` (relative to the worktree) from a checkpoint, leaving
+/// the branch, the index and every other file alone. Paths that didn't exist
+/// at checkpoint time are deleted. With `patch`, git asks hunk by hunk on the
+/// terminal instead.
+///
+/// The current state is checkpointed and saved to the pre-rollback ref
+/// first, so the restore can be undone like a rollback. Returns the restored
+/// checkpoint's index and the index of the safety checkpoint, if one was
+/// needed.
+pub fn restore_paths(
+    repo_root: &Path,
+    task: &Task,
+    target: Option<usize>,
+    paths: &[String],
+    patch: bool,
+) -> Result<(usize, Option<usize>)> {
+    if paths.is_empty() {
+        bail!("no paths to restore");
+    }
+    let checkpoints = list(repo_root, task.id)?;
+    let checkpoint = match target {
+        Some(idx) => checkpoints
+            .iter()
+            .find(|c| c.index == idx)
+            .ok_or_else(|| anyhow::anyhow!("checkpoint {} not found", idx))?,
+        None => checkpoints
+            .last()
+            .ok_or_else(|| anyhow::anyhow!("no checkpoints for task '{}'", task.name))?,
+    };
+    let worktree = Path::new(&task.worktree);
+
+    // Sort paths into ones the checkpoint has and ones to delete
+    let mut present = Vec::new();
+    let mut absent = Vec::new();
+    for path in paths {
+        let in_checkpoint = !git(
+            worktree,
+            &[
+                "ls-tree",
+                "-r",
+                "--name-only",
+                &checkpoint.refname,
+                "--",
+                path,
+            ],
+        )?
+        .is_empty();
+        if in_checkpoint {
+            present.push(path.as_str());
+        } else if worktree.join(path).exists() {
+            absent.push(path.as_str());
+        } else {
+            bail!(
+                "'{}' is in neither checkpoint {} nor the worktree",
+                path,
+                checkpoint.index
+            );
+        }
+    }
+
+    let safety = if changes_since_last(repo_root, task.id, worktree)?.files > 0 {
+        Some(create(
+            repo_root,
+            task.id,
+            &task.name,
+            &task.branch,
+            worktree,
+            None,
+        )?)
+    } else {
+        None
+    };
+    save_pre_rollback(repo_root, task.id, &task.name, worktree)?;
+
+    if !present.is_empty() {
+        let source = format!("--source={}", checkpoint.refname);
+        let mut args = vec!["restore", source.as_str(), "--worktree"];
+        if patch {
+            args.push("--patch");
+        }
+        args.push("--");
+        args.extend(&present);
+        if patch {
+            // Interactive: git talks to the terminal directly
+            let status = Command::new("git")
+                .args(&args)
+                .current_dir(worktree)
+                .status()
+                .context("failed to run git restore")?;
+            if !status.success() {
+                bail!("git restore --patch failed");
+            }
+        } else {
+            git(worktree, &args).context("failed to restore files")?;
+        }
+    }
+    for path in absent {
+        let full = worktree.join(path);
+        if full.is_dir() {
+            std::fs::remove_dir_all(&full)
+        } else {
+            std::fs::remove_file(&full)
+        }
+        .with_context(|| format!("failed to remove {}", path))?;
+    }
+
+    Ok((checkpoint.index, safety))
+}
+
+/// Delete a single checkpoint.
+pub fn remove(repo_root: &Path, checkpoint: &Checkpoint) -> Result<()> {
+    git(repo_root, &["update-ref", "-d", &checkpoint.refname])
+        .with_context(|| format!("failed to delete checkpoint {}", checkpoint.index))?;
+    Ok(())
+}
+
+/// Delete all of a task's checkpoint refs.
+pub fn remove_all(repo_root: &Path, task_id: i64) -> Result<()> {
+    let refs = git(
+        repo_root,
+        &["for-each-ref", "--format=%(refname)", &task_refs(task_id)],
+    )?;
+    for refname in refs.lines().filter(|l| !l.is_empty()) {
+        git(repo_root, &["update-ref", "-d", refname])?;
+    }
+    Ok(())
+}
+
+/// Move checkpoints from the old tag layout (`pit/checkpoint/<name>/<N>`,
+/// `pit/pre-rollback/<name>`) under `refs/pit/<task-id>/`. Tags older than
+/// the task with that name belonged to an earlier, deleted task and go to
+/// `refs/pit/orphaned/<name>/` instead. Returns how many tags were moved.
+pub fn migrate_tags(repo_root: &Path, tasks: &[Task]) -> Result<usize> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(refname) %(objectname) %(creatordate:format-local:%Y-%m-%d %H:%M:%S)",
+            "refs/tags/pit/",
+        ])
+        // Match the UTC timestamps SQLite stores in created_at
+        .env("TZ", "UTC")
+        .current_dir(repo_root)
+        .output()
+        .context("failed to list tags")?;
+    if !output.status.success() {
+        return Ok(0);
+    }
+
+    let mut moved = 0;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut parts = line.splitn(3, ' ');
+        let (Some(refname), Some(object), Some(date)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let rest = refname.strip_prefix("refs/tags/pit/").unwrap_or("");
+        let (name, suffix) = if let Some(r) = rest.strip_prefix("checkpoint/") {
+            match r.rsplit_once('/') {
+                Some((name, idx)) => (name, format!("checkpoints/{}", idx)),
+                None => continue,
+            }
+        } else if let Some(name) = rest.strip_prefix("pre-rollback/") {
+            (name, "pre-rollback".to_string())
+        } else {
+            continue;
+        };
+
+        // Matched by name. The date only picks between tasks that had the
+        // same name: the newest one created by then, else the oldest. The
+        // tag's and the row's clocks differ, so it can't rule a task out.
+        let mut same_name: Vec<&Task> = tasks.iter().filter(|t| t.name == name).collect();
+        same_name.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        let owner = same_name
+            .iter()
+            .rev()
+            .find(|t| t.created_at.as_str() <= date)
+            .or(same_name.first());
+        let prefix = match owner {
+            Some(t) => task_refs(t.id),
+            None => format!("refs/pit/orphaned/{}/", name),
+        };
+        git(
+            repo_root,
+            &["update-ref", &format!("{}{}", prefix, suffix), object],
+        )?;
+        git(repo_root, &["update-ref", "-d", refname])?;
+        moved += 1;
+    }
+    Ok(moved)
+}
+
 // ── Annotation builder ──
 
 /// Build the structured annotation for a checkpoint tag.
 fn build_annotation(
     repo_root: &Path,
+    task_id: i64,
     task_name: &str,
     branch: &str,
-    worktree: &Path,
+    commit: &str,
     agent_output: Option<&str>,
 ) -> String {
     let mut parts: Vec<String> = Vec::new();
@@ -215,7 +749,7 @@ fn build_annotation(
     parts.push(String::new());
 
     // ## Done — from commit messages since last checkpoint or main
-    let done = gather_done_section(repo_root, task_name, branch);
+    let done = gather_done_section(repo_root, task_id, branch);
     if !done.is_empty() {
         parts.push("## Done".to_string());
         for line in &done {
@@ -237,7 +771,7 @@ fn build_annotation(
     }
 
     // ## Files Changed — diff stat
-    let files = gather_files_section(worktree);
+    let files = gather_files_section(repo_root, commit);
     if !files.is_empty() {
         parts.push("## Files Changed".to_string());
         for f in &files {
@@ -249,10 +783,22 @@ fn build_annotation(
     parts.join("\n")
 }
 
+/// The "## Done" items of a checkpoint annotation.
+pub fn done_items(annotation: &str) -> Vec<String> {
+    annotation
+        .lines()
+        .skip_while(|l| l.trim() != "## Done")
+        .skip(1)
+        .take_while(|l| !l.starts_with("## "))
+        .filter_map(|l| l.strip_prefix("- "))
+        .map(|l| l.trim().to_string())
+        .collect()
+}
+
 /// Get commit messages since the last checkpoint (or since main).
-fn gather_done_section(repo_root: &Path, task_name: &str, branch: &str) -> Vec<String> {
-    let base = match list(repo_root, task_name) {
-        Ok(cps) if !cps.is_empty() => cps.last().unwrap().tag.clone(),
+fn gather_done_section(repo_root: &Path, task_id: i64, branch: &str) -> Vec<String> {
+    let base = match list(repo_root, task_id) {
+        Ok(cps) if !cps.is_empty() => cps.last().unwrap().refname.clone(),
         _ => match super::git_info::detect_main_branch(repo_root) {
             Ok(main) => main,
             Err(_) => return vec![],
@@ -284,7 +830,7 @@ fn gather_commits_since(repo_root: &Path, base: &str, branch: &str) -> Vec<Strin
 /// Extract meaningful context from agent terminal output.
 /// Looks for the last substantive block — skips blank lines, shell prompts,
 /// and keeps the last ~20 meaningful lines.
-fn extract_agent_context(raw_output: &str) -> Vec<String> {
+pub fn extract_agent_context(raw_output: &str) -> Vec<String> {
     let lines: Vec<&str> = raw_output.lines().collect();
 
     // Walk backwards to find meaningful content
@@ -309,12 +855,17 @@ fn extract_agent_context(raw_output: &str) -> Vec<String> {
     meaningful
 }
 
-/// Get the list of changed files in the worktree (vs HEAD).
-fn gather_files_section(worktree: &Path) -> Vec<String> {
+/// Get the list of files changed by the last commit plus any uncommitted
+/// work captured in `commit`.
+fn gather_files_section(repo_root: &Path, commit: &str) -> Vec<String> {
+    let base = match snapshot_parents(repo_root, commit) {
+        Some((tip, _)) => format!("{}~1", tip),
+        None => format!("{}~1", commit),
+    };
     // Use diff --stat for a compact summary
     let output = Command::new("git")
-        .args(["diff", "HEAD~1", "--stat", "--stat-width=80"])
-        .current_dir(worktree)
+        .args(["diff", &base, commit, "--stat", "--stat-width=80"])
+        .current_dir(repo_root)
         .output();
 
     match output {
@@ -330,11 +881,11 @@ fn gather_files_section(worktree: &Path) -> Vec<String> {
     }
 }
 
-/// Read the message from an annotated git tag.
-fn read_tag_message(repo_root: &Path, tag_name: &str) -> String {
-    // git tag -l --format='%(contents)' <tag> gives the full annotation
+/// Read the message from an annotated tag ref.
+fn read_tag_message(repo_root: &Path, refname: &str) -> String {
+    // %(contents) of a tag object is the full annotation
     let output = Command::new("git")
-        .args(["tag", "-l", "--format=%(contents)", tag_name])
+        .args(["for-each-ref", "--format=%(contents)", refname])
         .current_dir(repo_root)
         .output();
 
@@ -346,63 +897,48 @@ fn read_tag_message(repo_root: &Path, tag_name: &str) -> String {
 
 // ── Other helpers ──
 
-fn auto_commit_if_dirty(worktree: &Path, task_name: &str) -> Result<bool> {
-    let _ = Command::new("git")
-        .args(["add", "-A"])
-        .current_dir(worktree)
-        .output();
-
-    let output = Command::new("git")
-        .args(["diff", "--cached", "--quiet"])
-        .current_dir(worktree)
-        .output()
-        .context("failed to check staged changes")?;
-
-    if output.status.success() {
-        return Ok(false);
-    }
-
-    let msg = format!("[pit checkpoint] auto-save for {}", task_name);
-    let output = Command::new("git")
-        .args(["commit", "-m", &msg])
-        .current_dir(worktree)
-        .output()
-        .context("failed to auto-commit")?;
+fn save_pre_rollback(
+    repo_root: &Path,
+    task_id: i64,
+    task_name: &str,
+    worktree: &Path,
+) -> Result<()> {
+    let refname = format!("{}pre-rollback", task_refs(task_id));
+
+    let commit = match snapshot(worktree, task_name)? {
+        Some(c) => c,
+        None => {
+            let output = Command::new("git")
+                .args(["rev-parse", "HEAD"])
+                .current_dir(worktree)
+                .output()
+                .context("failed to get worktree HEAD")?;
+
+            if !output.status.success() {
+                return Ok(());
+            }
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+    };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("auto-commit failed: {}", stderr.trim());
-    }
+    git(repo_root, &["update-ref", &refname, &commit])
+        .context("failed to save pre-rollback ref")?;
 
-    Ok(true)
+    Ok(())
 }
 
-fn save_pre_rollback_tag(repo_root: &Path, task_name: &str, worktree: &Path) -> Result<()> {
-    let tag = format!("pit/pre-rollback/{}", task_name);
-
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
     let output = Command::new("git")
-        .args(["rev-parse", "HEAD"])
-        .current_dir(worktree)
+        .args(args)
+        .current_dir(dir)
         .output()
-        .context("failed to get worktree HEAD")?;
-
+        .context("failed to run git")?;
     if !output.status.success() {
-        return Ok(());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
     }
-
-    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-    let _ = Command::new("git")
-        .args(["tag", "-d", &tag])
-        .current_dir(repo_root)
-        .output();
-
-    let _ = Command::new("git")
-        .args(["tag", &tag, &commit])
-        .current_dir(repo_root)
-        .output();
-
-    Ok(())
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 fn has_commits_beyond_main(repo_root: &Path, branch: &str) -> bool {
@@ -486,10 +1022,18 @@ mod tests {
         let repo = make_git_repo();
         add_commit(repo.path(), "pit/test-task", "first change");
 
-        let idx = create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        let idx = create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
         assert_eq!(idx, 1);
 
-        let checkpoints = list(repo.path(), "test-task").unwrap();
+        let checkpoints = list(repo.path(), 1).unwrap();
         assert_eq!(checkpoints.len(), 1);
         assert_eq!(checkpoints[0].index, 1);
         assert_eq!(checkpoints[0].message, "first change");
@@ -500,9 +1044,17 @@ mod tests {
         let repo = make_git_repo();
         add_commit(repo.path(), "pit/test-task", "implement feature X");
 
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
-        let checkpoints = list(repo.path(), "test-task").unwrap();
+        let checkpoints = list(repo.path(), 1).unwrap();
         assert!(!checkpoints[0].annotation.is_empty());
         assert!(checkpoints[0].annotation.contains("[pit checkpoint]"));
         assert!(checkpoints[0].annotation.contains("## Done"));
@@ -522,6 +1074,7 @@ mod tests {
 
         create(
             repo.path(),
+            1,
             "test-task",
             "pit/test-task",
             repo.path(),
@@ -529,44 +1082,132 @@ mod tests {
         )
         .unwrap();
 
-        let checkpoints = list(repo.path(), "test-task").unwrap();
+        let checkpoints = list(repo.path(), 1).unwrap();
         let ann = &checkpoints[0].annotation;
         assert!(ann.contains("## Agent Context"), "annotation: {}", ann);
         assert!(ann.contains("rate limiting"), "annotation: {}", ann);
     }
 
-    #[test]
-    fn checkpoint_auto_commits_dirty_worktree() {
-        let repo = make_git_repo();
-        StdCommand::new("git")
-            .args(["checkout", "pit/test-task"])
-            .current_dir(repo.path())
+    fn git_out(dir: &Path, args: &[&str]) -> String {
+        let output = StdCommand::new("git")
+            .args(args)
+            .current_dir(dir)
             .output()
             .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn checkpoint_snapshots_dirty_worktree_without_commits() {
+        let repo = make_git_repo();
+        add_commit(repo.path(), "pit/test-task", "real work");
+        let head = git_out(repo.path(), &["rev-parse", "HEAD"]);
 
         write_file(repo.path(), "dirty.txt", "uncommitted work");
 
-        let idx = create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        let idx = create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
         assert_eq!(idx, 1);
 
-        let output = StdCommand::new("git")
-            .args(["show", "HEAD:dirty.txt"])
-            .current_dir(repo.path())
+        // Branch and worktree are untouched
+        assert_eq!(git_out(repo.path(), &["rev-parse", "HEAD"]), head);
+        assert_eq!(
+            git_out(repo.path(), &["status", "--porcelain"]),
+            "?? dirty.txt"
+        );
+
+        // The checkpoint holds the file and describes the branch tip
+        let content = git_out(repo.path(), &["show", "refs/pit/1/checkpoints/1:dirty.txt"]);
+        assert_eq!(content, "uncommitted work");
+        let cp = &list(repo.path(), 1).unwrap()[0];
+        assert!(cp.dirty);
+        assert_eq!(cp.message, "real work");
+        assert!(cp.annotation.contains("dirty.txt"), "{}", cp.annotation);
+    }
+
+    #[test]
+    fn rollback_restores_uncommitted_state_exactly() {
+        let repo = make_git_repo();
+        let dir = repo.path();
+        add_commit(dir, "pit/test-task", "base");
+        write_file(dir, "tracked.txt", "v1");
+        StdCommand::new("git")
+            .args(["add", "tracked.txt"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        add_commit(dir, "pit/test-task", "add tracked");
+
+        // Staged, unstaged and untracked changes
+        write_file(dir, "staged.txt", "staged");
+        StdCommand::new("git")
+            .args(["add", "staged.txt"])
+            .current_dir(dir)
             .output()
             .unwrap();
-        assert!(output.status.success());
+        write_file(dir, "tracked.txt", "v2 unstaged");
+        write_file(dir, "untracked.txt", "new");
+        let status = git_out(dir, &["status", "--porcelain"]);
+        let head = git_out(dir, &["rev-parse", "HEAD"]);
+        create(dir, 1, "test-task", "pit/test-task", dir, None).unwrap();
+
+        // Make a mess: commit, edit, delete, add files
+        add_commit(dir, "pit/test-task", "later");
+        write_file(dir, "tracked.txt", "v3");
+        std::fs::remove_file(dir.join("untracked.txt")).unwrap();
+        write_file(dir, "junk.txt", "junk");
+
+        rollback(dir, 1, "test-task", dir, None).unwrap();
+
+        assert_eq!(git_out(dir, &["rev-parse", "HEAD"]), head);
+        assert_eq!(git_out(dir, &["status", "--porcelain"]), status);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("tracked.txt")).unwrap(),
+            "v2 unstaged"
+        );
+        assert!(dir.join("untracked.txt").exists());
+        assert!(!dir.join("junk.txt").exists());
+        // No auto-save commits on the branch
+        assert!(!git_out(dir, &["log", "--format=%s"]).contains("[pit checkpoint]"));
+
+        // The discarded state is kept in the pre-rollback snapshot
+        let junk = git_out(dir, &["show", "refs/pit/1/pre-rollback:junk.txt"]);
+        assert_eq!(junk, "junk");
     }
 
     #[test]
     fn multiple_checkpoints_increment() {
         let repo = make_git_repo();
         add_commit(repo.path(), "pit/test-task", "change 1");
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
         add_commit(repo.path(), "pit/test-task", "change 2");
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
-        let checkpoints = list(repo.path(), "test-task").unwrap();
+        let checkpoints = list(repo.path(), 1).unwrap();
         assert_eq!(checkpoints.len(), 2);
         // Second checkpoint's Done section should only have "change 2"
         // (change 1 was before checkpoint #1)
@@ -577,7 +1218,15 @@ mod tests {
     fn rollback_creates_pre_rollback_tag() {
         let repo = make_git_repo();
         add_commit(repo.path(), "pit/test-task", "good");
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
         add_commit(repo.path(), "pit/test-task", "bad");
 
@@ -588,10 +1237,10 @@ mod tests {
             .unwrap();
         let pre_rollback_commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
-        rollback(repo.path(), "test-task", repo.path(), None).unwrap();
+        rollback(repo.path(), 1, "test-task", repo.path(), None).unwrap();
 
         let output = StdCommand::new("git")
-            .args(["rev-parse", "pit/pre-rollback/test-task"])
+            .args(["rev-parse", "refs/pit/1/pre-rollback"])
             .current_dir(repo.path())
             .output()
             .unwrap();
@@ -603,15 +1252,21 @@ mod tests {
     fn rollback_to_last_checkpoint() {
         let repo = make_git_repo();
         add_commit(repo.path(), "pit/test-task", "good change");
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
-        let cp_hash = list(repo.path(), "test-task").unwrap()[0]
-            .commit_hash
-            .clone();
+        let cp_hash = list(repo.path(), 1).unwrap()[0].commit_hash.clone();
 
         add_commit(repo.path(), "pit/test-task", "bad change");
 
-        let idx = rollback(repo.path(), "test-task", repo.path(), None).unwrap();
+        let idx = rollback(repo.path(), 1, "test-task", repo.path(), None).unwrap();
         assert_eq!(idx, 1);
 
         let output = StdCommand::new("git")
@@ -628,14 +1283,30 @@ mod tests {
         let repo = make_git_repo();
 
         add_commit(repo.path(), "pit/test-task", "v1");
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
         add_commit(repo.path(), "pit/test-task", "v2");
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
         add_commit(repo.path(), "pit/test-task", "v3");
 
-        let idx = rollback(repo.path(), "test-task", repo.path(), Some(1)).unwrap();
+        let idx = rollback(repo.path(), 1, "test-task", repo.path(), Some(1)).unwrap();
         assert_eq!(idx, 1);
 
         let output = StdCommand::new("git")
@@ -647,10 +1318,98 @@ mod tests {
         assert_eq!(msg, "v1");
     }
 
+    #[test]
+    fn undo_rollback_swaps_states() {
+        let repo = make_git_repo();
+        let dir = repo.path();
+        add_commit(dir, "pit/test-task", "v1");
+        create(dir, 1, "test-task", "pit/test-task", dir, None).unwrap();
+        add_commit(dir, "pit/test-task", "v2");
+        write_file(dir, "wip.txt", "wip");
+
+        assert!(!can_undo(dir, 1));
+        assert!(undo_rollback(dir, 1, "test-task", dir).is_err());
+
+        rollback(dir, 1, "test-task", dir, None).unwrap();
+        assert_eq!(git_out(dir, &["log", "-1", "--format=%s"]), "v1");
+        assert!(!dir.join("wip.txt").exists());
+
+        assert!(can_undo(dir, 1));
+        undo_rollback(dir, 1, "test-task", dir).unwrap();
+        assert_eq!(git_out(dir, &["log", "-1", "--format=%s"]), "v2");
+        assert_eq!(std::fs::read_to_string(dir.join("wip.txt")).unwrap(), "wip");
+
+        // Undoing again goes back to the rolled-back state
+        undo_rollback(dir, 1, "test-task", dir).unwrap();
+        assert_eq!(git_out(dir, &["log", "-1", "--format=%s"]), "v1");
+    }
+
+    #[test]
+    fn restore_paths_only_touches_given_files() {
+        let repo = make_git_repo();
+        let db = crate::db::open_memory().unwrap();
+        let t = crate::core::task::create(
+            &db,
+            repo.path(),
+            &crate::core::task::CreateOpts {
+                name: "partial",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let wt = Path::new(&t.worktree);
+        write_file(wt, "a.txt", "a1\n");
+        write_file(wt, "b.txt", "b1\n");
+        create(repo.path(), t.id, &t.name, &t.branch, wt, None).unwrap();
+
+        write_file(wt, "a.txt", "a2\n");
+        write_file(wt, "b.txt", "b2\n");
+        write_file(wt, "new.txt", "new\n");
+        let mut changed = changed_paths(wt, &list(repo.path(), t.id).unwrap()[0].refname).unwrap();
+        changed.sort();
+        assert_eq!(changed, vec!["a.txt", "b.txt", "new.txt"]);
+
+        let paths = vec!["a.txt".to_string(), "new.txt".to_string()];
+        let (idx, safety) = restore_paths(repo.path(), &t, Some(1), &paths, false).unwrap();
+        assert_eq!((idx, safety), (1, Some(2)));
+        assert_eq!(std::fs::read_to_string(wt.join("a.txt")).unwrap(), "a1\n");
+        assert_eq!(std::fs::read_to_string(wt.join("b.txt")).unwrap(), "b2\n");
+        assert!(!wt.join("new.txt").exists());
+
+        // The restore can be undone like a rollback
+        undo_rollback(repo.path(), t.id, &t.name, wt).unwrap();
+        assert_eq!(std::fs::read_to_string(wt.join("a.txt")).unwrap(), "a2\n");
+        assert!(wt.join("new.txt").exists());
+
+        let missing = vec!["nope.txt".to_string()];
+        assert!(restore_paths(repo.path(), &t, Some(1), &missing, false).is_err());
+    }
+
+    #[test]
+    fn diff_between_checkpoints() {
+        let repo = make_git_repo();
+        let dir = repo.path();
+        StdCommand::new("git")
+            .args(["checkout", "-q", "pit/test-task"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        write_file(dir, "a.txt", "one\n");
+        create(dir, 1, "test-task", "pit/test-task", dir, None).unwrap();
+        write_file(dir, "a.txt", "two\n");
+        create(dir, 1, "test-task", "pit/test-task", dir, None).unwrap();
+
+        let cps = list(dir, 1).unwrap();
+        let d = diff(dir, Some(&cps[0].refname), &cps[1].refname).unwrap();
+        assert!(d.contains("-one"), "{}", d);
+        assert!(d.contains("+two"), "{}", d);
+        assert!(d.contains("1 file changed"), "{}", d);
+    }
+
     #[test]
     fn rollback_no_checkpoints_fails() {
         let repo = make_git_repo();
-        let result = rollback(repo.path(), "test-task", repo.path(), None);
+        let result = rollback(repo.path(), 1, "test-task", repo.path(), None);
         assert!(result.is_err());
     }
 
@@ -658,12 +1417,20 @@ mod tests {
     fn has_new_commits_detects_changes() {
         let repo = make_git_repo();
         add_commit(repo.path(), "pit/test-task", "work");
-        create(repo.path(), "test-task", "pit/test-task", repo.path(), None).unwrap();
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
 
-        assert!(!has_new_commits(repo.path(), "test-task", "pit/test-task"));
+        assert!(!has_new_commits(repo.path(), 1, "pit/test-task"));
 
         add_commit(repo.path(), "pit/test-task", "more work");
-        assert!(has_new_commits(repo.path(), "test-task", "pit/test-task"));
+        assert!(has_new_commits(repo.path(), 1, "pit/test-task"));
     }
 
     #[test]
@@ -680,10 +1447,109 @@ mod tests {
         assert!(ctx.iter().any(|l| l.contains("Added tests")));
     }
 
+    #[test]
+    fn checkpoints_are_private_refs_removed_with_task() {
+        let repo = make_git_repo();
+        add_commit(repo.path(), "pit/test-task", "work");
+        create(
+            repo.path(),
+            1,
+            "test-task",
+            "pit/test-task",
+            repo.path(),
+            None,
+        )
+        .unwrap();
+        create(repo.path(), 2, "other", "pit/test-task", repo.path(), None).unwrap();
+
+        assert_eq!(git_out(repo.path(), &["tag", "--list"]), "");
+        assert_eq!(
+            list(repo.path(), 1).unwrap()[0].refname,
+            "refs/pit/1/checkpoints/1"
+        );
+
+        remove_all(repo.path(), 1).unwrap();
+        assert!(list(repo.path(), 1).unwrap().is_empty());
+        assert_eq!(list(repo.path(), 2).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn migrate_tags_moves_legacy_checkpoints() {
+        let repo = make_git_repo();
+        let dir = repo.path();
+        let db = crate::db::open_memory().unwrap();
+        let t = crate::core::task::create(
+            &db,
+            dir,
+            &crate::core::task::CreateOpts {
+                name: "mig",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let tag = |name: &str, date: Option<&str>| {
+            let mut cmd = StdCommand::new("git");
+            cmd.args([
+                "tag",
+                "-a",
+                name,
+                "-m",
+                "[pit checkpoint] mig\n\n## Done\n- x",
+            ])
+            .args(["--cleanup=verbatim"])
+            .current_dir(dir);
+            if let Some(d) = date {
+                cmd.env("GIT_COMMITTER_DATE", d);
+            }
+            cmd.output().unwrap();
+        };
+        // Made in the same second as the task: still the task's
+        tag(
+            "pit/checkpoint/mig/1",
+            Some(&format!("{} +0000", t.created_at)),
+        );
+        tag("pit/checkpoint/mig/2", Some("2000-01-01T00:00:00Z"));
+        tag("pit/checkpoint/gone/1", Some("2000-01-01T00:00:00Z"));
+        git_out(dir, &["tag", "pit/pre-rollback/mig"]);
+        git_out(dir, &["tag", "unrelated"]);
+
+        // An older task that had the same name gets the older tag
+        let mut older = t.clone();
+        older.id = t.id + 100;
+        older.created_at = "1999-01-01 00:00:00".to_string();
+        let moved = migrate_tags(dir, &[t.clone(), older.clone()]).unwrap();
+        assert_eq!(moved, 4);
+        assert_eq!(git_out(dir, &["tag", "--list"]), "unrelated");
+
+        let cps = list(dir, t.id).unwrap();
+        assert_eq!(cps.len(), 1);
+        assert_eq!(cps[0].index, 1);
+        assert!(cps[0].annotation.contains("## Done\n- x"));
+        assert_eq!(list(dir, older.id).unwrap().len(), 1);
+        let refs = git_out(dir, &["for-each-ref", "--format=%(refname)", "refs/pit/"]);
+        assert!(refs.contains(&format!("refs/pit/{}/pre-rollback", t.id)));
+        // No task of that name: kept aside
+        assert!(refs.contains("refs/pit/orphaned/gone/checkpoints/1"));
+
+        // With one task of that name, the date doesn't matter
+        git_out(
+            dir,
+            &[
+                "update-ref",
+                "-d",
+                &format!("{}checkpoints/2", task_refs(older.id)),
+            ],
+        );
+        tag("pit/checkpoint/mig/3", Some("2000-01-01T00:00:00Z"));
+        migrate_tags(dir, std::slice::from_ref(&t)).unwrap();
+        assert_eq!(list(dir, t.id).unwrap().len(), 2);
+    }
+
     #[test]
     fn list_empty_returns_empty_vec() {
         let repo = make_git_repo();
-        let checkpoints = list(repo.path(), "nonexistent-task").unwrap();
+        let checkpoints = list(repo.path(), 99).unwrap();
         assert!(checkpoints.is_empty());
     }
 }
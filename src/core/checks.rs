@@ -0,0 +1,224 @@
+//! Test and lint commands run in a task's worktree.
+//!
+//! The commands come from the per-repo `test_command` and `lint_command`
+//! config keys. Lint runs first and a failure stops there. Each run's
+//! pass/fail, duration and a short failure summary are stored on the task.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+use super::config;
+use super::task::{self, Task};
+
+/// Most output lines kept in a failure summary.
+const SUMMARY_LINES: usize = 10;
+
+/// The configured commands; `None` when not set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Commands {
+    pub test: Option<String>,
+    pub lint: Option<String>,
+}
+
+impl Commands {
+    /// Read `test_command` and `lint_command` for a repo. `test.command`,
+    /// which the merge queue used to read, still works in place of
+    /// `test_command`.
+    pub fn from_config(repo_root: &Path) -> Self {
+        let read =
+            |key: &str| config::get_for_repo(repo_root, key).filter(|c| !c.trim().is_empty());
+        Commands {
+            test: read("test_command").or_else(|| read("test.command")),
+            lint: read("lint_command"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.test.is_none() && self.lint.is_none()
+    }
+}
+
+/// Outcome of running the commands once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub passed: bool,
+    pub duration_ms: i64,
+    /// The failing command and the lines of its output that look like
+    /// errors; empty when everything passed.
+    pub summary: String,
+}
+
+/// Run lint, then tests, in `worktree`. With `echo`, output is printed as it
+/// arrives.
+pub fn run(commands: &Commands, worktree: &Path, echo: bool) -> Result<Report> {
+    if commands.is_empty() {
+        bail!("no test_command or lint_command configured (pit config set --repo test_command \"cargo test\")");
+    }
+    let started = Instant::now();
+    let mut summary = String::new();
+    for cmd in [&commands.lint, &commands.test].into_iter().flatten() {
+        let (code, output) = run_one(cmd, worktree, echo)?;
+        if code != Some(0) {
+            summary = summarize(cmd, code, &output);
+            break;
+        }
+    }
+    Ok(Report {
+        passed: summary.is_empty(),
+        duration_ms: started.elapsed().as_millis() as i64,
+        summary,
+    })
+}
+
+/// Store a report on the task.
+pub fn record(db: &Connection, task_id: i64, report: &Report) -> Result<()> {
+    let status = if report.passed { "pass" } else { "fail" };
+    task::set_test(db, task_id, status, report.duration_ms, &report.summary)
+}
+
+/// Run `pit test <name>` in the background and mark the task as being
+/// tested. The dashboard picks up the result from the database.
+pub fn spawn(db: &Connection, repo_root: &Path, task: &Task) -> Result<()> {
+    let exe = std::env::current_exe().context("failed to locate the pit binary")?;
+    Command::new(exe)
+        .args(["test", &task.name, "--quiet"])
+        .current_dir(repo_root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("failed to start pit test")?;
+    task::set_test(db, task.id, "running", 0, "")
+}
+
+/// Run one shell command with stderr folded into stdout. Returns the exit
+/// code (`None` if killed) and the output.
+pub fn run_one(cmd: &str, dir: &Path, echo: bool) -> Result<(Option<i32>, String)> {
+    let mut child = Command::new("sh")
+        .args(["-c", &format!("exec 2>&1; {}", cmd)])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run '{}'", cmd))?;
+    let mut output = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            if echo {
+                println!("{}", line);
+            }
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+    let status = child.wait()?;
+    Ok((status.code(), output))
+}
+
+/// First line names the failing command; then the output lines that look
+/// like errors, or the tail of the output if none do.
+fn summarize(cmd: &str, code: Option<i32>, output: &str) -> String {
+    let head = match code {
+        Some(code) => format!("'{}' failed (exit code {})", cmd, code),
+        None => format!("'{}' was killed", cmd),
+    };
+    let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+    let mut picked: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|l| looks_like_failure(l))
+        .take(SUMMARY_LINES)
+        .collect();
+    if picked.is_empty() {
+        picked = lines[lines.len().saturating_sub(SUMMARY_LINES)..].to_vec();
+    }
+    std::iter::once(head.as_str())
+        .chain(picked)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn looks_like_failure(line: &str) -> bool {
+    let line = line.trim_start();
+    [
+        "error", "Error", "ERROR", "FAIL", "failed", "panicked", "✕", "✗",
+    ]
+    .iter()
+    .any(|marker| line.starts_with(marker) || line.contains(&format!(" {}", marker)))
+}
+
+/// Human-readable duration: "850ms", "12.3s", "2m 05s".
+pub fn format_duration(ms: i64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {:02}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_runs_first_and_stops_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let commands = Commands {
+            lint: Some("echo linting; echo 'error: unused variable' >&2; exit 3".into()),
+            test: Some("touch tested".into()),
+        };
+        let report = run(&commands, dir.path(), false).unwrap();
+        assert!(!report.passed);
+        assert_eq!(
+            report.summary,
+            "'echo linting; echo 'error: unused variable' >&2; exit 3' failed (exit code 3)\n\
+             error: unused variable"
+        );
+        assert!(!dir.path().join("tested").exists());
+
+        let commands = Commands {
+            lint: None,
+            test: Some("touch tested".into()),
+        };
+        let report = run(&commands, dir.path(), false).unwrap();
+        assert!(report.passed && report.summary.is_empty());
+        assert!(dir.path().join("tested").exists());
+        assert!(run(&Commands::default(), dir.path(), false).is_err());
+    }
+
+    #[test]
+    fn summary_falls_back_to_output_tail() {
+        let output: String = (1..=15).map(|i| format!("line {}\n", i)).collect();
+        let summary = summarize("make", Some(2), &output);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[0], "'make' failed (exit code 2)");
+        assert_eq!(lines[1], "line 6");
+        assert_eq!(lines.len(), 1 + SUMMARY_LINES);
+    }
+
+    #[test]
+    fn old_test_command_key_still_works() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".pit")).unwrap();
+        std::fs::write(
+            config::repo_config_path(dir.path()),
+            "[test]\ncommand = \"make check\"\n",
+        )
+        .unwrap();
+        let commands = Commands::from_config(dir.path());
+        assert_eq!(commands.test.as_deref(), Some("make check"));
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(850), "850ms");
+        assert_eq!(format_duration(12_340), "12.3s");
+        assert_eq!(format_duration(125_000), "2m 05s");
+    }
+}
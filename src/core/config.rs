@@ -4,10 +4,12 @@
 //! or `~/.local/share/pit/config.toml` (Linux).
 //!
 //! Values can also be set via environment variables (higher priority).
+//! Per-repo values live in `.pit/config.toml` and override the global file
+//! for lookups through [`get_for_repo`].
 
 use anyhow::{Context, Result};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Get the pit data directory.
 pub fn data_dir() -> PathBuf {
@@ -21,6 +23,11 @@ pub fn config_path() -> PathBuf {
     data_dir().join("config.toml")
 }
 
+/// Get the per-repo config file path.
+pub fn repo_config_path(repo_root: &Path) -> PathBuf {
+    repo_root.join(".pit").join("config.toml")
+}
+
 /// Read a config value. Checks env var first (uppercase, dots→underscores),
 /// then falls back to config file.
 ///
@@ -35,7 +42,7 @@ pub fn get(key: &str) -> Option<String> {
     }
 
     // Fall back to config file
-    let config = load_config().unwrap_or_default();
+    let config = load_file(&config_path());
     // Support dotted keys: "linear.api_key" looks in [linear] section for api_key
     let parts: Vec<&str> = key.splitn(2, '.').collect();
     match parts.as_slice() {
@@ -45,34 +52,63 @@ pub fn get(key: &str) -> Option<String> {
     }
 }
 
+/// Read a config value for a repo: env var first, then the repo's
+/// `.pit/config.toml`, then the global config file.
+pub fn get_for_repo(repo_root: &Path, key: &str) -> Option<String> {
+    let env_key = key.replace('.', "_").to_uppercase();
+    if let Ok(val) = std::env::var(&env_key) {
+        if !val.is_empty() {
+            return Some(val);
+        }
+    }
+    load_file(&repo_config_path(repo_root))
+        .get(key)
+        .cloned()
+        .or_else(|| get(key))
+}
+
 /// Set a config value in the config file.
 pub fn set(key: &str, value: &str) -> Result<()> {
-    let mut config = load_config().unwrap_or_default();
-    config.insert(key.to_string(), value.to_string());
-    save_config(&config)
+    set_in(&config_path(), key, value)
 }
 
 /// Remove a config value from the config file.
 pub fn unset(key: &str) -> Result<()> {
-    let mut config = load_config().unwrap_or_default();
-    config.remove(key);
-    save_config(&config)
+    unset_in(&config_path(), key)
 }
 
 /// List all config values.
 pub fn list() -> HashMap<String, String> {
-    load_config().unwrap_or_default()
+    load_file(&config_path())
 }
 
-/// Load config from file. Simple key=value format (one per line).
+/// Set a config value in a config file at `path`.
+pub fn set_in(path: &Path, key: &str, value: &str) -> Result<()> {
+    let mut config = load_file(path);
+    config.insert(key.to_string(), value.to_string());
+    save_file(path, &config)
+}
+
+/// Remove a config value from a config file at `path`.
+pub fn unset_in(path: &Path, key: &str) -> Result<()> {
+    let mut config = load_file(path);
+    config.remove(key);
+    save_file(path, &config)
+}
+
+/// Load a config file. Simple key=value format (one per line).
 /// Lines starting with # are comments. Section headers [name] prefix subsequent keys.
-fn load_config() -> Result<HashMap<String, String>> {
-    let path = config_path();
-    let content = match std::fs::read_to_string(&path) {
-        Ok(c) => c,
-        Err(_) => return Ok(HashMap::new()),
-    };
+/// A missing file is an empty config.
+pub fn load_file(path: &Path) -> HashMap<String, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => parse(&content),
+        Err(_) => HashMap::new(),
+    }
+}
 
+/// Parse TOML-like `key = "value"` lines with `[section]` headers into a
+/// flat map of dotted keys. Also used for template front matter.
+pub fn parse(content: &str) -> HashMap<String, String> {
     let mut map = HashMap::new();
     let mut section = String::new();
 
@@ -97,13 +133,15 @@ fn load_config() -> Result<HashMap<String, String>> {
         }
     }
 
-    Ok(map)
+    map
 }
 
 /// Save config to file in TOML-like format.
-fn save_config(config: &HashMap<String, String>) -> Result<()> {
-    let dir = data_dir();
-    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+fn save_file(path: &Path, config: &HashMap<String, String>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
 
     // Group by section
     let mut sections: HashMap<String, Vec<(String, String)>> = HashMap::new();
@@ -152,8 +190,7 @@ fn save_config(config: &HashMap<String, String>) -> Result<()> {
         }
     }
 
-    let path = config_path();
-    std::fs::write(&path, output).with_context(|| format!("failed to write {}", path.display()))?;
+    std::fs::write(path, output).with_context(|| format!("failed to write {}", path.display()))?;
 
     Ok(())
 }
@@ -187,6 +224,25 @@ mod tests {
         assert!(val.is_none());
     }
 
+    #[test]
+    fn repo_config_overrides_global() {
+        let dir = tempfile::tempdir().unwrap();
+        env::remove_var("PIT_REPO_ONLY_KEY");
+        assert!(get_for_repo(dir.path(), "pit_repo_only_key").is_none());
+
+        let path = repo_config_path(dir.path());
+        set_in(&path, "pit_repo_only_key", "cargo test").unwrap();
+        set_in(&path, "lint.extra", "x").unwrap();
+        assert_eq!(
+            get_for_repo(dir.path(), "pit_repo_only_key").as_deref(),
+            Some("cargo test")
+        );
+        assert_eq!(load_file(&path).get("lint.extra").unwrap(), "x");
+
+        unset_in(&path, "pit_repo_only_key").unwrap();
+        assert!(get_for_repo(dir.path(), "pit_repo_only_key").is_none());
+    }
+
     #[test]
     fn load_save_roundtrip() {
         let dir = tempfile::tempdir().unwrap();
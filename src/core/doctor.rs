@@ -0,0 +1,860 @@
+//! Health checks for `pit doctor` and cleanup for `pit gc`.
+//!
+//! [`environment`] looks at the tools pit runs and its config files.
+//! [`find`] looks for state that has drifted apart: worktrees and branches
+//! without tasks, tasks without worktrees, leftover tmux sessions, refs of
+//! deleted tasks and large build directories. [`fix`] repairs or prunes one
+//! problem; anything that might hold unrecovered work is left for
+//! `pit repair`.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::config;
+use super::git_info;
+use super::meta;
+use super::task::{self, Status, Task};
+use super::tmux;
+use super::worktrees;
+
+/// Oldest git with everything pit runs: `merge-tree --write-tree` for
+/// conflict prediction (2.38), `merge --autostash` for sync (2.27) and
+/// `worktree remove` (2.17).
+const MIN_GIT: (u32, u32) = (2, 38);
+
+/// Oldest tmux known to work with pit's session config.
+const MIN_TMUX: (u32, u32) = (2, 6);
+
+/// Config keys pit reads. Anything else is probably a typo.
+const KNOWN_KEYS: &[&str] = &[
+    "linear.api_key",
+    "github.token",
+    "github.api_url",
+    "pr.remote",
+    "issues.prompt_limit",
+    "issues.context_limit",
+    "checkpoints.interval",
+    "checkpoints.lines",
+    "checkpoints.keep",
+    "gc.build_dir_limit",
+    "test_command",
+    "lint_command",
+    "harvest_pattern",
+    "worktree_root",
+];
+
+/// Keys that were renamed: (old, new). The old ones still work.
+const RENAMED_KEYS: &[(&str, &str)] = &[("test.command", "test_command")];
+
+/// Keys whose values must be whole numbers.
+const NUMERIC_KEYS: &[&str] = &[
+    "issues.prompt_limit",
+    "issues.context_limit",
+    "checkpoints.interval",
+    "checkpoints.lines",
+    "checkpoints.keep",
+    "gc.build_dir_limit",
+];
+
+/// Git-ignored directories in a worktree that are reported once they pass
+/// the size limit.
+const BUILD_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    ".next",
+    ".venv",
+    "__pycache__",
+    ".gradle",
+];
+
+/// Default size above which a build directory is reported, in MB.
+const DEFAULT_BUILD_DIR_LIMIT_MB: u64 = 1024;
+
+/// How serious a check result is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Ok,
+    Warn,
+    Error,
+}
+
+/// One environment check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub level: Level,
+    pub message: String,
+}
+
+impl Check {
+    fn new(level: Level, message: impl Into<String>) -> Self {
+        Check {
+            level,
+            message: message.into(),
+        }
+    }
+}
+
+/// Check git, tmux, the agents tasks use and the config files.
+pub fn environment(repo_root: &Path, tasks: &[Task]) -> Vec<Check> {
+    let mut checks = vec![
+        tool_check(
+            "git",
+            &["--version"],
+            MIN_GIT,
+            "pit needs it for everything",
+        ),
+        tool_check(
+            "tmux",
+            &["-V"],
+            MIN_TMUX,
+            "agents can't be launched without it",
+        ),
+    ];
+
+    let mut agents: Vec<&str> = vec!["claude"];
+    for t in tasks {
+        if !agents.contains(&t.agent.as_str()) {
+            agents.push(&t.agent);
+        }
+    }
+    for agent in agents.into_iter().filter(|a| *a != "custom") {
+        let users = tasks.iter().filter(|t| t.agent == agent).count();
+        checks.push(match find_in_path(agent) {
+            Some(path) => Check::new(Level::Ok, format!("{}  {}", agent, path.display())),
+            None if users > 0 => Check::new(
+                Level::Warn,
+                format!("{} not found on PATH (used by {} task(s))", agent, users),
+            ),
+            None => Check::new(
+                Level::Warn,
+                format!("{} not found on PATH (the default agent)", agent),
+            ),
+        });
+    }
+
+    for path in [config::config_path(), config::repo_config_path(repo_root)] {
+        checks.extend(config_checks(&path));
+    }
+    checks
+}
+
+/// `name` with `version_args`, at least `min`.
+fn tool_check(name: &str, version_args: &[&str], min: (u32, u32), why: &str) -> Check {
+    let output = Command::new(name).args(version_args).output().ok();
+    let Some(output) = output.filter(|o| o.status.success()) else {
+        return Check::new(Level::Error, format!("{} not found — {}", name, why));
+    };
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    match parse_version(&text) {
+        Some(v) if v < min => Check::new(
+            Level::Error,
+            format!("{} is too old (need {}.{}+)", text, min.0, min.1),
+        ),
+        _ => Check::new(Level::Ok, text),
+    }
+}
+
+/// `git version 2.43.0` → (2, 43); `tmux next-3.4` → (3, 4).
+fn parse_version(text: &str) -> Option<(u32, u32)> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let mut parts = text[start..].split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|m| m.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|p| p.is_file())
+}
+
+/// Problems in one config file; nothing if it doesn't exist.
+fn config_checks(path: &Path) -> Vec<Check> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut checks = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let ok = line.is_empty()
+            || line.starts_with('#')
+            || (line.starts_with('[') && line.ends_with(']'))
+            || line.contains('=');
+        if !ok {
+            checks.push(Check::new(
+                Level::Error,
+                format!("{}:{}: can't parse '{}'", path.display(), i + 1, line),
+            ));
+        }
+    }
+    let mut entries: Vec<(String, String)> = config::parse(&content).into_iter().collect();
+    entries.sort();
+    for (key, value) in entries {
+        if let Some((_, new)) = RENAMED_KEYS.iter().find(|(old, _)| *old == key) {
+            checks.push(Check::new(
+                Level::Warn,
+                format!("{}: '{}' is now '{}'", path.display(), key, new),
+            ));
+        } else if !KNOWN_KEYS.contains(&key.as_str()) {
+            checks.push(Check::new(
+                Level::Warn,
+                format!("{}: unknown key '{}'", path.display(), key),
+            ));
+        } else if NUMERIC_KEYS.contains(&key.as_str()) && value.parse::<u64>().is_err() {
+            checks.push(Check::new(
+                Level::Error,
+                format!(
+                    "{}: '{}' should be a number, not '{}'",
+                    path.display(),
+                    key,
+                    value
+                ),
+            ));
+        }
+    }
+    if checks.is_empty() {
+        checks.push(Check::new(Level::Ok, format!("config  {}", path.display())));
+    }
+    checks
+}
+
+/// State that has drifted apart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// A directory in the worktree root that no task uses.
+    OrphanWorktree { path: PathBuf },
+    /// A `pit/*` branch with no task. `merged` means it has no commits the
+    /// base branch lacks.
+    OrphanBranch { branch: String, merged: bool },
+    /// A task whose worktree is gone. Without its branch there is nothing
+    /// left of it.
+    MissingWorktree {
+        task: String,
+        id: i64,
+        branch_exists: bool,
+    },
+    /// A pit tmux session that belongs to no task.
+    StaleSession { session: String },
+    /// Checkpoint and metadata refs of a deleted task, or tags from the
+    /// old checkpoint layout.
+    StaleRefs { owner: String, refs: Vec<String> },
+    /// Refs of a task that isn't in the DB but still has a branch or
+    /// archive: `pit repair` can bring it back.
+    Unrecovered { task: String },
+    /// A large git-ignored build directory in a task's worktree.
+    BuildDir {
+        task: String,
+        path: PathBuf,
+        bytes: u64,
+        running: bool,
+    },
+}
+
+impl Problem {
+    /// One line for `pit doctor`.
+    pub fn describe(&self, repo_root: &Path) -> String {
+        match self {
+            Problem::OrphanWorktree { path } => {
+                format!("orphaned worktree {} (no task)", relative(repo_root, path))
+            }
+            Problem::OrphanBranch {
+                branch,
+                merged: true,
+            } => {
+                format!("branch {} has no task (already merged)", branch)
+            }
+            Problem::OrphanBranch { branch, .. } => {
+                format!("branch {} has no task and unmerged commits", branch)
+            }
+            Problem::MissingWorktree {
+                task,
+                branch_exists: true,
+                ..
+            } => format!("task '{}' has lost its worktree", task),
+            Problem::MissingWorktree { task, .. } => {
+                format!("task '{}' has lost its worktree and branch", task)
+            }
+            Problem::StaleSession { session } => {
+                format!("tmux session {} belongs to no task", session)
+            }
+            Problem::StaleRefs { owner, refs } => {
+                format!("{} stale ref(s) of {}", refs.len(), owner)
+            }
+            Problem::Unrecovered { task } => {
+                format!("task '{}' is in git but not in the database", task)
+            }
+            Problem::BuildDir {
+                task, path, bytes, ..
+            } => format!(
+                "{} in '{}' is {}",
+                relative(repo_root, path),
+                task,
+                human_size(*bytes)
+            ),
+        }
+    }
+}
+
+/// Look for drifted state. Build directories over `build_dir_limit` bytes
+/// are reported.
+pub fn find(db: &Connection, repo_root: &Path, build_dir_limit: u64) -> Result<Vec<Problem>> {
+    let tasks = task::list(db)?;
+    let mut all = tasks.clone();
+    all.extend(task::list_archived(db)?);
+    let mut problems = Vec::new();
+
+    // Worktrees nobody owns. A root outside `.pit` may be shared with other
+    // repos, so only this repo's worktrees count there.
+    let roots = worktrees::roots(repo_root);
+    let linked = git(repo_root, &["worktree", "list", "--porcelain"])?;
+    let linked: Vec<PathBuf> = linked
+        .lines()
+        .filter_map(|l| l.strip_prefix("worktree "))
+        .map(PathBuf::from)
+        .collect();
+    for root in &roots {
+        let Ok(entries) = std::fs::read_dir(root) else {
+            continue;
+        };
+        let shared = !root.starts_with(repo_root.join(".pit"));
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .filter(|p| !shared || linked.iter().any(|l| same_path(l, p)))
+            .collect();
+        dirs.sort();
+        for path in dirs {
+            if !all.iter().any(|t| same_path(Path::new(&t.worktree), &path)) {
+                problems.push(Problem::OrphanWorktree { path });
+            }
+        }
+    }
+
+    // Branches nobody owns. Those with metadata or an archive are for
+    // `pit repair`, reported below.
+    let metas = meta::load_all(repo_root)?;
+    let archived_refs = git(
+        repo_root,
+        &[
+            "for-each-ref",
+            "--format=%(refname:strip=3)",
+            "refs/pit/archive/",
+        ],
+    )?;
+    let base = git_info::detect_main_branch(repo_root).ok();
+    let branches = git(
+        repo_root,
+        &[
+            "for-each-ref",
+            "--format=%(refname:strip=2)",
+            "refs/heads/pit/",
+        ],
+    )?;
+    let mut unrecovered: Vec<String> = Vec::new();
+    for branch in branches.lines().filter(|b| !b.is_empty()) {
+        if all.iter().any(|t| t.branch == branch) {
+            continue;
+        }
+        if let Some(m) = metas.iter().find(|m| m.branch == branch) {
+            unrecovered.push(m.name.clone());
+            continue;
+        }
+        let merged = base.as_deref().is_some_and(|base| {
+            git(
+                repo_root,
+                &["rev-list", "--count", &format!("{}..{}", base, branch)],
+            )
+            .map(|n| n == "0")
+            .unwrap_or(false)
+        });
+        problems.push(Problem::OrphanBranch {
+            branch: branch.to_string(),
+            merged,
+        });
+    }
+    for name in archived_refs.lines().filter(|n| !n.is_empty()) {
+        if !all.iter().any(|t| t.name == name) && !unrecovered.iter().any(|u| u == name) {
+            unrecovered.push(name.to_string());
+        }
+    }
+
+    // Tasks whose worktree is gone
+    for t in &tasks {
+        if !Path::new(&t.worktree).exists() {
+            let branch_exists = git(
+                repo_root,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/heads/{}", t.branch),
+                ],
+            )
+            .is_ok();
+            problems.push(Problem::MissingWorktree {
+                task: t.name.clone(),
+                id: t.id,
+                branch_exists,
+            });
+        }
+    }
+
+    // tmux sessions nobody owns. The socket is shared by all repos, so only
+    // sessions started in this one or its worktrees count.
+    for (session, path) in tmux::list_sessions_with_paths().unwrap_or_default() {
+        if !path.starts_with(repo_root) && !roots.iter().any(|r| path.starts_with(r)) {
+            continue;
+        }
+        let owned = tasks.iter().any(|t| {
+            session == tmux::session_name(&t.name) || session == tmux::shell_session_name(&t.name)
+        });
+        if !owned {
+            problems.push(Problem::StaleSession { session });
+        }
+    }
+
+    // Refs of deleted tasks, and old-style checkpoint tags
+    let refs = git(
+        repo_root,
+        &[
+            "for-each-ref",
+            "--format=%(refname)",
+            "refs/pit/",
+            "refs/tags/pit/",
+        ],
+    )?;
+    let mut stale: Vec<(String, Vec<String>)> = Vec::new();
+    for refname in refs.lines().filter(|r| !r.is_empty()) {
+        let owner = if let Some(rest) = refname.strip_prefix("refs/pit/") {
+            let first = rest.split('/').next().unwrap_or("");
+            match first.parse::<i64>() {
+                Ok(id) if all.iter().any(|t| t.id == id) => continue,
+                Ok(id) => match metas.iter().find(|m| m.id == id) {
+                    Some(m) if unrecovered.contains(&m.name) => continue,
+                    Some(m) => format!("deleted task '{}'", m.name),
+                    None => format!("deleted task #{}", id),
+                },
+                Err(_) if first == "orphaned" => "tasks deleted before checkpoint refs".to_string(),
+                Err(_) => continue,
+            }
+        } else {
+            "the old checkpoint tags".to_string()
+        };
+        match stale.iter_mut().find(|(o, _)| *o == owner) {
+            Some((_, refs)) => refs.push(refname.to_string()),
+            None => stale.push((owner, vec![refname.to_string()])),
+        }
+    }
+    for (owner, refs) in stale {
+        problems.push(Problem::StaleRefs { owner, refs });
+    }
+    for task in unrecovered {
+        problems.push(Problem::Unrecovered { task });
+    }
+
+    // Big build directories
+    for t in &tasks {
+        let wt = Path::new(&t.worktree);
+        for name in BUILD_DIRS {
+            let path = wt.join(name);
+            if !path.is_dir() || git(wt, &["check-ignore", "-q", name]).is_err() {
+                continue;
+            }
+            let bytes = dir_size(&path);
+            if bytes > build_dir_limit {
+                problems.push(Problem::BuildDir {
+                    task: t.name.clone(),
+                    path,
+                    bytes,
+                    running: t.status == Status::Running,
+                });
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// The repo's `gc.build_dir_limit` (in MB), in bytes.
+pub fn build_dir_limit(repo_root: &Path) -> u64 {
+    config::get_for_repo(repo_root, "gc.build_dir_limit")
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_BUILD_DIR_LIMIT_MB)
+        * 1024
+        * 1024
+}
+
+/// What `fix` does, or why it leaves the problem alone, as an imperative
+/// ("remove …").
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Fixed(String),
+    Skipped(String),
+}
+
+/// Repair or prune one problem. With `dry_run`, only say what would happen.
+pub fn fix(db: &Connection, repo_root: &Path, problem: &Problem, dry_run: bool) -> Result<Outcome> {
+    let run = |args: &[&str]| -> Result<()> {
+        if !dry_run {
+            git(repo_root, args)?;
+        }
+        Ok(())
+    };
+    let outcome = match problem {
+        Problem::OrphanWorktree { path } => {
+            let shown = relative(repo_root, path);
+            if !path.join(".git").exists() {
+                // Not a worktree: only an empty leftover directory goes
+                let empty = std::fs::read_dir(path)
+                    .map(|mut d| d.next().is_none())
+                    .unwrap_or(false);
+                if !empty {
+                    return Ok(Outcome::Skipped(format!(
+                        "leave {}: not a worktree, and not empty",
+                        shown
+                    )));
+                }
+                if !dry_run {
+                    std::fs::remove_dir(path)
+                        .with_context(|| format!("failed to remove {}", path.display()))?;
+                }
+                return Ok(Outcome::Fixed(format!(
+                    "remove orphaned worktree {}",
+                    shown
+                )));
+            }
+            let branch = git(path, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok();
+            if let Some(m) = branch.as_ref().and_then(|b| {
+                meta::load_all(repo_root)
+                    .ok()?
+                    .into_iter()
+                    .find(|m| &m.branch == b)
+            }) {
+                return Ok(Outcome::Skipped(format!(
+                    "leave {}: it belongs to '{}' (bring it back with pit repair)",
+                    shown, m.name
+                )));
+            }
+            if !git(path, &["status", "--porcelain"])?.is_empty() {
+                return Ok(Outcome::Skipped(format!(
+                    "leave {}: it has uncommitted changes",
+                    shown
+                )));
+            }
+            if !dry_run {
+                git(repo_root, &["worktree", "remove", &path.to_string_lossy()])?;
+            }
+            Outcome::Fixed(format!("remove orphaned worktree {}", shown))
+        }
+        Problem::OrphanBranch {
+            branch,
+            merged: true,
+        } => {
+            run(&["branch", "-D", branch])?;
+            Outcome::Fixed(format!("delete merged branch {}", branch))
+        }
+        Problem::OrphanBranch { branch, .. } => Outcome::Skipped(format!(
+            "leave {}: it has unmerged commits (adopt it with: pit adopt {})",
+            branch, branch
+        )),
+        Problem::MissingWorktree {
+            task: name,
+            id,
+            branch_exists: true,
+        } => {
+            let t = task::get(db, *id)?.context("task not found")?;
+            if !dry_run {
+                let _ = git(repo_root, &["worktree", "prune"]);
+                git(repo_root, &["worktree", "add", &t.worktree, &t.branch])?;
+            }
+            Outcome::Fixed(format!("re-add the worktree of '{}'", name))
+        }
+        Problem::MissingWorktree { task: name, id, .. } => {
+            if !dry_run {
+                task::delete(db, repo_root, *id)?;
+            }
+            Outcome::Fixed(format!("delete task '{}' (nothing left of it)", name))
+        }
+        Problem::StaleSession { session } => {
+            if !dry_run {
+                tmux::kill_session(session)?;
+            }
+            Outcome::Fixed(format!("kill tmux session {}", session))
+        }
+        Problem::StaleRefs { owner, refs } => {
+            for refname in refs {
+                run(&["update-ref", "-d", refname])?;
+            }
+            Outcome::Fixed(format!("delete {} ref(s) of {}", refs.len(), owner))
+        }
+        Problem::Unrecovered { task } => {
+            Outcome::Skipped(format!("leave '{}': bring it back with pit repair", task))
+        }
+        Problem::BuildDir {
+            task, path, bytes, ..
+        } if matches!(problem, Problem::BuildDir { running: true, .. }) => {
+            Outcome::Skipped(format!(
+                "leave {} ({}): '{}' is running",
+                relative(repo_root, path),
+                human_size(*bytes),
+                task
+            ))
+        }
+        Problem::BuildDir { path, bytes, .. } => {
+            if !dry_run {
+                std::fs::remove_dir_all(path)
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+            }
+            Outcome::Fixed(format!(
+                "remove {} ({})",
+                relative(repo_root, path),
+                human_size(*bytes)
+            ))
+        }
+    };
+    Ok(outcome)
+}
+
+/// Disk usage of each task's worktree, largest first.
+pub fn worktree_usage(tasks: &[Task]) -> Vec<(String, u64)> {
+    let mut usage: Vec<(String, u64)> = tasks
+        .iter()
+        .filter(|t| Path::new(&t.worktree).exists())
+        .map(|t| (t.name.clone(), dir_size(Path::new(&t.worktree))))
+        .collect();
+    usage.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    usage
+}
+
+/// Total size of the files under `path`, without following symlinks.
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| dir_size(&e.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// `1536` → `1.5 KB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn relative(repo_root: &Path, path: &Path) -> String {
+    path.strip_prefix(repo_root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::checkpoint;
+    use task::CreateOpts;
+
+    fn setup() -> (tempfile::TempDir, Connection) {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@test.com"],
+            &["config", "user.name", "Test"],
+            &["commit", "--allow-empty", "-m", "init"],
+            &["branch", "-M", "main"],
+        ] {
+            git(dir.path(), args).unwrap();
+        }
+        std::fs::write(dir.path().join(".gitignore"), ".pit\ntarget\n").unwrap();
+        git(dir.path(), &["add", "."]).unwrap();
+        git(dir.path(), &["commit", "-q", "-m", "ignore"]).unwrap();
+        (dir, crate::db::open_memory().unwrap())
+    }
+
+    fn new_task(db: &Connection, repo: &Path, name: &str) -> Task {
+        task::create(
+            db,
+            repo,
+            &CreateOpts {
+                name,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_and_fixes_drifted_state() {
+        let (dir, db) = setup();
+        let repo = dir.path();
+        let lost = new_task(&db, repo, "lost");
+        let built = new_task(&db, repo, "built");
+        let deleted = new_task(&db, repo, "deleted");
+        checkpoint::create(
+            repo,
+            deleted.id,
+            "deleted",
+            &deleted.branch,
+            Path::new(&deleted.worktree),
+            None,
+        )
+        .unwrap();
+        // Row gone, branch and refs left behind
+        db.execute("DELETE FROM tasks WHERE id = ?1", [deleted.id])
+            .unwrap();
+        git(repo, &["update-ref", "-d", &meta::meta_ref(deleted.id)]).unwrap();
+        git(repo, &["branch", "pit/stray"]).unwrap();
+        std::fs::create_dir_all(repo.join(".pit/worktrees/junk")).unwrap();
+        git(repo, &["worktree", "remove", "--force", &lost.worktree]).unwrap();
+        let target = Path::new(&built.worktree).join("target");
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("big.o"), vec![0u8; 4096]).unwrap();
+
+        let problems = find(&db, repo, 1024).unwrap();
+        let has = |f: &dyn Fn(&Problem) -> bool| problems.iter().any(f);
+        assert!(has(
+            &|p| matches!(p, Problem::OrphanWorktree { path } if path.ends_with("junk"))
+        ));
+        assert!(has(
+            &|p| matches!(p, Problem::OrphanWorktree { path } if path.ends_with("deleted"))
+        ));
+        assert!(has(&|p| *p
+            == Problem::OrphanBranch {
+                branch: "pit/stray".into(),
+                merged: true
+            }));
+        assert!(has(
+            &|p| matches!(p, Problem::MissingWorktree { task, branch_exists: true, .. } if task == "lost")
+        ));
+        assert!(has(
+            &|p| matches!(p, Problem::StaleRefs { owner, refs } if owner == &format!("deleted task #{}", deleted.id) && refs.len() == 1)
+        ));
+        assert!(has(
+            &|p| matches!(p, Problem::BuildDir { task, bytes: 4096, .. } if task == "built")
+        ));
+        assert!(find(&db, repo, 1 << 20)
+            .unwrap()
+            .iter()
+            .all(|p| !matches!(p, Problem::BuildDir { .. })));
+
+        // A dry run changes nothing
+        for p in &problems {
+            fix(&db, repo, p, true).unwrap();
+        }
+        assert_eq!(find(&db, repo, 1024).unwrap(), problems);
+
+        for p in &problems {
+            fix(&db, repo, p, false).unwrap();
+        }
+        assert_eq!(find(&db, repo, 1024).unwrap(), vec![]);
+        assert!(Path::new(&lost.worktree).exists());
+        assert!(!target.exists());
+        assert!(!repo.join(".pit/worktrees/junk").exists());
+    }
+
+    #[test]
+    fn orphaned_worktrees_with_work_are_left_alone() {
+        let (dir, db) = setup();
+        let repo = dir.path();
+        let lost = new_task(&db, repo, "lost");
+        let dirty = new_task(&db, repo, "dirty");
+        std::fs::write(Path::new(&dirty.worktree).join("wip.txt"), "wip").unwrap();
+        // The DB lost both rows; only "lost" still has metadata in git
+        db.execute("DELETE FROM tasks", []).unwrap();
+        git(repo, &["update-ref", "-d", &meta::meta_ref(dirty.id)]).unwrap();
+        std::fs::create_dir_all(repo.join(".pit/worktrees/notes")).unwrap();
+        std::fs::write(repo.join(".pit/worktrees/notes/todo.txt"), "x").unwrap();
+
+        let orphans: Vec<Problem> = find(&db, repo, u64::MAX)
+            .unwrap()
+            .into_iter()
+            .filter(|p| matches!(p, Problem::OrphanWorktree { .. }))
+            .collect();
+        assert_eq!(orphans.len(), 3);
+        for p in &orphans {
+            assert!(matches!(
+                fix(&db, repo, p, false).unwrap(),
+                Outcome::Skipped(_)
+            ));
+        }
+        assert!(Path::new(&lost.worktree).exists());
+        assert!(Path::new(&dirty.worktree).join("wip.txt").exists());
+        assert!(repo.join(".pit/worktrees/notes/todo.txt").exists());
+    }
+
+    #[test]
+    fn checks_config_files_and_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "test_command = \"cargo test\"\n[checkpoints]\ninterval = \"soon\"\n[github]\ntokne = \"x\"\nnonsense\n",
+        )
+        .unwrap();
+        let checks = config_checks(&path);
+        let messages: Vec<(Level, &str)> = checks
+            .iter()
+            .map(|c| (c.level, c.message.as_str()))
+            .collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages[0].1.ends_with(":6: can't parse 'nonsense'"));
+        assert_eq!(messages[0].0, Level::Error);
+        assert!(messages[1]
+            .1
+            .contains("'checkpoints.interval' should be a number"));
+        assert_eq!(messages[2], (Level::Warn, messages[2].1));
+        assert!(messages[2].1.contains("unknown key 'github.tokne'"));
+        assert!(config_checks(&dir.path().join("missing.toml")).is_empty());
+
+        assert_eq!(parse_version("git version 2.43.0"), Some((2, 43)));
+        assert_eq!(parse_version("tmux next-3.4"), Some((3, 4)));
+        assert_eq!(parse_version("tmux 3.3a"), Some((3, 3)));
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KB");
+        assert_eq!(human_size(3 << 30), "3.0 GB");
+    }
+}
@@ -0,0 +1,66 @@
+//! Open text in the user's editor (`$VISUAL`, then `$EDITOR`, then `vi`).
+
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+
+/// The editor command to run.
+pub fn command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|v| std::env::var(v).ok())
+        .find(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Write `initial` to a temp file, open it in the editor and return the
+/// saved contents. `extension` picks the file type (e.g. "md") so the
+/// editor can highlight it.
+pub fn edit(initial: &str, extension: &str) -> Result<String> {
+    let path = std::env::temp_dir().join(format!(
+        "pit-{}.{}",
+        uuid::Uuid::new_v4().simple(),
+        extension
+    ));
+    std::fs::write(&path, initial)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    // Run through the shell so editors with arguments ("code --wait") work.
+    let editor = command();
+    let status = Command::new("sh")
+        .args(["-c", &format!("{} \"$1\"", editor), "sh"])
+        .arg(&path)
+        .status()
+        .with_context(|| format!("failed to run editor '{}'", editor));
+
+    let result = match status {
+        Ok(s) if s.success() => std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display())),
+        Ok(s) => Err(anyhow::anyhow!("editor '{}' exited with {}", editor, s)),
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Fail early with a clear message when there's no terminal to edit in.
+pub fn ensure_interactive() -> Result<()> {
+    use std::io::IsTerminal;
+    if !std::io::stdin().is_terminal() {
+        bail!("no terminal for the editor — pass the fields as flags instead");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_returns_saved_text() {
+        // A non-interactive "editor" that rewrites the file.
+        std::env::set_var("VISUAL", "sh -c 'echo new text > \"$0\"'");
+        let out = edit("old text\n", "md").unwrap();
+        std::env::remove_var("VISUAL");
+        assert_eq!(out, "new text\n");
+    }
+}
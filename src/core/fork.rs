@@ -0,0 +1,176 @@
+//! Fork a task into a new one that starts from the same state.
+//!
+//! The fork starts from a checkpoint, or from the source task's current
+//! state: its branch tip plus any uncommitted work, which is carried over
+//! uncommitted. The prompt, agent, model and issue link are copied and the
+//! parent is recorded for the dashboard's lineage view.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::path::Path;
+use std::process::Command;
+
+use super::checkpoint;
+use super::task::{self, CreateOpts, Task};
+
+/// Create `name` from `source`, at checkpoint `at` or the current state.
+pub fn fork(
+    db: &Connection,
+    repo_root: &Path,
+    source: &Task,
+    name: &str,
+    at: Option<usize>,
+) -> Result<Task> {
+    let source_worktree = Path::new(&source.worktree);
+    let (rev, fork_point) = match at {
+        Some(index) => {
+            let cp = checkpoint::get(repo_root, source.id, index)?;
+            (cp.refname, format!("checkpoint #{}", index))
+        }
+        None => {
+            let tip = short_hash(repo_root, &source.branch)?;
+            let snapshot = if source_worktree.exists() {
+                checkpoint::snapshot(source_worktree, &source.name)?
+            } else {
+                None
+            };
+            match snapshot {
+                Some(commit) => (commit, format!("{} + uncommitted work", tip)),
+                None => (source.branch.clone(), tip),
+            }
+        }
+    };
+
+    let t = task::create(
+        db,
+        repo_root,
+        &CreateOpts {
+            name,
+            description: &source.description,
+            prompt: &source.prompt,
+            issue_url: &source.issue_url,
+            agent: &source.agent,
+            model: &source.model,
+        },
+    )?;
+    if let Err(e) = checkpoint::restore(Path::new(&t.worktree), &rev) {
+        let _ = task::delete(db, repo_root, t.id);
+        return Err(e.context(format!("failed to start '{}' from {}", name, fork_point)));
+    }
+    task::set_parent(db, t.id, source.id, &fork_point)?;
+    task::get(db, t.id)?.context("task disappeared after fork")
+}
+
+/// The source task's forks, oldest first.
+pub fn children<'a>(task: &Task, tasks: &'a [Task]) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|t| t.parent_id == Some(task.id))
+        .collect()
+}
+
+fn short_hash(repo_root: &Path, rev: &str) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", rev])
+        .current_dir(repo_root)
+        .output()
+        .context("failed to run git rev-parse")?;
+    if !output.status.success() {
+        bail!("branch '{}' not found", rev);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn setup() -> (tempfile::TempDir, Connection, Task) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git(repo, &["init", "-q"]);
+        git(repo, &["config", "user.email", "test@test.com"]);
+        git(repo, &["config", "user.name", "Test"]);
+        git(repo, &["commit", "--allow-empty", "-m", "init"]);
+        git(repo, &["branch", "-M", "main"]);
+        let db = crate::db::open_memory().unwrap();
+        let source = task::create(
+            &db,
+            repo,
+            &CreateOpts {
+                name: "source",
+                prompt: "build the parser",
+                issue_url: "https://github.com/o/r/issues/1",
+                agent: "codex",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (dir, db, source)
+    }
+
+    #[test]
+    fn fork_carries_over_committed_and_uncommitted_work() {
+        let (dir, db, source) = setup();
+        let wt = Path::new(&source.worktree);
+        std::fs::write(wt.join("lexer.rs"), "lexer").unwrap();
+        git(wt, &["add", "."]);
+        git(wt, &["commit", "-m", "lexer"]);
+        std::fs::write(wt.join("parser.rs"), "wip").unwrap();
+
+        let f = fork(&db, dir.path(), &source, "alt", None).unwrap();
+        assert_eq!(f.parent_id, Some(source.id));
+        assert!(f.fork_point.ends_with("+ uncommitted work"));
+        assert_eq!(f.prompt, "build the parser");
+        assert_eq!(f.agent, "codex");
+        assert_eq!(f.issue_url, source.issue_url);
+        let fwt = Path::new(&f.worktree);
+        assert_eq!(git(fwt, &["log", "-1", "--format=%s"]), "lexer");
+        assert_eq!(
+            std::fs::read_to_string(fwt.join("parser.rs")).unwrap(),
+            "wip"
+        );
+        // The source is left as it was
+        assert_eq!(git(wt, &["status", "--porcelain"]), "?? parser.rs");
+        let tasks = task::list(&db).unwrap();
+        let kids: Vec<&str> = children(&source, &tasks)
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(kids, vec!["alt"]);
+
+        // Deleting the parent keeps the fork
+        task::delete(&db, dir.path(), source.id).unwrap();
+        assert_eq!(task::get(&db, f.id).unwrap().unwrap().parent_id, None);
+    }
+
+    #[test]
+    fn fork_from_checkpoint() {
+        let (dir, db, source) = setup();
+        let wt = Path::new(&source.worktree);
+        std::fs::write(wt.join("a.txt"), "first").unwrap();
+        checkpoint::create(dir.path(), source.id, "source", &source.branch, wt, None).unwrap();
+        std::fs::write(wt.join("a.txt"), "second").unwrap();
+        git(wt, &["add", "."]);
+        git(wt, &["commit", "-m", "second"]);
+
+        let f = fork(&db, dir.path(), &source, "retry", Some(1)).unwrap();
+        assert_eq!(f.fork_point, "checkpoint #1");
+        let fwt = Path::new(&f.worktree);
+        assert_eq!(std::fs::read_to_string(fwt.join("a.txt")).unwrap(), "first");
+        assert_eq!(git(fwt, &["log", "-1", "--format=%s"]), "init");
+
+        assert!(fork(&db, dir.path(), &source, "nope", Some(9)).is_err());
+        assert!(task::get_by_name(&db, "nope").unwrap().is_none());
+    }
+}
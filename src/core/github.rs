@@ -7,6 +7,8 @@
 
 use anyhow::{bail, Context, Result};
 
+use super::issues::{summarize, Comment, IssueContext, Limits, LinkedIssue};
+
 /// Parsed issue data from GitHub.
 #[derive(Debug, Clone)]
 pub struct GitHubIssue {
@@ -112,11 +114,117 @@ pub fn fetch_from_url(url: &str) -> Result<GitHubIssue> {
     fetch_issue(&issue_ref)
 }
 
-/// Build a prompt from a GitHub issue.
+/// Fetch comments and referenced issues for an already-fetched issue.
+/// Best-effort: any API failure just leaves that part empty.
+pub fn fetch_context(issue_ref: &GitHubIssueRef, issue: &GitHubIssue) -> IssueContext {
+    let comments = api_get(&format!(
+        "https://api.github.com/repos/{}/{}/issues/{}/comments?per_page=100",
+        issue_ref.owner, issue_ref.repo, issue_ref.number
+    ))
+    .map(|v| parse_comments(&v))
+    .unwrap_or_default();
+
+    let mut links: Vec<LinkedIssue> = api_get(&format!(
+        "https://api.github.com/repos/{}/{}/issues/{}/sub_issues",
+        issue_ref.owner, issue_ref.repo, issue_ref.number
+    ))
+    .map(|v| parse_sub_issues(&v))
+    .unwrap_or_default();
+
+    let mut texts = vec![issue.body.as_str()];
+    texts.extend(comments.iter().map(|c| c.body.as_str()));
+    for r in texts.iter().flat_map(|t| extract_references(t)) {
+        if r == issue.number || links.iter().any(|l| l.identifier == format!("#{}", r)) {
+            continue;
+        }
+        links.push(LinkedIssue {
+            relation: "mentions".to_string(),
+            identifier: format!("#{}", r),
+            title: String::new(),
+            state: String::new(),
+        });
+    }
+
+    IssueContext {
+        labels: issue.labels.clone(),
+        comments,
+        links,
+        attachments: Vec::new(),
+    }
+}
+
+fn api_get(url: &str) -> Result<serde_json::Value> {
+    let mut req = ureq::get(url)
+        .set("User-Agent", "pit-cli")
+        .set("Accept", "application/vnd.github+json");
+    if let Some(token) = super::config::get("github.token") {
+        req = req.set("Authorization", &format!("Bearer {}", token));
+    }
+    let resp = req.call().context("failed to call GitHub API")?;
+    resp.into_json().context("failed to parse GitHub response")
+}
+
+/// Parse the `/issues/N/comments` response.
+pub fn parse_comments(body: &serde_json::Value) -> Vec<Comment> {
+    body.as_array()
+        .map(|arr| {
+            arr.iter()
+                .map(|c| Comment {
+                    author: c["user"]["login"].as_str().unwrap_or("unknown").to_string(),
+                    body: c["body"].as_str().unwrap_or("").to_string(),
+                    created_at: c["created_at"].as_str().unwrap_or("").to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_sub_issues(body: &serde_json::Value) -> Vec<LinkedIssue> {
+    body.as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|i| {
+                    Some(LinkedIssue {
+                        relation: "sub-issue".to_string(),
+                        identifier: format!("#{}", i["number"].as_u64()?),
+                        title: i["title"].as_str().unwrap_or("").to_string(),
+                        state: i["state"].as_str().unwrap_or("").to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Find same-repo issue references (`#123`) in markdown text, in order,
+/// without duplicates.
+pub fn extract_references(text: &str) -> Vec<u64> {
+    let mut refs = Vec::new();
+    let bytes = text.as_bytes();
+    for (i, _) in text.match_indices('#') {
+        // Skip things like "abc#1" (URL fragments) and markdown headings.
+        if i > 0 && bytes[i - 1].is_ascii_alphanumeric() {
+            continue;
+        }
+        let digits: String = text[i + 1..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if let Ok(n) = digits.parse::<u64>() {
+            if !refs.contains(&n) {
+                refs.push(n);
+            }
+        }
+    }
+    refs
+}
+
+/// Build a prompt from a GitHub issue. Long bodies are summarised to the
+/// configured `issues.prompt_limit`.
 pub fn issue_to_prompt(issue: &GitHubIssue) -> String {
     let mut prompt = format!("#{}: {}", issue.number, issue.title);
     if !issue.body.is_empty() {
-        let body: String = issue.body.chars().take(2000).collect();
+        let (body, _) = summarize(&issue.body, Limits::from_config().prompt);
         prompt.push_str(&format!("\n\n{}", body));
     }
     prompt
@@ -215,4 +323,23 @@ mod tests {
         let prompt = issue_to_prompt(&issue);
         assert!(prompt.len() < 2100);
     }
+
+    #[test]
+    fn parse_comments_from_api_response() {
+        let v = serde_json::json!([
+            {"user": {"login": "alice"}, "body": "Repro attached.", "created_at": "2025-01-02T03:04:05Z"},
+            {"user": null, "body": "+1", "created_at": "2025-01-03T00:00:00Z"}
+        ]);
+        let comments = parse_comments(&v);
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].author, "alice");
+        assert_eq!(comments[0].body, "Repro attached.");
+        assert_eq!(comments[1].author, "unknown");
+    }
+
+    #[test]
+    fn extract_references_finds_issue_numbers() {
+        let text = "Blocked by #12, see also #3 and #12.\n## Heading\nurl#9 is not a ref";
+        assert_eq!(extract_references(text), vec![12, 3]);
+    }
 }
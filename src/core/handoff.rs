@@ -0,0 +1,231 @@
+//! Handoff briefs — hand a task's worktree from one agent to another.
+//!
+//! A new agent can't resume another agent's session, so instead it gets a
+//! brief: the original prompt, what was done so far (from checkpoint
+//! annotations), the current diff and the tail of the previous agent's
+//! output.
+
+use std::path::Path;
+use std::process::Command;
+
+use super::checkpoint;
+use super::task::Task;
+
+const TITLE: &str = "# Handoff brief";
+const ORIGINAL: &str = "## Original task";
+const PROGRESS: &str = "## Progress so far";
+const CHANGES: &str = "## Current changes";
+const OUTPUT: &str = "## Last output";
+
+/// How many of the most recent checkpoints to include.
+const MAX_CHECKPOINTS: usize = 5;
+
+/// Build the handoff prompt for `task`, which is moving to `new_agent`.
+/// `transcript` is the previous agent's recent terminal output, if any.
+pub fn build(repo_root: &Path, task: &Task, new_agent: &str, transcript: Option<&str>) -> String {
+    let mut out = vec![
+        TITLE.to_string(),
+        String::new(),
+        format!(
+            "You ({}) are taking over this task from another coding agent ({}). \
+             The work so far is in this worktree on branch `{}`. Review the current \
+             state of the code before changing anything, then carry on with the task.",
+            new_agent, task.agent, task.branch
+        ),
+        String::new(),
+        ORIGINAL.to_string(),
+        String::new(),
+    ];
+    let original = original_prompt(&task.prompt);
+    out.push(if original.is_empty() {
+        "_No prompt was given._".to_string()
+    } else {
+        original
+    });
+    out.push(String::new());
+
+    let checkpoints = checkpoint::list(repo_root, task.id).unwrap_or_default();
+    if !checkpoints.is_empty() {
+        out.push(PROGRESS.to_string());
+        out.push(String::new());
+        let skip = checkpoints.len().saturating_sub(MAX_CHECKPOINTS);
+        for cp in &checkpoints[skip..] {
+            out.push(format!("### Checkpoint #{} ({})", cp.index, cp.timestamp));
+            out.push(String::new());
+            out.push(demote_headings(strip_title(&cp.annotation)));
+            out.push(String::new());
+        }
+    }
+
+    let stat = diff_stat(repo_root, Path::new(&task.worktree));
+    if !stat.is_empty() {
+        out.push(format!("{} (vs base branch)", CHANGES));
+        out.push(String::new());
+        out.push("```".to_string());
+        out.push(stat);
+        out.push("```".to_string());
+        out.push(String::new());
+    }
+
+    if let Some(t) = transcript {
+        let tail = checkpoint::extract_agent_context(t);
+        if !tail.is_empty() {
+            out.push(format!("{} from {}", OUTPUT, task.agent));
+            out.push(String::new());
+            out.push("```".to_string());
+            out.extend(tail);
+            out.push("```".to_string());
+            out.push(String::new());
+        }
+    }
+
+    out.join("\n").trim_end().to_string()
+}
+
+/// The task's original prompt. If `prompt` is itself a handoff brief (the
+/// task was already reassigned), unwrap it so briefs don't nest.
+pub fn original_prompt(prompt: &str) -> String {
+    if !prompt.starts_with(TITLE) {
+        return prompt.trim().to_string();
+    }
+    let Some(start) = prompt.find(&format!("\n{}\n", ORIGINAL)) else {
+        return prompt.trim().to_string();
+    };
+    let body = &prompt[start + ORIGINAL.len() + 2..];
+    let end = body
+        .match_indices('\n')
+        .map(|(i, _)| i + 1)
+        .find(|&i| {
+            let line = body[i..].lines().next().unwrap_or("");
+            line == PROGRESS || line.starts_with(CHANGES) || line.starts_with(OUTPUT)
+        })
+        .unwrap_or(body.len());
+    body[..end].trim().to_string()
+}
+
+/// Drop the "[pit checkpoint] <task>" title line.
+fn strip_title(annotation: &str) -> &str {
+    match annotation.split_once('\n') {
+        Some((first, rest)) if first.starts_with("[pit checkpoint]") => rest.trim(),
+        _ => annotation.trim(),
+    }
+}
+
+/// Nest "## Done" etc. under the checkpoint heading.
+fn demote_headings(text: &str) -> String {
+    text.lines()
+        .map(|l| match l.strip_prefix("## ") {
+            Some(h) => format!("#### {}", h),
+            None => l.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `git diff --stat` of the worktree (committed and uncommitted) against
+/// where the branch forked from the main branch.
+fn diff_stat(repo_root: &Path, worktree: &Path) -> String {
+    let main = match super::git_info::detect_main_branch(repo_root) {
+        Ok(m) => m,
+        Err(_) => return String::new(),
+    };
+    let base = Command::new("git")
+        .args(["merge-base", &main, "HEAD"])
+        .current_dir(worktree)
+        .output();
+    let base = match base {
+        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).trim().to_string(),
+        _ => return String::new(),
+    };
+    let output = Command::new("git")
+        .args(["diff", "--stat", "--stat-width=80", &base])
+        .current_dir(worktree)
+        .output();
+    match output {
+        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).trim_end().to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::task;
+    use std::process::Command as StdCommand;
+
+    fn git(dir: &Path, args: &[&str]) {
+        StdCommand::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+    }
+
+    fn setup() -> (tempfile::TempDir, Task) {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        git(dir.path(), &["config", "user.email", "test@test.com"]);
+        git(dir.path(), &["config", "user.name", "Test"]);
+        git(dir.path(), &["commit", "--allow-empty", "-m", "init"]);
+        git(dir.path(), &["branch", "-M", "main"]);
+        let db = crate::db::open_memory().unwrap();
+        let t = task::create(
+            &db,
+            dir.path(),
+            &task::CreateOpts {
+                name: "login",
+                prompt: "Fix the login timeout",
+                agent: "claude",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (dir, t)
+    }
+
+    #[test]
+    fn brief_includes_prompt_progress_changes_and_output() {
+        let (repo, t) = setup();
+        let wt = Path::new(&t.worktree);
+        std::fs::write(wt.join("auth.rs"), "fn login() {}\n").unwrap();
+        git(wt, &["add", "-A"]);
+        git(wt, &["commit", "-m", "add login handler"]);
+        checkpoint::create(repo.path(), t.id, &t.name, &t.branch, wt, None).unwrap();
+        std::fs::write(wt.join("auth.rs"), "fn login() { retry() }\n").unwrap();
+
+        let brief = build(
+            repo.path(),
+            &t,
+            "codex",
+            Some("Tried retries, still flaky.\n$ "),
+        );
+        assert!(brief.starts_with(TITLE));
+        assert!(brief.contains("You (codex) are taking over"));
+        assert!(brief.contains("another coding agent (claude)"));
+        assert!(brief.contains("## Original task\n\nFix the login timeout"));
+        assert!(brief.contains("### Checkpoint #1"));
+        assert!(brief.contains("#### Done\n- add login handler"));
+        assert!(brief.contains("auth.rs"));
+        assert!(brief.contains("## Last output from claude"));
+        assert!(brief.contains("Tried retries, still flaky."));
+    }
+
+    #[test]
+    fn original_prompt_unwraps_previous_brief() {
+        let (repo, mut t) = setup();
+        t.prompt = "Fix it.\n\n## Notes\nkeep API".to_string();
+        let first = build(repo.path(), &t, "codex", Some("done some work"));
+        assert_eq!(original_prompt(&first), t.prompt);
+
+        // Reassigning again doesn't nest briefs
+        t.prompt = first;
+        let second = build(repo.path(), &t, "pi", None);
+        assert_eq!(second.matches(TITLE).count(), 1);
+        assert_eq!(original_prompt(&second), "Fix it.\n\n## Notes\nkeep API");
+    }
+
+    #[test]
+    fn original_prompt_passes_plain_prompts_through() {
+        assert_eq!(original_prompt("  just do it \n"), "just do it");
+    }
+}
@@ -0,0 +1,308 @@
+//! Harvest `TODO(agent): …` comments into tasks.
+//!
+//! Markers are found with `git grep`, so only tracked files are searched and
+//! the pattern is a POSIX extended regex. Each harvested task remembers the
+//! marker's file and text, so pit can tell when the branch has removed it.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+use super::config;
+use super::task::{self, Task};
+
+/// Markers looked for when no pattern is given.
+pub const DEFAULT_PATTERN: &str = r"TODO\(agent\)";
+
+/// The repo's `harvest_pattern`, or [`DEFAULT_PATTERN`].
+pub fn pattern(repo_root: &Path) -> String {
+    config::get_for_repo(repo_root, "harvest_pattern")
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PATTERN.to_string())
+}
+
+/// Lines of code shown above and below a marker in the prompt.
+const CONTEXT_LINES: usize = 8;
+
+/// A marker comment in the repo.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Marker {
+    /// Path relative to the repo root.
+    pub file: String,
+    pub line: u32,
+    /// The whole line, trimmed.
+    pub text: String,
+}
+
+impl Marker {
+    /// What the comment asks for: the text after the marker's `:` with the
+    /// comment leader removed. `// TODO(agent): add retries` → `add retries`.
+    pub fn summary(&self) -> String {
+        let text = self.text.trim_start_matches(|c: char| !c.is_alphanumeric());
+        let after = match text.find(':') {
+            Some(i) if i <= 30 => &text[i + 1..],
+            _ => text,
+        };
+        after
+            .trim()
+            .trim_end_matches("*/")
+            .trim_end_matches("-->")
+            .trim()
+            .to_string()
+    }
+}
+
+/// All markers matching `pattern` in tracked files.
+pub fn scan(repo_root: &Path, pattern: &str) -> Result<Vec<Marker>> {
+    let output = Command::new("git")
+        .args(["grep", "-n", "-I", "-E", "-e", pattern])
+        .current_dir(repo_root)
+        .output()
+        .context("failed to run git grep")?;
+    match output.status.code() {
+        Some(0) => {}
+        // No matches
+        Some(1) => return Ok(Vec::new()),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("git grep failed: {}", stderr.trim());
+        }
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| {
+            let (file, rest) = l.split_once(':')?;
+            let (line, text) = rest.split_once(':')?;
+            Some(Marker {
+                file: file.to_string(),
+                line: line.parse().ok()?,
+                text: text.trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// The task already created from this marker, if any.
+pub fn harvested_by<'a>(marker: &Marker, tasks: &'a [Task]) -> Option<&'a Task> {
+    tasks
+        .iter()
+        .find(|t| t.source_file == marker.file && t.source_marker == marker.text)
+}
+
+/// Create a task for a marker, recording where it came from.
+pub fn create(
+    db: &Connection,
+    repo_root: &Path,
+    marker: &Marker,
+    agent: &str,
+    model: &str,
+) -> Result<Task> {
+    let existing = task::names(db)?;
+    let summary = marker.summary();
+    let t = task::create(
+        db,
+        repo_root,
+        &task::CreateOpts {
+            name: &task_name(&summary, &existing),
+            description: &summary,
+            prompt: &prompt(repo_root, marker),
+            issue_url: "",
+            agent,
+            model,
+        },
+    )?;
+    task::set_source(db, t.id, &marker.file, marker.line, &marker.text)?;
+    task::get(db, t.id)?.context("task disappeared after create")
+}
+
+/// Whether the task's source marker is still in its worktree; `None` for
+/// tasks that weren't harvested.
+pub fn marker_present(task: &Task) -> Option<bool> {
+    if task.source_file.is_empty() {
+        return None;
+    }
+    let path = Path::new(&task.worktree).join(&task.source_file);
+    Some(
+        std::fs::read_to_string(path)
+            .map(|content| content.lines().any(|l| l.trim() == task.source_marker))
+            .unwrap_or(false),
+    )
+}
+
+/// Prompt with the comment, its location and the code around it.
+pub fn prompt(repo_root: &Path, marker: &Marker) -> String {
+    let mut out = vec![
+        marker.summary(),
+        String::new(),
+        format!(
+            "This comes from a TODO comment at {}:{}:",
+            marker.file, marker.line
+        ),
+        String::new(),
+        "```".to_string(),
+    ];
+    let content = std::fs::read_to_string(repo_root.join(&marker.file)).unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    let at = marker.line as usize;
+    let start = at.saturating_sub(CONTEXT_LINES + 1);
+    let end = (at + CONTEXT_LINES).min(lines.len());
+    for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+        out.push(format!("{:>5} {}", i + 1, line));
+    }
+    if start >= end {
+        out.push(marker.text.clone());
+    }
+    out.push("```".to_string());
+    out.push(String::new());
+    out.push("Do what the comment asks, then remove the TODO comment.".to_string());
+    out.join("\n")
+}
+
+/// `todo-` and the first words of the summary, unique among `existing`.
+fn task_name(summary: &str, existing: &[String]) -> String {
+    let mut slug = String::new();
+    for word in summary
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if !slug.is_empty() && slug.len() + word.len() >= 40 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    let base = match slug.len() {
+        0 => "todo".to_string(),
+        _ => format!("todo-{}", &slug[..slug.len().min(40)]),
+    };
+    let mut name = base.clone();
+    let mut n = 2;
+    while existing.contains(&name) {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+/// Parse a selection like `1,3-5` into zero-based indexes below `count`.
+pub fn parse_selection(selection: &str, count: usize) -> Result<Vec<usize>> {
+    let mut picked = Vec::new();
+    for part in selection
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        let (from, to) = match part.split_once('-') {
+            Some((a, b)) => (a.trim(), b.trim()),
+            None => (part, part),
+        };
+        let (Ok(from), Ok(to)) = (from.parse::<usize>(), to.parse::<usize>()) else {
+            bail!("invalid selection '{}' (use numbers like 1,3-5)", part);
+        };
+        if from == 0 || to > count || from > to {
+            bail!("'{}' is out of range (1-{})", part, count);
+        }
+        for i in from - 1..to {
+            if !picked.contains(&i) {
+                picked.push(i);
+            }
+        }
+    }
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git(repo, &["init", "-q"]);
+        git(repo, &["config", "user.email", "test@test.com"]);
+        git(repo, &["config", "user.name", "Test"]);
+        std::fs::create_dir(repo.join("src")).unwrap();
+        std::fs::write(
+            repo.join("src/fetch.rs"),
+            "fn fetch() {\n    // TODO(agent): add retries with backoff\n    get()\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            repo.join("build.py"),
+            "# TODO(agent): drop the python 2 shim\n# TODO: not for agents\n",
+        )
+        .unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", "init"]);
+        git(repo, &["branch", "-M", "main"]);
+        dir
+    }
+
+    #[test]
+    fn scans_markers_and_builds_prompts() {
+        let dir = setup();
+        let markers = scan(dir.path(), DEFAULT_PATTERN).unwrap();
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers[0].file, "build.py");
+        assert_eq!(markers[0].summary(), "drop the python 2 shim");
+        assert_eq!(markers[1].file, "src/fetch.rs");
+        assert_eq!(markers[1].line, 2);
+        assert_eq!(markers[1].text, "// TODO(agent): add retries with backoff");
+
+        let p = prompt(dir.path(), &markers[1]);
+        assert!(p.starts_with("add retries with backoff\n"));
+        assert!(p.contains("at src/fetch.rs:2:"));
+        assert!(p.contains("    1 fn fetch() {\n    2     // TODO(agent)"));
+
+        assert!(scan(dir.path(), "FIXME").unwrap().is_empty());
+        assert_eq!(scan(dir.path(), "TODO").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn harvested_task_tracks_its_marker() {
+        let dir = setup();
+        let db = crate::db::open_memory().unwrap();
+        let marker = scan(dir.path(), DEFAULT_PATTERN).unwrap().remove(1);
+        let t = create(&db, dir.path(), &marker, "claude", "").unwrap();
+        assert_eq!(t.name, "todo-add-retries-with-backoff");
+        assert_eq!(t.source_file, "src/fetch.rs");
+        assert_eq!(t.source_line, 2);
+        assert_eq!(marker_present(&t), Some(true));
+        let tasks = task::list(&db).unwrap();
+        assert_eq!(harvested_by(&marker, &tasks).map(|t| t.id), Some(t.id));
+
+        // A second task from the same words gets its own name
+        let again = create(&db, dir.path(), &marker, "claude", "").unwrap();
+        assert_eq!(again.name, "todo-add-retries-with-backoff-2");
+
+        std::fs::write(
+            Path::new(&t.worktree).join("src/fetch.rs"),
+            "fn fetch() {\n    retry(get)\n}\n",
+        )
+        .unwrap();
+        assert_eq!(marker_present(&t), Some(false));
+    }
+
+    #[test]
+    fn parses_selections() {
+        assert_eq!(parse_selection("1, 3-5,3", 5).unwrap(), vec![0, 2, 3, 4]);
+        assert!(parse_selection("", 5).unwrap().is_empty());
+        assert!(parse_selection("0", 5).is_err());
+        assert!(parse_selection("4-6", 5).is_err());
+        assert!(parse_selection("two", 5).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Recent prompts, recalled with Up/Down in the new-task modal.
+//!
+//! Stored as JSON lines (one JSON string per prompt, oldest first) in
+//! `.pit/prompt-history.jsonl` so multi-line prompts survive intact.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// How many prompts to keep.
+const MAX_ENTRIES: usize = 50;
+
+fn path(repo_root: &Path) -> PathBuf {
+    repo_root.join(".pit").join("prompt-history.jsonl")
+}
+
+/// Load prompt history, oldest first. Unreadable lines are skipped.
+pub fn load(repo_root: &Path) -> Vec<String> {
+    std::fs::read_to_string(path(repo_root))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|l| serde_json::from_str::<String>(l).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Record a prompt as the most recent entry. Blank prompts are ignored and
+/// an earlier identical entry is moved to the end instead of duplicated.
+pub fn push(repo_root: &Path, prompt: &str) -> Result<()> {
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Ok(());
+    }
+    let mut entries = load(repo_root);
+    entries.retain(|e| e != prompt);
+    entries.push(prompt.to_string());
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+
+    let mut out = String::new();
+    for e in &entries {
+        out.push_str(&serde_json::to_string(e)?);
+        out.push('\n');
+    }
+    let p = path(repo_root);
+    std::fs::write(&p, out).with_context(|| format!("failed to write {}", p.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".pit")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn empty_when_missing() {
+        let dir = setup();
+        assert!(load(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn push_keeps_multiline_prompts() {
+        let dir = setup();
+        push(dir.path(), "first").unwrap();
+        push(dir.path(), "line one\nline \"two\"").unwrap();
+        assert_eq!(load(dir.path()), vec!["first", "line one\nline \"two\""]);
+    }
+
+    #[test]
+    fn push_dedupes_and_skips_blank() {
+        let dir = setup();
+        push(dir.path(), "a").unwrap();
+        push(dir.path(), "b").unwrap();
+        push(dir.path(), "  ").unwrap();
+        push(dir.path(), "a").unwrap();
+        assert_eq!(load(dir.path()), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn push_caps_entries() {
+        let dir = setup();
+        for i in 0..(MAX_ENTRIES + 5) {
+            push(dir.path(), &format!("p{}", i)).unwrap();
+        }
+        let entries = load(dir.path());
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0], "p5");
+    }
+}
@@ -1,10 +1,21 @@
 //! Unified issue fetching — dispatches to Linear or GitHub based on URL.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use super::github;
 use super::linear;
 
+/// File written into a task's worktree with the full issue context.
+/// The prompt references it so agents can read comments and links on demand.
+pub const CONTEXT_FILE: &str = ".pit-context.md";
+
+/// Default prompt budget for the issue description (characters).
+const DEFAULT_PROMPT_LIMIT: usize = 2000;
+/// Default budget for the whole context file (characters).
+const DEFAULT_CONTEXT_LIMIT: usize = 60_000;
+
 /// Fetched issue data (provider-agnostic).
 #[derive(Debug, Clone)]
 pub struct Issue {
@@ -13,6 +24,89 @@ pub struct Issue {
     pub title: String,
     pub description: String,
     pub state: String,
+    pub url: String,
+    pub context: IssueContext,
+}
+
+impl From<linear::LinearIssue> for Issue {
+    fn from(issue: linear::LinearIssue) -> Self {
+        Issue {
+            provider: Provider::Linear,
+            identifier: issue.identifier,
+            title: issue.title,
+            description: issue.description,
+            state: issue.state,
+            url: issue.url,
+            context: IssueContext::default(),
+        }
+    }
+}
+
+/// Everything around an issue that isn't its body: labels, discussion,
+/// related issues and attachments.
+#[derive(Debug, Clone, Default)]
+pub struct IssueContext {
+    pub labels: Vec<String>,
+    pub comments: Vec<Comment>,
+    pub links: Vec<LinkedIssue>,
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub author: String,
+    pub body: String,
+    pub created_at: String,
+}
+
+/// A related issue (sub-issue, blocker, duplicate, mention…).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedIssue {
+    pub relation: String,
+    pub identifier: String,
+    pub title: String,
+    pub state: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub title: String,
+    pub url: String,
+}
+
+/// Size limits applied when turning an issue into a prompt / context file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Max characters of description inlined into the prompt.
+    pub prompt: usize,
+    /// Max characters of the context file.
+    pub context: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            prompt: DEFAULT_PROMPT_LIMIT,
+            context: DEFAULT_CONTEXT_LIMIT,
+        }
+    }
+}
+
+impl Limits {
+    /// Read limits from config (`issues.prompt_limit`, `issues.context_limit`).
+    pub fn from_config() -> Self {
+        let defaults = Limits::default();
+        let read = |key: &str, default: usize| {
+            super::config::get(key)
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        Limits {
+            prompt: read("issues.prompt_limit", defaults.prompt),
+            context: read("issues.context_limit", defaults.context),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,28 +137,45 @@ pub fn detect_provider(url: &str) -> Provider {
     }
 }
 
-/// Fetch an issue from any supported provider.
+/// Fetch an issue from any supported provider, including comments and
+/// linked issues. Failing to load the extra context is not fatal — the
+/// issue is still returned with whatever could be fetched.
 pub fn fetch(url: &str) -> Result<Issue> {
     let url = url.trim();
     match detect_provider(url) {
         Provider::Linear => {
             let issue = linear::fetch_from_url(url)?;
+            let context = linear::fetch_context(&issue.identifier).unwrap_or_default();
             Ok(Issue {
                 provider: Provider::Linear,
                 identifier: issue.identifier,
                 title: issue.title,
                 description: issue.description,
                 state: issue.state,
+                url: if issue.url.is_empty() {
+                    url.to_string()
+                } else {
+                    issue.url
+                },
+                context,
             })
         }
         Provider::GitHub => {
-            let issue = github::fetch_from_url(url)?;
+            let issue_ref = github::parse_issue_url(url).context("not a valid GitHub issue URL")?;
+            let issue = github::fetch_issue(&issue_ref)?;
+            let context = github::fetch_context(&issue_ref, &issue);
             Ok(Issue {
                 provider: Provider::GitHub,
                 identifier: format!("#{}", issue.number),
                 title: issue.title,
                 description: issue.body,
                 state: issue.state,
+                url: if issue.url.is_empty() {
+                    url.to_string()
+                } else {
+                    issue.url
+                },
+                context,
             })
         }
         Provider::Unknown => {
@@ -73,16 +184,256 @@ pub fn fetch(url: &str) -> Result<Issue> {
     }
 }
 
-/// Build a prompt from a fetched issue.
+/// Build a prompt from a fetched issue, using the configured limits.
 pub fn issue_to_prompt(issue: &Issue) -> String {
+    issue_to_prompt_with(issue, &Limits::from_config())
+}
+
+/// Build a prompt from a fetched issue. Long descriptions are summarised
+/// and the prompt points at [`CONTEXT_FILE`] whenever something was left out.
+pub fn issue_to_prompt_with(issue: &Issue, limits: &Limits) -> String {
     let mut prompt = format!("{}: {}", issue.identifier, issue.title);
+    if !issue.context.labels.is_empty() {
+        prompt.push_str(&format!("\nLabels: {}", issue.context.labels.join(", ")));
+    }
+
+    let mut omitted = false;
     if !issue.description.is_empty() {
-        let desc: String = issue.description.chars().take(2000).collect();
+        let (desc, cut) = summarize(&issue.description, limits.prompt);
+        omitted = cut;
         prompt.push_str(&format!("\n\n{}", desc));
     }
+
+    if let Some(note) = context_reference(issue, omitted) {
+        prompt.push_str(&format!("\n\n{}", note));
+    }
     prompt
 }
 
+/// Combine a user-supplied prompt with a fetched issue: an empty prompt is
+/// replaced by the issue prompt, otherwise the context file reference is
+/// appended when the prompt doesn't already mention it.
+pub fn prompt_for(prompt: &str, issue: &Issue) -> String {
+    prompt_for_with(prompt, issue, &Limits::from_config())
+}
+
+/// [`prompt_for`] with explicit limits.
+pub fn prompt_for_with(prompt: &str, issue: &Issue, limits: &Limits) -> String {
+    if prompt.trim().is_empty() {
+        return issue_to_prompt_with(issue, limits);
+    }
+    if prompt.contains(CONTEXT_FILE) {
+        return prompt.to_string();
+    }
+    let omitted = !issue.description.is_empty() && !prompt.contains(issue.description.trim());
+    match context_reference(issue, omitted) {
+        Some(note) => format!("{}\n\n{}", prompt, note),
+        None => prompt.to_string(),
+    }
+}
+
+/// Sentence pointing the agent at [`CONTEXT_FILE`], if it holds anything
+/// the prompt doesn't.
+fn context_reference(issue: &Issue, description_omitted: bool) -> Option<String> {
+    let ctx = &issue.context;
+    let mut extras = Vec::new();
+    if description_omitted {
+        extras.push("the full description".to_string());
+    }
+    if !ctx.comments.is_empty() {
+        extras.push(plural(ctx.comments.len(), "comment"));
+    }
+    if !ctx.links.is_empty() {
+        extras.push(plural(ctx.links.len(), "linked issue"));
+    }
+    if !ctx.attachments.is_empty() {
+        extras.push(plural(ctx.attachments.len(), "attachment"));
+    }
+    if extras.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Full issue context ({}) is in `{}` in the repository root — read it before starting.",
+        extras.join(", "),
+        CONTEXT_FILE
+    ))
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+/// Render the full issue context as markdown, sized to roughly `limits.context`.
+/// When the discussion is too long, each comment gets an equal share of the
+/// remaining budget and is summarised rather than dropped.
+pub fn context_markdown(issue: &Issue, limits: &Limits) -> String {
+    let ctx = &issue.context;
+    let mut out = format!("# {}: {}\n\n", issue.identifier, issue.title);
+
+    let mut meta = vec![format!("- **State:** {}", issue.state)];
+    if !issue.url.is_empty() {
+        meta.push(format!("- **URL:** {}", issue.url));
+    }
+    if !ctx.labels.is_empty() {
+        meta.push(format!("- **Labels:** {}", ctx.labels.join(", ")));
+    }
+    out.push_str(&meta.join("\n"));
+    out.push_str("\n\n");
+
+    let mut links = String::new();
+    if !ctx.links.is_empty() {
+        links.push_str("## Linked issues\n\n");
+        for l in &ctx.links {
+            let state = if l.state.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", l.state)
+            };
+            links.push_str(&format!(
+                "- {}: {} — {}{}\n",
+                l.relation, l.identifier, l.title, state
+            ));
+        }
+        links.push('\n');
+    }
+    if !ctx.attachments.is_empty() {
+        links.push_str("## Attachments\n\n");
+        for a in &ctx.attachments {
+            links.push_str(&format!("- [{}]({})\n", a.title, a.url));
+        }
+        links.push('\n');
+    }
+
+    // Description and comments share whatever budget the fixed parts leave.
+    let budget = limits
+        .context
+        .saturating_sub(out.chars().count() + links.chars().count());
+    let comment_chars: usize = ctx.comments.iter().map(|c| c.body.chars().count()).sum();
+    let desc_chars = issue.description.chars().count();
+    let (desc_budget, per_comment) = if desc_chars + comment_chars <= budget {
+        (budget, budget)
+    } else {
+        let desc_budget = desc_chars.min(budget / 2);
+        let per_comment = budget.saturating_sub(desc_budget) / ctx.comments.len().max(1);
+        (desc_budget, per_comment)
+    };
+
+    out.push_str("## Description\n\n");
+    if issue.description.trim().is_empty() {
+        out.push_str("_No description._\n\n");
+    } else {
+        out.push_str(&summarize(&issue.description, desc_budget).0);
+        out.push_str("\n\n");
+    }
+
+    if !ctx.comments.is_empty() {
+        out.push_str(&format!("## Comments ({})\n\n", ctx.comments.len()));
+        for c in &ctx.comments {
+            out.push_str(&format!("### {} — {}\n\n", c.author, c.created_at));
+            out.push_str(&summarize(&c.body, per_comment).0);
+            out.push_str("\n\n");
+        }
+    }
+
+    out.push_str(&links);
+    out.trim_end().to_string() + "\n"
+}
+
+/// Write the issue context file into a worktree. Returns its path.
+pub fn write_context_file(worktree: &Path, issue: &Issue) -> Result<PathBuf> {
+    write_context_file_with(worktree, issue, &Limits::from_config())
+}
+
+/// [`write_context_file`] with explicit limits. The file is added to the
+/// repo's `info/exclude` unless it is already ignored (repos initialised
+/// before it existed don't list it in `.gitignore`), so agents don't
+/// commit it.
+pub fn write_context_file_with(worktree: &Path, issue: &Issue, limits: &Limits) -> Result<PathBuf> {
+    let path = worktree.join(CONTEXT_FILE);
+    let content = context_markdown(issue, limits);
+    std::fs::write(&path, content)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    exclude(worktree, CONTEXT_FILE)?;
+    Ok(path)
+}
+
+/// Add `file` to the repo's `info/exclude` if git doesn't ignore it yet.
+fn exclude(worktree: &Path, file: &str) -> Result<()> {
+    let ignored = Command::new("git")
+        .args(["check-ignore", "-q", file])
+        .current_dir(worktree)
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if ignored {
+        return Ok(());
+    }
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "info/exclude"])
+        .current_dir(worktree)
+        .output()
+        .context("failed to run git rev-parse")?;
+    if !output.status.success() {
+        // Not a git checkout: nothing to keep it out of
+        return Ok(());
+    }
+    let exclude = worktree.join(String::from_utf8_lossy(&output.stdout).trim());
+    let mut content = std::fs::read_to_string(&exclude).unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(file);
+    content.push('\n');
+    if let Some(dir) = exclude.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&exclude, content)
+        .with_context(|| format!("failed to write {}", exclude.display()))
+}
+
+/// Shorten `text` to at most `limit` characters without cutting mid-thought:
+/// keep whole paragraphs while they fit, otherwise end on a sentence or word
+/// boundary, and note how much was left out. Returns (text, was_shortened).
+pub fn summarize(text: &str, limit: usize) -> (String, bool) {
+    let text = text.trim();
+    let total = text.chars().count();
+    if total <= limit {
+        return (text.to_string(), false);
+    }
+
+    let mut kept = String::new();
+    for para in text.split("\n\n") {
+        let sep = if kept.is_empty() { 0 } else { 2 };
+        if kept.chars().count() + sep + para.chars().count() > limit {
+            break;
+        }
+        if sep > 0 {
+            kept.push_str("\n\n");
+        }
+        kept.push_str(para);
+    }
+
+    if kept.is_empty() {
+        // First paragraph alone is too long: cut at a sentence, then a word.
+        let head: String = text.chars().take(limit).collect();
+        let cut = head
+            .rfind(". ")
+            .map(|i| i + 1)
+            .filter(|i| *i > limit / 2)
+            .or_else(|| head.rfind(char::is_whitespace))
+            .unwrap_or(head.len());
+        kept = head[..cut].trim_end().to_string();
+    }
+
+    let omitted = total - kept.chars().count();
+    kept.push_str(&format!("\n\n[… {} more characters omitted]", omitted));
+    (kept, true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,8 +472,10 @@ mod tests {
             title: "Fix timeout".to_string(),
             description: "Details here.".to_string(),
             state: "In Progress".to_string(),
+            url: String::new(),
+            context: IssueContext::default(),
         };
-        let prompt = issue_to_prompt(&issue);
+        let prompt = issue_to_prompt_with(&issue, &Limits::default());
         assert!(prompt.starts_with("ENG-42: Fix timeout"));
         assert!(prompt.contains("Details here."));
     }
@@ -135,7 +488,202 @@ mod tests {
             title: "Add tests".to_string(),
             description: String::new(),
             state: "open".to_string(),
+            url: String::new(),
+            context: IssueContext::default(),
+        };
+        assert_eq!(
+            issue_to_prompt_with(&issue, &Limits::default()),
+            "#7: Add tests"
+        );
+    }
+
+    fn make_issue(description: &str, context: IssueContext) -> Issue {
+        Issue {
+            provider: Provider::Linear,
+            identifier: "ENG-1".to_string(),
+            title: "Fix it".to_string(),
+            description: description.to_string(),
+            state: "Todo".to_string(),
+            url: "https://linear.app/t/issue/ENG-1".to_string(),
+            context,
+        }
+    }
+
+    fn comment(body: &str) -> Comment {
+        Comment {
+            author: "alice".to_string(),
+            body: body.to_string(),
+            created_at: "2025-01-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn summarize_keeps_short_text() {
+        assert_eq!(summarize("  hello  ", 100), ("hello".to_string(), false));
+    }
+
+    #[test]
+    fn summarize_keeps_whole_paragraphs() {
+        let text = format!(
+            "{}\n\n{}\n\n{}",
+            "a".repeat(40),
+            "b".repeat(40),
+            "c".repeat(40)
+        );
+        let (out, cut) = summarize(&text, 90);
+        assert!(cut);
+        assert!(out.starts_with(&format!("{}\n\n{}", "a".repeat(40), "b".repeat(40))));
+        assert!(!out.contains("ccc"));
+        assert!(out.ends_with("[… 42 more characters omitted]"));
+    }
+
+    #[test]
+    fn summarize_cuts_long_paragraph_at_sentence() {
+        let text = "First sentence is here. Second one runs on and on and on.";
+        let (out, cut) = summarize(text, 40);
+        assert!(cut);
+        assert!(out.starts_with("First sentence is here.\n\n[…"));
+    }
+
+    #[test]
+    fn summarize_cuts_at_word_boundary() {
+        let (out, _) = summarize("alpha beta gamma delta", 13);
+        assert!(out.starts_with("alpha beta\n\n"));
+    }
+
+    #[test]
+    fn prompt_references_context_file_when_there_is_more() {
+        let ctx = IssueContext {
+            comments: vec![comment("one"), comment("two")],
+            ..Default::default()
         };
-        assert_eq!(issue_to_prompt(&issue), "#7: Add tests");
+        let prompt = issue_to_prompt_with(&make_issue("Short.", ctx), &Limits::default());
+        assert!(prompt.contains("Short."));
+        assert!(prompt.contains(CONTEXT_FILE));
+        assert!(prompt.contains("2 comments"));
+    }
+
+    #[test]
+    fn prompt_summarises_long_description() {
+        let desc = "word ".repeat(100);
+        let limits = Limits {
+            prompt: 50,
+            context: 1000,
+        };
+        let prompt = issue_to_prompt_with(&make_issue(&desc, IssueContext::default()), &limits);
+        assert!(prompt.contains("more characters omitted"));
+        assert!(prompt.contains("the full description"));
+    }
+
+    #[test]
+    fn prompt_without_extras_has_no_context_reference() {
+        let prompt = issue_to_prompt_with(
+            &make_issue("Short.", IssueContext::default()),
+            &Limits::default(),
+        );
+        assert!(!prompt.contains(CONTEXT_FILE));
+    }
+
+    #[test]
+    fn prompt_for_fills_empty_prompt() {
+        let issue = make_issue("Desc.", IssueContext::default());
+        assert_eq!(
+            prompt_for_with("", &issue, &Limits::default()),
+            issue_to_prompt_with(&issue, &Limits::default())
+        );
+    }
+
+    #[test]
+    fn prompt_for_appends_reference_to_custom_prompt() {
+        let ctx = IssueContext {
+            comments: vec![comment("hi")],
+            ..Default::default()
+        };
+        let issue = make_issue("Desc.", ctx);
+        let prompt = prompt_for_with("Do the thing", &issue, &Limits::default());
+        assert!(prompt.starts_with("Do the thing\n\n"));
+        assert!(prompt.contains("the full description, 1 comment"));
+        // Already-referencing prompts are left alone.
+        assert_eq!(prompt_for_with(&prompt, &issue, &Limits::default()), prompt);
+    }
+
+    #[test]
+    fn context_markdown_includes_everything() {
+        let ctx = IssueContext {
+            labels: vec!["bug".to_string()],
+            comments: vec![comment("Repro steps")],
+            links: vec![LinkedIssue {
+                relation: "blocks".to_string(),
+                identifier: "ENG-2".to_string(),
+                title: "Other".to_string(),
+                state: "Todo".to_string(),
+            }],
+            attachments: vec![Attachment {
+                title: "Log".to_string(),
+                url: "https://example.com/log".to_string(),
+            }],
+        };
+        let md = context_markdown(&make_issue("The description.", ctx), &Limits::default());
+        assert!(md.starts_with("# ENG-1: Fix it"));
+        assert!(md.contains("**Labels:** bug"));
+        assert!(md.contains("The description."));
+        assert!(md.contains("## Comments (1)"));
+        assert!(md.contains("### alice — 2025-01-01"));
+        assert!(md.contains("Repro steps"));
+        assert!(md.contains("- blocks: ENG-2 — Other [Todo]"));
+        assert!(md.contains("[Log](https://example.com/log)"));
+    }
+
+    #[test]
+    fn context_markdown_summarises_to_limit() {
+        let ctx = IssueContext {
+            comments: (0..10)
+                .map(|_| comment(&"lorem ipsum ".repeat(200)))
+                .collect(),
+            ..Default::default()
+        };
+        let limits = Limits {
+            prompt: 100,
+            context: 3000,
+        };
+        let md = context_markdown(&make_issue(&"desc ".repeat(1000), ctx), &limits);
+        assert!(md.chars().count() < 4000);
+        assert_eq!(md.matches("### alice").count(), 10);
+        assert!(md.contains("more characters omitted"));
+    }
+
+    #[test]
+    fn write_context_file_creates_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_context_file_with(
+            dir.path(),
+            &make_issue("Body", IssueContext::default()),
+            &Limits::default(),
+        )
+        .unwrap();
+        assert_eq!(path, dir.path().join(CONTEXT_FILE));
+        assert!(std::fs::read_to_string(path).unwrap().contains("Body"));
+    }
+
+    #[test]
+    fn write_context_file_keeps_it_out_of_git() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&out.stdout).to_string()
+        };
+        git(&["init", "-q"]);
+        let issue = make_issue("Body", IssueContext::default());
+        write_context_file_with(dir.path(), &issue, &Limits::default()).unwrap();
+        assert_eq!(git(&["status", "--porcelain"]), "");
+
+        // Written again, it isn't listed twice
+        write_context_file_with(dir.path(), &issue, &Limits::default()).unwrap();
+        let exclude = std::fs::read_to_string(dir.path().join(".git/info/exclude")).unwrap();
+        assert_eq!(exclude.lines().filter(|l| *l == CONTEXT_FILE).count(), 1);
     }
 }
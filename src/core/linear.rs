@@ -7,6 +7,8 @@
 
 use anyhow::{bail, Context, Result};
 
+use super::issues::{summarize, Attachment, Comment, IssueContext, Limits, LinkedIssue};
+
 /// Parsed issue data from Linear.
 #[derive(Debug, Clone)]
 pub struct LinearIssue {
@@ -263,12 +265,133 @@ pub fn fetch_from_url(url: &str) -> Result<LinearIssue> {
     fetch_issue(&identifier)
 }
 
-/// Build a prompt from a Linear issue.
+/// Fetch labels, comments, related issues and attachments for an issue.
+pub fn fetch_context(identifier: &str) -> Result<IssueContext> {
+    let api_key = super::config::get("linear.api_key")
+        .context("Linear API key not set. Run: pit config set linear.api_key <your-key>")?;
+
+    let query = serde_json::json!({
+        "query": r#"
+            query($filter: IssueFilter!) {
+                issues(filter: $filter, first: 1) {
+                    nodes {
+                        labels { nodes { name } }
+                        comments(first: 100) {
+                            nodes { body createdAt user { name } }
+                        }
+                        parent { identifier title state { name } }
+                        children { nodes { identifier title state { name } } }
+                        relations {
+                            nodes { type relatedIssue { identifier title state { name } } }
+                        }
+                        attachments { nodes { title url } }
+                    }
+                }
+            }
+        "#,
+        "variables": {
+            "filter": {
+                "identifier": { "eq": identifier }
+            }
+        }
+    });
+
+    let resp = ureq::post("https://api.linear.app/graphql")
+        .set("Authorization", &api_key)
+        .set("Content-Type", "application/json")
+        .send_string(&query.to_string())
+        .context("failed to call Linear API")?;
+
+    let body: serde_json::Value = resp
+        .into_json()
+        .context("failed to parse Linear response")?;
+
+    if let Some(errors) = body.get("errors") {
+        bail!("Linear API error: {}", errors);
+    }
+
+    let node = body
+        .pointer("/data/issues/nodes/0")
+        .context(format!("issue '{}' not found in Linear", identifier))?;
+    Ok(parse_context(node))
+}
+
+/// Turn an issue node from [`fetch_context`]'s query into an [`IssueContext`].
+pub fn parse_context(node: &serde_json::Value) -> IssueContext {
+    let nodes = |ptr: &str| -> Vec<serde_json::Value> {
+        node.pointer(ptr)
+            .and_then(|n| n.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let str_at = |v: &serde_json::Value, ptr: &str| -> String {
+        v.pointer(ptr)
+            .and_then(|s| s.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let linked = |relation: &str, v: &serde_json::Value| LinkedIssue {
+        relation: relation.to_string(),
+        identifier: str_at(v, "/identifier"),
+        title: str_at(v, "/title"),
+        state: str_at(v, "/state/name"),
+    };
+
+    let labels = nodes("/labels/nodes")
+        .iter()
+        .map(|l| str_at(l, "/name"))
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    let mut comments: Vec<Comment> = nodes("/comments/nodes")
+        .iter()
+        .map(|c| Comment {
+            author: c
+                .pointer("/user/name")
+                .and_then(|n| n.as_str())
+                .unwrap_or("unknown")
+                .to_string(),
+            body: str_at(c, "/body"),
+            created_at: str_at(c, "/createdAt"),
+        })
+        .collect();
+    comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    let mut links = Vec::new();
+    if let Some(parent) = node.get("parent").filter(|p| !p.is_null()) {
+        links.push(linked("parent", parent));
+    }
+    for child in nodes("/children/nodes") {
+        links.push(linked("sub-issue", &child));
+    }
+    for rel in nodes("/relations/nodes") {
+        if let Some(issue) = rel.get("relatedIssue") {
+            links.push(linked(rel["type"].as_str().unwrap_or("related"), issue));
+        }
+    }
+
+    let attachments = nodes("/attachments/nodes")
+        .iter()
+        .map(|a| Attachment {
+            title: str_at(a, "/title"),
+            url: str_at(a, "/url"),
+        })
+        .collect();
+
+    IssueContext {
+        labels,
+        comments,
+        links,
+        attachments,
+    }
+}
+
+/// Build a prompt from a Linear issue. Long descriptions are summarised to
+/// the configured `issues.prompt_limit`.
 pub fn issue_to_prompt(issue: &LinearIssue) -> String {
     let mut prompt = format!("{}: {}", issue.identifier, issue.title);
     if !issue.description.is_empty() {
-        // Truncate very long descriptions
-        let desc: String = issue.description.chars().take(2000).collect();
+        let (desc, _) = summarize(&issue.description, Limits::from_config().prompt);
         prompt.push_str(&format!("\n\n{}", desc));
     }
     prompt
@@ -358,7 +481,46 @@ mod tests {
             url: String::new(),
         };
         let prompt = issue_to_prompt(&issue);
-        // Title + \n\n + 2000 chars
+        // Title + \n\n + 2000 chars + omission note
         assert!(prompt.len() < 2100);
+        assert!(prompt.contains("more characters omitted"));
+    }
+
+    #[test]
+    fn parse_context_reads_all_sections() {
+        let node = serde_json::json!({
+            "labels": { "nodes": [{ "name": "bug" }, { "name": "auth" }] },
+            "comments": { "nodes": [
+                { "body": "second", "createdAt": "2025-01-02", "user": { "name": "Bo" } },
+                { "body": "first", "createdAt": "2025-01-01", "user": null }
+            ] },
+            "parent": { "identifier": "ENG-1", "title": "Epic", "state": { "name": "Todo" } },
+            "children": { "nodes": [
+                { "identifier": "ENG-3", "title": "Child", "state": { "name": "Done" } }
+            ] },
+            "relations": { "nodes": [
+                { "type": "blocks", "relatedIssue": { "identifier": "ENG-9", "title": "Other", "state": { "name": "Todo" } } }
+            ] },
+            "attachments": { "nodes": [{ "title": "Sentry", "url": "https://sentry.io/x" }] }
+        });
+        let ctx = parse_context(&node);
+        assert_eq!(ctx.labels, vec!["bug", "auth"]);
+        assert_eq!(ctx.comments.len(), 2);
+        assert_eq!(ctx.comments[0].body, "first");
+        assert_eq!(ctx.comments[0].author, "unknown");
+        assert_eq!(ctx.comments[1].author, "Bo");
+        let rels: Vec<_> = ctx.links.iter().map(|l| l.relation.as_str()).collect();
+        assert_eq!(rels, vec!["parent", "sub-issue", "blocks"]);
+        assert_eq!(ctx.links[2].identifier, "ENG-9");
+        assert_eq!(ctx.attachments[0].url, "https://sentry.io/x");
+    }
+
+    #[test]
+    fn parse_context_handles_missing_fields() {
+        let ctx = parse_context(&serde_json::json!({ "parent": null }));
+        assert!(ctx.labels.is_empty());
+        assert!(ctx.comments.is_empty());
+        assert!(ctx.links.is_empty());
+        assert!(ctx.attachments.is_empty());
     }
 }
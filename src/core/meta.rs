@@ -0,0 +1,162 @@
+//! Task metadata mirrored into git, so the DB can be rebuilt if it is lost.
+//!
+//! Each task's row is kept as a JSON blob at `refs/pit/<id>/meta`, next to its
+//! checkpoints. It is rewritten whenever the task's metadata changes and goes
+//! away with the task's other refs when it is deleted. The DB stays the
+//! source of truth; these blobs are only read by `pit repair`.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::checkpoint;
+use super::task::Task;
+
+/// The part of a task worth keeping: everything but its run state.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Meta {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub prompt: String,
+    pub issue_url: String,
+    pub agent: String,
+    pub model: String,
+    pub branch: String,
+    pub worktree: String,
+    pub session_id: Option<String>,
+    pub created_at: String,
+    pub pr_url: String,
+    pub pr_state: String,
+    pub source_file: String,
+    pub source_line: i64,
+    pub source_marker: String,
+    pub adopted: bool,
+    pub parent_id: Option<i64>,
+    pub fork_point: String,
+    pub archived_at: String,
+}
+
+impl From<&Task> for Meta {
+    fn from(t: &Task) -> Self {
+        Meta {
+            id: t.id,
+            name: t.name.clone(),
+            description: t.description.clone(),
+            prompt: t.prompt.clone(),
+            issue_url: t.issue_url.clone(),
+            agent: t.agent.clone(),
+            model: t.model.clone(),
+            branch: t.branch.clone(),
+            worktree: t.worktree.clone(),
+            session_id: t.session_id.clone(),
+            created_at: t.created_at.clone(),
+            pr_url: t.pr_url.clone(),
+            pr_state: t.pr_state.clone(),
+            source_file: t.source_file.clone(),
+            source_line: t.source_line,
+            source_marker: t.source_marker.clone(),
+            adopted: t.adopted,
+            parent_id: t.parent_id,
+            fork_point: t.fork_point.clone(),
+            archived_at: t.archived_at.clone(),
+        }
+    }
+}
+
+/// Where a task's metadata is kept.
+pub fn meta_ref(task_id: i64) -> String {
+    format!("{}meta", checkpoint::task_refs(task_id))
+}
+
+/// Write the task's metadata. `dir` is any checkout of the repo; nothing is
+/// written if it doesn't exist.
+pub fn save(dir: &Path, task: &Task) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(&Meta::from(task))?;
+    let mut child = Command::new("git")
+        .args(["hash-object", "-w", "--stdin"])
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run git hash-object")?;
+    child
+        .stdin
+        .take()
+        .context("no stdin for git hash-object")?
+        .write_all(json.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git hash-object failed: {}", stderr.trim());
+    }
+    let blob = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    git(dir, &["update-ref", &meta_ref(task.id), &blob])?;
+    Ok(())
+}
+
+/// Write metadata for the tasks that don't have any yet, such as tasks
+/// created before it was kept. Returns how many were written.
+pub fn backfill(repo_root: &Path, tasks: &[Task]) -> Result<usize> {
+    let saved = saved_ids(repo_root)?;
+    let mut written = 0;
+    for t in tasks.iter().filter(|t| !saved.contains(&t.id)) {
+        save(repo_root, t)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// All saved metadata, by task ID.
+pub fn load_all(repo_root: &Path) -> Result<Vec<Meta>> {
+    let mut out = Vec::new();
+    for id in saved_ids(repo_root)? {
+        let Ok(json) = git(repo_root, &["cat-file", "blob", &meta_ref(id)]) else {
+            continue;
+        };
+        if let Ok(meta) = serde_json::from_str::<Meta>(&json) {
+            out.push(Meta { id, ..meta });
+        }
+    }
+    Ok(out)
+}
+
+/// IDs of the tasks with saved metadata, in order.
+fn saved_ids(repo_root: &Path) -> Result<Vec<i64>> {
+    let refs = git(
+        repo_root,
+        &["for-each-ref", "--format=%(refname)", "refs/pit/"],
+    )?;
+    let mut ids: Vec<i64> = refs
+        .lines()
+        .filter_map(|r| {
+            r.strip_prefix("refs/pit/")?
+                .strip_suffix("/meta")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
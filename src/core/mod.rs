@@ -1,15 +1,35 @@
+pub mod adopt;
+pub mod archive;
+pub mod autocheckpoint;
 pub mod checkpoint;
+pub mod checks;
 pub mod config;
+pub mod doctor;
+pub mod editor;
+pub mod fork;
 pub mod git_info;
 #[allow(dead_code)]
 pub mod github;
+pub mod handoff;
+pub mod harvest;
+pub mod history;
 #[allow(dead_code)]
 pub mod issues;
 #[allow(dead_code)]
 pub mod linear;
+pub mod meta;
 pub mod names;
+pub mod overlap;
+pub mod pr;
 pub mod project;
+pub mod queue;
 pub mod reap;
+pub mod repair;
+pub mod squash;
+pub mod sync;
 pub mod task;
+pub mod template;
+pub mod test_failures;
 #[allow(dead_code)]
 pub mod tmux;
+pub mod worktrees;
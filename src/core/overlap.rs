@@ -0,0 +1,200 @@
+//! Predict which active tasks will conflict with each other.
+//!
+//! Every pair of tasks that touch the same files (committed or not) is an
+//! overlap. For those pairs a trial `git merge-tree` of the two branches, with
+//! uncommitted work included, tells whether the changes actually collide or
+//! just sit in different parts of the same files.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+
+use super::checkpoint;
+use super::git_info;
+use super::task::{Status, Task};
+
+/// Two tasks that change the same files.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Overlap {
+    pub a: String,
+    pub b: String,
+    /// Files both tasks change.
+    pub files: Vec<String>,
+    /// Files the trial merge couldn't merge cleanly.
+    pub conflicts: Vec<String>,
+}
+
+impl Overlap {
+    pub fn involves(&self, task_name: &str) -> bool {
+        self.a == task_name || self.b == task_name
+    }
+
+    /// The other task of the pair.
+    pub fn other(&self, task_name: &str) -> &str {
+        if self.a == task_name {
+            &self.b
+        } else {
+            &self.a
+        }
+    }
+}
+
+/// Overlaps between all tasks that aren't done, conflicting pairs first.
+pub fn analyze(repo_root: &Path, tasks: &[Task]) -> Vec<Overlap> {
+    let active: Vec<(&Task, BTreeSet<String>)> = tasks
+        .iter()
+        .filter(|t| t.status != Status::Done && Path::new(&t.worktree).exists())
+        .map(|t| {
+            let info =
+                git_info::gather_with_worktree(repo_root, &t.branch, Some(Path::new(&t.worktree)));
+            (t, info.files.into_iter().map(|f| f.path).collect())
+        })
+        .collect();
+
+    let mut overlaps = Vec::new();
+    for (i, (a, a_files)) in active.iter().enumerate() {
+        for (b, b_files) in &active[i + 1..] {
+            let files: Vec<String> = a_files.intersection(b_files).cloned().collect();
+            if files.is_empty() {
+                continue;
+            }
+            let conflicts = trial_merge(repo_root, a, b).unwrap_or_default();
+            overlaps.push(Overlap {
+                a: a.name.clone(),
+                b: b.name.clone(),
+                files,
+                conflicts,
+            });
+        }
+    }
+    overlaps.sort_by_key(|o| o.conflicts.is_empty());
+    overlaps
+}
+
+/// Merge the two tasks' work in memory and return the conflicting files.
+fn trial_merge(repo_root: &Path, a: &Task, b: &Task) -> Result<Vec<String>> {
+    let a_tip = tip(repo_root, a)?;
+    let b_tip = tip(repo_root, b)?;
+    let output = Command::new("git")
+        .args([
+            "merge-tree",
+            "--write-tree",
+            "--name-only",
+            "--no-messages",
+            &a_tip,
+            &b_tip,
+        ])
+        .current_dir(repo_root)
+        .output()
+        .context("failed to run git merge-tree")?;
+    match output.status.code() {
+        Some(0) => Ok(Vec::new()),
+        // The first line is the merged tree, then one conflicted file per line
+        Some(1) => Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .take_while(|l| !l.is_empty())
+            .map(|l| l.to_string())
+            .collect()),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!("git merge-tree failed: {}", stderr.trim());
+        }
+    }
+}
+
+/// A commit with the task's current work: the branch itself when the
+/// worktree is clean, otherwise an unreferenced commit of the worktree.
+fn tip(repo_root: &Path, task: &Task) -> Result<String> {
+    let worktree = Path::new(&task.worktree);
+    let tree = checkpoint::current_tree(worktree)?;
+    if tree == "HEAD" {
+        return Ok(task.branch.clone());
+    }
+    let output = Command::new("git")
+        .args([
+            "commit-tree",
+            &tree,
+            "-p",
+            &task.branch,
+            "-m",
+            "pit conflict probe",
+        ])
+        .current_dir(repo_root)
+        .output()
+        .context("failed to run git commit-tree")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git commit-tree failed: {}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::task;
+
+    fn git(dir: &Path, args: &[&str]) {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+    }
+
+    #[test]
+    fn overlapping_and_conflicting_pairs() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git(repo, &["init", "-q"]);
+        git(repo, &["config", "user.email", "test@test.com"]);
+        git(repo, &["config", "user.name", "Test"]);
+        std::fs::write(repo.join("lib.rs"), "a\nb\nc\nd\ne\nf\n").unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", "init"]);
+        git(repo, &["branch", "-M", "main"]);
+
+        let db = crate::db::open_memory().unwrap();
+        let make = |name: &str, content: Option<&str>| {
+            let t = task::create(
+                &db,
+                repo,
+                &task::CreateOpts {
+                    name,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            if let Some(content) = content {
+                std::fs::write(Path::new(&t.worktree).join("lib.rs"), content).unwrap();
+            }
+            t
+        };
+        let top = make("top", Some("A\nb\nc\nd\ne\nf\n"));
+        make("bottom", Some("a\nb\nc\nd\ne\nF\n"));
+        make("also-top", Some("X\nb\nc\nd\ne\nf\n"));
+        make("elsewhere", None);
+        // Committed work counts as well as uncommitted
+        git(Path::new(&top.worktree), &["commit", "-qam", "top"]);
+
+        let overlaps = analyze(repo, &task::list(&db).unwrap());
+        let pairs: Vec<(&str, &str, bool)> = overlaps
+            .iter()
+            .map(|o| (o.a.as_str(), o.b.as_str(), !o.conflicts.is_empty()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("top", "also-top", true),
+                ("top", "bottom", false),
+                ("bottom", "also-top", false),
+            ]
+        );
+        assert_eq!(overlaps[0].conflicts, vec!["lib.rs"]);
+        assert_eq!(overlaps[0].other("also-top"), "top");
+        assert!(!overlaps.iter().any(|o| o.involves("elsewhere")));
+    }
+}
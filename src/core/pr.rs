@@ -0,0 +1,465 @@
+//! Pull requests for task branches.
+//!
+//! `pit pr` pushes `pit/<name>` to the configured remote (`pr.remote`,
+//! default `origin`) and opens a GitHub pull request for it, reusing
+//! `github.token`. The API base URL can be changed with `github.api_url`
+//! (e.g. for GitHub Enterprise).
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use super::checkpoint;
+use super::handoff;
+use super::squash;
+use super::task::Task;
+
+const DEFAULT_API: &str = "https://api.github.com";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A pull request as pit tracks it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub url: String,
+    /// "open", "merged" or "closed".
+    pub state: String,
+}
+
+/// What to open a pull request with.
+#[derive(Debug, Clone, Default)]
+pub struct NewPullRequest {
+    pub title: String,
+    pub body: String,
+    /// Branch to merge, e.g. `pit/fix-auth`.
+    pub head: String,
+    /// Branch to merge into.
+    pub base: String,
+    pub draft: bool,
+}
+
+/// Remote to push task branches to.
+pub fn remote_name() -> String {
+    super::config::get("pr.remote")
+        .filter(|r| !r.trim().is_empty())
+        .unwrap_or_else(|| "origin".to_string())
+}
+
+/// Base URL of the GitHub API.
+pub fn api_base() -> String {
+    super::config::get("github.api_url")
+        .filter(|u| !u.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_API.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Hosts a GitHub repository URL can be on: `github.com`, and the host of
+/// `api` for GitHub Enterprise (`ghe.example.com` for
+/// `https://ghe.example.com/api/v3` or `https://api.ghe.example.com`).
+fn web_hosts(api: &str) -> Vec<String> {
+    let mut hosts = vec!["github.com".to_string()];
+    let host = api
+        .split_once("://")
+        .map_or(api, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or("");
+    let host = host.strip_prefix("api.").unwrap_or(host);
+    if !host.is_empty() && !hosts.iter().any(|h| h == host) {
+        hosts.push(host.to_string());
+    }
+    hosts
+}
+
+/// Push `branch` to `remote` and set it as the upstream. The push is forced
+/// with a lease, since `pit squash` rewrites task branches.
+pub fn push(repo_root: &Path, remote: &str, branch: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args([
+            "push",
+            "--force-with-lease",
+            "--set-upstream",
+            remote,
+            &format!("{0}:{0}", branch),
+        ])
+        .current_dir(repo_root)
+        .output()
+        .context("failed to run git push")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git push to '{}' failed: {}", remote, stderr.trim());
+    }
+    Ok(())
+}
+
+/// The GitHub (owner, repo) a remote points at.
+pub fn remote_repo(repo_root: &Path, remote: &str) -> Result<(String, String)> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", remote])
+        .current_dir(repo_root)
+        .output()
+        .context("failed to run git remote")?;
+    if !output.status.success() {
+        bail!("no git remote named '{}'", remote);
+    }
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    parse_remote_url(&url, &api_base())
+        .with_context(|| format!("remote '{}' ({}) is not a GitHub repository", remote, url))
+}
+
+/// Parse `https://github.com/o/r(.git)`, `git@github.com:o/r.git` or
+/// `ssh://git@github.com/o/r.git` into (owner, repo). The host may also be
+/// the one the `api` base URL is on.
+pub fn parse_remote_url(url: &str, api: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    let path = web_hosts(api).into_iter().find_map(|host| {
+        url.strip_prefix(&format!("git@{}:", host))
+            .or_else(|| url.split_once(&format!("{}/", host)).map(|(_, p)| p))
+    })?;
+    let mut parts = path.trim_end_matches('/').split('/');
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    let repo = parts.next()?.trim_end_matches(".git");
+    if repo.is_empty() || parts.next().is_some() {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+/// Parse `https://github.com/o/r/pull/N` into (owner, repo, number). The
+/// host may also be the one the `api` base URL is on.
+pub fn parse_pr_url(url: &str, api: &str) -> Option<(String, String, u64)> {
+    let url = url.trim();
+    let path = web_hosts(api)
+        .into_iter()
+        .find_map(|host| url.split_once(&format!("{}/", host)).map(|(_, p)| p))?;
+    let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    match parts[..] {
+        [owner, repo, "pull", n, ..] => {
+            Some((owner.to_string(), repo.to_string(), n.parse().ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// The pull request title for a task.
+pub fn title(task: &Task) -> String {
+    squash::title(task)
+}
+
+/// The pull request body: the original prompt, the linked issue and the
+/// Done notes from the task's checkpoints.
+pub fn body(repo_root: &Path, task: &Task) -> String {
+    let mut out = Vec::new();
+    let prompt = handoff::original_prompt(&task.prompt);
+    if !prompt.is_empty() {
+        out.push(prompt);
+        out.push(String::new());
+    }
+    if !task.issue_url.is_empty() {
+        out.push(format!("Issue: {}", task.issue_url));
+        out.push(String::new());
+    }
+
+    let mut done: Vec<String> = Vec::new();
+    for cp in checkpoint::list(repo_root, task.id).unwrap_or_default() {
+        for item in checkpoint::done_items(&cp.annotation) {
+            if !done.contains(&item) {
+                done.push(item);
+            }
+        }
+    }
+    if !done.is_empty() {
+        out.push("## Progress".to_string());
+        out.push(String::new());
+        out.extend(done.iter().map(|d| format!("- {}", d)));
+        out.push(String::new());
+    }
+
+    out.push(format!(
+        "_Worked on by {} in pit task `{}`._",
+        task.agent, task.name
+    ));
+    out.join("\n")
+}
+
+/// Open a pull request.
+pub fn create(api: &str, owner: &str, repo: &str, pr: &NewPullRequest) -> Result<PullRequest> {
+    let body = serde_json::json!({
+        "title": pr.title,
+        "body": pr.body,
+        "head": pr.head,
+        "base": pr.base,
+        "draft": pr.draft,
+    });
+    let resp = request(
+        "POST",
+        &format!("{}/repos/{}/{}/pulls", api, owner, repo),
+        Some(body),
+    )?;
+    parse_pull_request(&resp)
+}
+
+/// Find an existing pull request (in any state) for `head`.
+pub fn find(api: &str, owner: &str, repo: &str, head: &str) -> Result<Option<PullRequest>> {
+    let resp = request(
+        "GET",
+        &format!(
+            "{}/repos/{}/{}/pulls?state=all&head={}:{}",
+            api, owner, repo, owner, head
+        ),
+        None,
+    )?;
+    match resp.as_array().and_then(|prs| prs.first()) {
+        Some(pr) => parse_pull_request(pr).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Fetch the current state of the pull request at `url`.
+pub fn fetch(api: &str, url: &str) -> Result<PullRequest> {
+    let (owner, repo, number) = parse_pr_url(url, api)
+        .with_context(|| format!("not a GitHub pull request URL: {}", url))?;
+    let resp = request(
+        "GET",
+        &format!("{}/repos/{}/{}/pulls/{}", api, owner, repo, number),
+        None,
+    )?;
+    parse_pull_request(&resp)
+}
+
+fn parse_pull_request(v: &serde_json::Value) -> Result<PullRequest> {
+    let url = v["html_url"]
+        .as_str()
+        .context("GitHub response has no pull request URL")?;
+    let merged = v["merged"].as_bool().unwrap_or(false) || v["merged_at"].is_string();
+    let state = if merged {
+        "merged"
+    } else {
+        v["state"].as_str().unwrap_or("open")
+    };
+    Ok(PullRequest {
+        number: v["number"].as_u64().unwrap_or(0),
+        url: url.to_string(),
+        state: state.to_string(),
+    })
+}
+
+fn request(method: &str, url: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
+    let mut req = ureq::request(method, url)
+        .timeout(TIMEOUT)
+        .set("User-Agent", "pit-cli")
+        .set("Accept", "application/vnd.github+json");
+    if let Some(token) = super::config::get("github.token") {
+        req = req.set("Authorization", &format!("Bearer {}", token));
+    }
+    let resp = match body {
+        Some(b) => req.send_json(b),
+        None => req.call(),
+    };
+    match resp {
+        Ok(r) => r.into_json().context("failed to parse GitHub response"),
+        Err(ureq::Error::Status(code, r)) => {
+            let v: serde_json::Value = r.into_json().unwrap_or_default();
+            let mut msg = v["message"]
+                .as_str()
+                .unwrap_or("request failed")
+                .to_string();
+            if let Some(detail) = v["errors"][0]["message"].as_str() {
+                msg = format!("{}: {}", msg, detail);
+            }
+            bail!("GitHub API error ({}): {}", code, msg)
+        }
+        Err(e) => Err(e).context("failed to call GitHub API"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve canned JSON responses, one per connection, and return the
+    /// server's base URL plus a handle yielding the requests seen
+    /// ("METHOD /path" followed by the body).
+    fn mock_api(responses: Vec<(u16, String)>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut seen = Vec::new();
+            for (code, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(v) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                }
+                let mut req_body = vec![0; length];
+                reader.read_exact(&mut req_body).unwrap();
+                let parts: Vec<&str> = request_line.split_whitespace().collect();
+                seen.push(format!(
+                    "{} {}\n{}",
+                    parts[0],
+                    parts[1],
+                    String::from_utf8_lossy(&req_body)
+                ));
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    code,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            seen
+        });
+        (base, handle)
+    }
+
+    #[test]
+    fn parse_remote_urls() {
+        let want = Some(("o".to_string(), "r".to_string()));
+        let parse = |url| parse_remote_url(url, DEFAULT_API);
+        assert_eq!(parse("https://github.com/o/r.git"), want);
+        assert_eq!(parse("https://github.com/o/r"), want);
+        assert_eq!(parse("git@github.com:o/r.git"), want);
+        assert_eq!(parse("ssh://git@github.com/o/r.git"), want);
+        assert_eq!(parse("/tmp/bare.git"), None);
+        assert_eq!(parse("https://gitlab.com/o/r.git"), None);
+        assert_eq!(parse("git@ghe.example.com:o/r.git"), None);
+    }
+
+    #[test]
+    fn parse_remote_urls_on_github_enterprise() {
+        let want = Some(("o".to_string(), "r".to_string()));
+        for api in [
+            "https://ghe.example.com/api/v3",
+            "https://api.ghe.example.com",
+        ] {
+            assert_eq!(parse_remote_url("git@ghe.example.com:o/r.git", api), want);
+            assert_eq!(parse_remote_url("https://ghe.example.com/o/r", api), want);
+            assert_eq!(parse_remote_url("https://github.com/o/r", api), want);
+            assert_eq!(parse_remote_url("git@other.example.com:o/r.git", api), None);
+        }
+        assert_eq!(
+            parse_pr_url(
+                "https://ghe.example.com/o/r/pull/3",
+                "https://ghe.example.com/api/v3"
+            ),
+            Some(("o".to_string(), "r".to_string(), 3))
+        );
+    }
+
+    #[test]
+    fn parse_pr_urls() {
+        assert_eq!(
+            parse_pr_url("https://github.com/o/r/pull/12", DEFAULT_API),
+            Some(("o".to_string(), "r".to_string(), 12))
+        );
+        assert_eq!(
+            parse_pr_url("https://github.com/o/r/issues/12", DEFAULT_API),
+            None
+        );
+    }
+
+    #[test]
+    fn create_posts_pull_request() {
+        let (api, server) = mock_api(vec![(
+            201,
+            r#"{"number":5,"html_url":"https://github.com/o/r/pull/5","state":"open"}"#.to_string(),
+        )]);
+        let pr = create(
+            &api,
+            "o",
+            "r",
+            &NewPullRequest {
+                title: "Fix login".into(),
+                body: "body".into(),
+                head: "pit/login".into(),
+                base: "main".into(),
+                draft: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(pr.number, 5);
+        assert_eq!(pr.state, "open");
+
+        let seen = server.join().unwrap();
+        assert!(seen[0].starts_with("POST /repos/o/r/pulls\n"));
+        let sent: serde_json::Value =
+            serde_json::from_str(seen[0].split_once('\n').unwrap().1).unwrap();
+        assert_eq!(sent["head"], "pit/login");
+        assert_eq!(sent["title"], "Fix login");
+    }
+
+    #[test]
+    fn fetch_reports_merged_and_errors() {
+        let (api, server) = mock_api(vec![
+            (
+                200,
+                r#"{"number":5,"html_url":"https://github.com/o/r/pull/5","state":"closed","merged":true}"#
+                    .to_string(),
+            ),
+            (404, r#"{"message":"Not Found"}"#.to_string()),
+        ]);
+        let pr = fetch(&api, "https://github.com/o/r/pull/5").unwrap();
+        assert_eq!(pr.state, "merged");
+        let err = fetch(&api, "https://github.com/o/r/pull/6")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("404") && err.contains("Not Found"), "{}", err);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn body_includes_prompt_issue_and_progress() {
+        let repo = tempfile::tempdir().unwrap();
+        let task = Task {
+            id: 1,
+            name: "login".into(),
+            description: String::new(),
+            prompt: "Fix the login timeout".into(),
+            issue_url: "https://github.com/o/r/issues/7".into(),
+            agent: "claude".into(),
+            model: String::new(),
+            branch: "pit/login".into(),
+            worktree: String::new(),
+            status: super::super::task::Status::Idle,
+            session_id: None,
+            tmux_session: None,
+            pid: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            pr_url: String::new(),
+            pr_state: String::new(),
+            test_status: String::new(),
+            test_duration_ms: 0,
+            test_summary: String::new(),
+            tested_at: String::new(),
+            source_file: String::new(),
+            source_line: 0,
+            source_marker: String::new(),
+            adopted: false,
+            parent_id: None,
+            fork_point: String::new(),
+            archived_at: String::new(),
+        };
+        let b = body(repo.path(), &task);
+        assert!(b.starts_with("Fix the login timeout\n\nIssue: https://github.com/o/r/issues/7"));
+        assert!(b.contains("pit task `login`"));
+        assert_eq!(title(&task), "Fix the login timeout");
+    }
+}
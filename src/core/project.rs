@@ -40,6 +40,7 @@ impl Project {
 
         let db_path = pit_dir.join(DB_FILE);
         let db = crate::db::open(&db_path)?;
+        reserve_task_ids(&db, repo_root)?;
 
         Ok(Project {
             repo_root: repo_root.to_path_buf(),
@@ -61,6 +62,15 @@ impl Project {
         }
 
         let db = crate::db::open(&db_path)?;
+        reserve_task_ids(&db, repo_root)?;
+
+        // Checkpoints used to be tags; move any left over under refs/pit/
+        if let Ok(tasks) = super::task::list(&db) {
+            let _ = super::checkpoint::migrate_tags(repo_root, &tasks);
+            // Tasks from before their metadata was kept in git
+            let archived = super::task::list_archived(&db).unwrap_or_default();
+            let _ = super::meta::backfill(repo_root, &[tasks, archived].concat());
+        }
 
         Ok(Project {
             repo_root: repo_root.to_path_buf(),
@@ -69,6 +79,34 @@ impl Project {
         })
     }
 
+    /// Move the database aside to `pit.db.bak` (replacing an older backup)
+    /// and start an empty one. Returns the backup's path, if there was a
+    /// database.
+    pub fn reset_db(repo_root: &Path) -> Result<(Self, Option<PathBuf>)> {
+        let pit_dir = repo_root.join(PIT_DIR);
+        let db_path = pit_dir.join(DB_FILE);
+        let backup = pit_dir.join(format!("{}.bak", DB_FILE));
+
+        let moved = if db_path.exists() {
+            std::fs::rename(&db_path, &backup)
+                .with_context(|| format!("failed to move {} aside", db_path.display()))?;
+            // SQLite finds the WAL by the database's name
+            for suffix in ["-wal", "-shm"] {
+                let from = pit_dir.join(format!("{}{}", DB_FILE, suffix));
+                let to = pit_dir.join(format!("{}.bak{}", DB_FILE, suffix));
+                let _ = std::fs::remove_file(&to);
+                if from.exists() {
+                    std::fs::rename(&from, &to)?;
+                }
+            }
+            Some(backup)
+        } else {
+            None
+        };
+
+        Ok((Self::init(repo_root)?, moved))
+    }
+
     /// Find the repo root by walking up from `start` looking for `.git`.
     pub fn find_repo_root(start: &Path) -> Result<PathBuf> {
         let mut dir = start.to_path_buf();
@@ -89,7 +127,15 @@ impl Project {
     }
 }
 
-/// Ensure `.pit` and `.pit-prompt` are listed in `.gitignore`.
+/// Keep new task IDs clear of the tasks that have refs in git, so a new
+/// DB (after `.pit/pit.db` was lost) doesn't hand a new task the metadata
+/// and checkpoints of a task `pit repair` could bring back.
+fn reserve_task_ids(db: &Connection, repo_root: &Path) -> Result<()> {
+    let highest = super::checkpoint::highest_task_id(repo_root).unwrap_or(0);
+    super::task::reserve_ids(db, highest)
+}
+
+/// Ensure `.pit`, `.pit-prompt` and `.pit-context.md` are listed in `.gitignore`.
 fn ensure_gitignored(repo_root: &Path) -> Result<()> {
     let gitignore = repo_root.join(".gitignore");
     let mut content = if gitignore.exists() {
@@ -98,7 +144,7 @@ fn ensure_gitignored(repo_root: &Path) -> Result<()> {
         String::new()
     };
 
-    let entries = [".pit", ".pit-prompt"];
+    let entries = [".pit", ".pit-prompt", super::issues::CONTEXT_FILE];
     let mut needs_write = false;
 
     for entry in &entries {
@@ -252,6 +298,39 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn new_db_skips_ids_of_tasks_still_in_git() {
+        use crate::core::{meta, task};
+        let repo = make_git_repo();
+        let project = Project::init(repo.path()).unwrap();
+        let create = |db: &Connection, name: &str| {
+            task::create(
+                db,
+                repo.path(),
+                &task::CreateOpts {
+                    name,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let lost = create(&project.db, "lost");
+        drop(project);
+
+        // The DB is lost; bare `pit` would start a new one
+        std::fs::remove_file(repo.path().join(".pit/pit.db")).unwrap();
+        let project = Project::init(repo.path()).unwrap();
+        let fresh = create(&project.db, "fresh");
+        assert!(fresh.id > lost.id);
+        let metas = meta::load_all(repo.path()).unwrap();
+        assert!(metas.iter().any(|m| m.id == lost.id && m.name == "lost"));
+
+        // Reopening doesn't move the sequence backwards
+        drop(project);
+        let project = Project::open(repo.path()).unwrap();
+        assert!(create(&project.db, "next").id > fresh.id);
+    }
+
     #[test]
     fn gitignore_preserves_existing_content() {
         let repo = make_git_repo();
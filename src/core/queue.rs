@@ -0,0 +1,297 @@
+//! Merge queue: land finished tasks onto the base branch one at a time.
+//!
+//! Each queued task is rebased onto the base branch as left by the previous
+//! merge, checked in its worktree with the configured lint and test commands, and
+//! fast-forwarded into the base only if that passes. A task that fails stays
+//! queued with the reason, and the rest of the queue carries on.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::process::Command;
+
+use super::checks;
+use super::git_info;
+use super::sync;
+use super::task::{self, Status, Task};
+
+/// A queued task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub task_id: i64,
+    pub position: i64,
+    /// Why the last attempt to land it failed; empty if not tried yet.
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// Merged into the base branch with this many commits.
+    Merged {
+        commits: usize,
+    },
+    Failed(String),
+}
+
+/// Add a task to the end of the queue. Already queued tasks keep their place.
+pub fn add(db: &Connection, task_id: i64) -> Result<()> {
+    db.execute(
+        "INSERT OR IGNORE INTO merge_queue (task_id, position)
+         SELECT ?1, COALESCE(MAX(position), 0) + 1 FROM merge_queue",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
+/// Take a task out of the queue.
+pub fn remove(db: &Connection, task_id: i64) -> Result<()> {
+    db.execute(
+        "DELETE FROM merge_queue WHERE task_id = ?1",
+        params![task_id],
+    )?;
+    Ok(())
+}
+
+/// Empty the queue.
+pub fn clear(db: &Connection) -> Result<()> {
+    db.execute("DELETE FROM merge_queue", [])?;
+    Ok(())
+}
+
+/// The queue, in landing order.
+pub fn list(db: &Connection) -> Result<Vec<Entry>> {
+    let mut stmt =
+        db.prepare("SELECT task_id, position, error FROM merge_queue ORDER BY position ASC")?;
+    let entries = stmt
+        .query_map([], |r| {
+            Ok(Entry {
+                task_id: r.get(0)?,
+                position: r.get(1)?,
+                error: r.get(2)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+fn set_error(db: &Connection, task_id: i64, error: &str) -> Result<()> {
+    db.execute(
+        "UPDATE merge_queue SET error = ?1 WHERE task_id = ?2",
+        params![error, task_id],
+    )?;
+    Ok(())
+}
+
+/// Land every queued task in order. `report` is called with `None` before a
+/// task is tried and with its outcome after. Merged tasks are marked done and
+/// leave the queue; failed ones stay with the error recorded.
+pub fn run(
+    db: &Connection,
+    repo_root: &Path,
+    checks: &checks::Commands,
+    mut report: impl FnMut(&Task, Option<&Outcome>),
+) -> Result<Vec<(Task, Outcome)>> {
+    let mut results = Vec::new();
+    for entry in list(db)? {
+        let Some(t) = task::get(db, entry.task_id)? else {
+            remove(db, entry.task_id)?;
+            continue;
+        };
+        report(&t, None);
+        let outcome = land(db, repo_root, &t, checks);
+        match &outcome {
+            Outcome::Merged { .. } => {
+                task::set_status(db, t.id, &Status::Done)?;
+                remove(db, t.id)?;
+            }
+            Outcome::Failed(error) => set_error(db, t.id, error)?,
+        }
+        report(&t, Some(&outcome));
+        results.push((t, outcome));
+    }
+    Ok(results)
+}
+
+/// Rebase, test and fast-forward one task into the base branch. The test
+/// result is recorded on the task.
+pub fn land(db: &Connection, repo_root: &Path, task: &Task, checks: &checks::Commands) -> Outcome {
+    match try_land(db, repo_root, task, checks) {
+        Ok(outcome) => outcome,
+        Err(e) => Outcome::Failed(format!("{:#}", e)),
+    }
+}
+
+fn try_land(
+    db: &Connection,
+    repo_root: &Path,
+    task: &Task,
+    checks: &checks::Commands,
+) -> Result<Outcome> {
+    if task.status == Status::Running {
+        return Ok(Outcome::Failed("task is running — stop it first".into()));
+    }
+    let worktree = Path::new(&task.worktree);
+    if !git(worktree, &["status", "--porcelain"])?.is_empty() {
+        return Ok(Outcome::Failed(
+            "uncommitted changes in the worktree — commit or discard them first".into(),
+        ));
+    }
+
+    if let sync::Outcome::Conflicts { base, files } =
+        sync::sync(repo_root, task, sync::Strategy::Rebase, false)?
+    {
+        return Ok(Outcome::Failed(format!(
+            "conflicts with {} in {}",
+            base,
+            files.join(", ")
+        )));
+    }
+
+    let base = git_info::detect_main_branch(repo_root)?;
+    let commits: usize = git(
+        repo_root,
+        &["rev-list", "--count", &format!("{}..{}", base, task.branch)],
+    )?
+    .parse()
+    .unwrap_or(0);
+    if commits == 0 {
+        return Ok(Outcome::Failed(format!(
+            "no commits to merge into {}",
+            base
+        )));
+    }
+
+    if !checks.is_empty() {
+        let report = checks::run(checks, worktree, true)?;
+        checks::record(db, task.id, &report)?;
+        if !report.passed {
+            let reason = report.summary.lines().next().unwrap_or("checks failed");
+            return Ok(Outcome::Failed(reason.to_string()));
+        }
+    }
+
+    fast_forward(repo_root, &base, &task.branch)?;
+    Ok(Outcome::Merged { commits })
+}
+
+/// Move `base` up to `branch`, updating the main checkout if it is on `base`.
+fn fast_forward(repo_root: &Path, base: &str, branch: &str) -> Result<()> {
+    let current = git(repo_root, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok();
+    if current.as_deref() == Some(base) {
+        git(repo_root, &["merge", "--ff-only", "--quiet", branch])?;
+    } else {
+        let old = git(repo_root, &["rev-parse", base])?;
+        let new = git(repo_root, &["rev-parse", branch])?;
+        git(
+            repo_root,
+            &["update-ref", &format!("refs/heads/{}", base), &new, &old],
+        )?;
+    }
+    Ok(())
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (tempfile::TempDir, Connection) {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@test.com"],
+            &["config", "user.name", "Test"],
+            &["commit", "--allow-empty", "-m", "init"],
+            &["branch", "-M", "main"],
+        ] {
+            git(dir.path(), args).unwrap();
+        }
+        (dir, crate::db::open_memory().unwrap())
+    }
+
+    fn task_with_commit(db: &Connection, repo: &Path, name: &str, file: &str) -> Task {
+        let t = task::create(
+            db,
+            repo,
+            &task::CreateOpts {
+                name,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let wt = Path::new(&t.worktree);
+        std::fs::write(wt.join(file), name).unwrap();
+        git(wt, &["add", file]).unwrap();
+        git(wt, &["commit", "-m", name]).unwrap();
+        t
+    }
+
+    #[test]
+    fn queue_keeps_order_and_places() {
+        let (repo, db) = setup();
+        let a = task_with_commit(&db, repo.path(), "a", "a.txt");
+        let b = task_with_commit(&db, repo.path(), "b", "b.txt");
+        add(&db, b.id).unwrap();
+        add(&db, a.id).unwrap();
+        add(&db, b.id).unwrap();
+        let order: Vec<i64> = list(&db).unwrap().iter().map(|e| e.task_id).collect();
+        assert_eq!(order, vec![b.id, a.id]);
+
+        // Deleting a task drops it from the queue
+        task::delete(&db, repo.path(), b.id).unwrap();
+        let order: Vec<i64> = list(&db).unwrap().iter().map(|e| e.task_id).collect();
+        assert_eq!(order, vec![a.id]);
+    }
+
+    #[test]
+    fn lands_in_order_and_keeps_failures_queued() {
+        let (repo, db) = setup();
+        let first = task_with_commit(&db, repo.path(), "first", "first.txt");
+        let broken = task_with_commit(&db, repo.path(), "broken", "broken.txt");
+        let second = task_with_commit(&db, repo.path(), "second", "second.txt");
+        for t in [&first, &broken, &second] {
+            add(&db, t.id).unwrap();
+        }
+
+        let mut started = Vec::new();
+        let checks = checks::Commands {
+            test: Some("test ! -f broken.txt".into()),
+            lint: None,
+        };
+        let results = run(&db, repo.path(), &checks, |t, o| {
+            if o.is_none() {
+                started.push(t.name.clone());
+            }
+        })
+        .unwrap();
+        assert_eq!(started, vec!["first", "broken", "second"]);
+        assert_eq!(results[0].1, Outcome::Merged { commits: 1 });
+        assert!(matches!(&results[1].1, Outcome::Failed(e) if e.contains("exit code 1")));
+        // Rebased onto the first merge, so both land on main
+        assert_eq!(results[2].1, Outcome::Merged { commits: 1 });
+        let log = git(repo.path(), &["log", "--format=%s", "main"]).unwrap();
+        assert_eq!(log, "second\nfirst\ninit");
+        assert!(repo.path().join("second.txt").exists());
+
+        let left = list(&db).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].task_id, broken.id);
+        assert!(left[0].error.contains("exit code 1"));
+        let first = task::get(&db, first.id).unwrap().unwrap();
+        assert_eq!(first.status, Status::Done);
+        assert_eq!(first.test_status, "pass");
+    }
+}
@@ -3,11 +3,13 @@ use rusqlite::Connection;
 use std::path::Path;
 
 use super::checkpoint;
+use super::checks;
 use super::task::{self, Status};
 use super::tmux;
 
 /// Check all "running" tasks and mark as "idle" if their tmux session is gone.
-/// Auto-checkpoints when an agent exits with new commits.
+/// Auto-checkpoints when an agent exits with new commits, and starts the
+/// configured checks in the background.
 /// Returns the number of tasks reaped.
 pub fn reap_dead(db: &Connection, repo_root: &Path) -> Result<usize> {
     let tasks = task::list(db)?;
@@ -26,7 +28,7 @@ pub fn reap_dead(db: &Connection, repo_root: &Path) -> Result<usize> {
         if !is_alive {
             // Auto-checkpoint if the agent made new commits
             let worktree = Path::new(&t.worktree);
-            if checkpoint::has_new_commits(repo_root, &t.name, &t.branch) {
+            if checkpoint::has_new_commits(repo_root, t.id, &t.branch) {
                 // Try to capture last agent output (session may already be gone)
                 let agent_output = t
                     .tmux_session
@@ -34,6 +36,7 @@ pub fn reap_dead(db: &Connection, repo_root: &Path) -> Result<usize> {
                     .and_then(|name| tmux::capture_pane(name, 50).ok());
                 let _ = checkpoint::create(
                     repo_root,
+                    t.id,
                     &t.name,
                     &t.branch,
                     worktree,
@@ -42,6 +45,9 @@ pub fn reap_dead(db: &Connection, repo_root: &Path) -> Result<usize> {
             }
 
             task::set_status(db, t.id, &Status::Idle)?;
+            if !checks::Commands::from_config(repo_root).is_empty() {
+                let _ = checks::spawn(db, repo_root, t);
+            }
             reaped += 1;
         }
     }
@@ -0,0 +1,349 @@
+//! Rebuild task rows from git, for when `.pit/pit.db` is lost or corrupted.
+//!
+//! Tasks come back from their metadata refs (see [`meta`]) with their prompt,
+//! agent and links intact. `pit/*` branches, worktrees in the worktree root
+//! and archived branches without metadata come back with defaults. Checkpoint
+//! refs are keyed by task ID, so recovered tasks keep the ID their checkpoints
+//! were saved under, and a missing worktree is added again.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::meta::{self, Meta};
+use super::task::{self, Task};
+use super::worktrees;
+
+/// A task put back by [`recover`].
+#[derive(Debug)]
+pub struct Recovered {
+    pub task: Task,
+    /// What it was recovered from: "metadata", "branch", "worktree" or
+    /// "archive".
+    pub from: &'static str,
+    pub archived: bool,
+}
+
+/// Add a row for every task found in git that the DB doesn't have.
+pub fn recover(db: &Connection, repo_root: &Path) -> Result<Vec<Recovered>> {
+    let mut known = task::list(db)?;
+    known.extend(task::list_archived(db)?);
+
+    let branches = lines(git(
+        repo_root,
+        &["for-each-ref", "--format=%(refname:strip=2)", "refs/heads/"],
+    )?);
+    let archived = lines(git(
+        repo_root,
+        &[
+            "for-each-ref",
+            "--format=%(refname:strip=3)",
+            "refs/pit/archive/",
+        ],
+    )?);
+    let linked = linked_worktrees(repo_root)?;
+    let checkpoint_ids = checkpoint_ids(repo_root)?;
+    let managed = worktrees::roots(repo_root);
+    let default_worktree = |name: &str| {
+        worktrees::path(repo_root, name)
+            .to_string_lossy()
+            .to_string()
+    };
+
+    let mut found: Vec<(Meta, &'static str)> = meta::load_all(repo_root)?
+        .into_iter()
+        .map(|m| (m, "metadata"))
+        .collect();
+    let add = |found: &mut Vec<(Meta, &'static str)>, meta: Meta, from| {
+        if !found
+            .iter()
+            .any(|(m, _)| m.name == meta.name || m.branch == meta.branch)
+        {
+            let id = checkpoint_ids
+                .get(&meta.name)
+                .copied()
+                .filter(|id| !found.iter().any(|(m, _)| m.id == *id))
+                .unwrap_or(0);
+            found.push((Meta { id, ..meta }, from));
+        }
+    };
+    for branch in &branches {
+        if let Some(name) = branch.strip_prefix("pit/") {
+            let meta = Meta {
+                name: name.to_string(),
+                branch: branch.clone(),
+                worktree: default_worktree(name),
+                ..Default::default()
+            };
+            add(&mut found, meta, "branch");
+        }
+    }
+    for (path, branch) in &linked {
+        let (Some(branch), Some(name)) = (branch, path.file_name()) else {
+            continue;
+        };
+        if !managed.iter().any(|m| path.parent() == Some(m.as_path())) {
+            continue;
+        }
+        let meta = Meta {
+            name: name.to_string_lossy().to_string(),
+            branch: branch.clone(),
+            worktree: path.to_string_lossy().to_string(),
+            adopted: true,
+            ..Default::default()
+        };
+        add(&mut found, meta, "worktree");
+    }
+    for name in &archived {
+        let meta = Meta {
+            name: name.clone(),
+            branch: format!("pit/{}", name),
+            worktree: default_worktree(name),
+            ..Default::default()
+        };
+        add(&mut found, meta, "archive");
+    }
+
+    // Tasks with known IDs first, so new IDs don't take theirs
+    found.sort_by_key(|(m, _)| (m.id == 0, m.id));
+    let mut recovered = Vec::new();
+    for (mut meta, from) in found {
+        if known
+            .iter()
+            .any(|t| t.name == meta.name || t.branch == meta.branch)
+        {
+            continue;
+        }
+        let is_archived = archived.contains(&meta.name);
+        if !is_archived {
+            if !branches.contains(&meta.branch) {
+                // Deleted by hand; nothing left to recover
+                continue;
+            }
+            match linked
+                .iter()
+                .find(|(_, b)| b.as_deref() == Some(meta.branch.as_str()))
+            {
+                Some((path, _)) => meta.worktree = path.to_string_lossy().to_string(),
+                None => {
+                    if meta.worktree.is_empty() {
+                        meta.worktree = default_worktree(&meta.name);
+                    }
+                    let _ = git(repo_root, &["worktree", "prune"]);
+                    git(
+                        repo_root,
+                        &["worktree", "add", &meta.worktree, &meta.branch],
+                    )
+                    .with_context(|| format!("failed to add a worktree for '{}'", meta.name))?;
+                }
+            }
+        }
+        if known.iter().any(|t| t.id == meta.id) {
+            meta.id = 0;
+        }
+        // The archive ref decides; the metadata only has the time
+        if !is_archived {
+            meta.archived_at.clear();
+        }
+
+        let t = task::insert_recovered(db, &meta)?;
+        if is_archived && t.archived_at.is_empty() {
+            task::set_archived(db, t.id, true)?;
+        }
+        known.push(t.clone());
+        recovered.push((meta.parent_id, from, is_archived, t.id));
+    }
+
+    // Fork links, now that parents are back
+    let mut out = Vec::new();
+    for (parent_id, from, archived, id) in recovered {
+        let mut t = task::get(db, id)?.context("task disappeared after recovery")?;
+        if let Some(parent) = parent_id.filter(|p| known.iter().any(|k| k.id == *p)) {
+            task::set_parent(db, id, parent, &t.fork_point)?;
+            t.parent_id = Some(parent);
+        }
+        out.push(Recovered {
+            task: t,
+            from,
+            archived,
+        });
+    }
+    Ok(out)
+}
+
+/// Task IDs that checkpoints were saved under, by task name (read from the
+/// checkpoint tags' names, `pit/checkpoint/<name>/<N>`).
+fn checkpoint_ids(repo_root: &Path) -> Result<HashMap<String, i64>> {
+    let refs = git(
+        repo_root,
+        &["for-each-ref", "--format=%(refname) %(tag)", "refs/pit/"],
+    )?;
+    let mut ids = HashMap::new();
+    for line in refs.lines() {
+        let Some((refname, tag)) = line.split_once(' ') else {
+            continue;
+        };
+        let id = refname
+            .strip_prefix("refs/pit/")
+            .and_then(|r| r.split_once("/checkpoints/"))
+            .and_then(|(id, _)| id.parse::<i64>().ok());
+        let name = tag
+            .strip_prefix("pit/checkpoint/")
+            .and_then(|t| t.rsplit_once('/'))
+            .map(|(name, _)| name.to_string());
+        if let (Some(id), Some(name)) = (id, name) {
+            ids.insert(name, id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Linked worktrees and their branches (the main checkout is left out).
+fn linked_worktrees(repo_root: &Path) -> Result<Vec<(PathBuf, Option<String>)>> {
+    let mut out: Vec<(PathBuf, Option<String>)> = Vec::new();
+    for line in git(repo_root, &["worktree", "list", "--porcelain"])?.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            out.push((PathBuf::from(path), None));
+        } else if let (Some(b), Some(w)) = (line.strip_prefix("branch refs/heads/"), out.last_mut())
+        {
+            w.1 = Some(b.to_string());
+        }
+    }
+    Ok(out.into_iter().skip(1).collect())
+}
+
+fn lines(s: String) -> Vec<String> {
+    s.lines()
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{archive, checkpoint, fork};
+    use task::CreateOpts;
+
+    fn setup() -> (tempfile::TempDir, Connection) {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@test.com"],
+            &["config", "user.name", "Test"],
+            &["commit", "--allow-empty", "-m", "init"],
+            &["branch", "-M", "main"],
+        ] {
+            git(dir.path(), args).unwrap();
+        }
+        (dir, crate::db::open_memory().unwrap())
+    }
+
+    #[test]
+    fn rebuilds_tasks_from_metadata_branches_and_archives() {
+        let (dir, db) = setup();
+        let repo = dir.path();
+        let parser = task::create(
+            &db,
+            repo,
+            &CreateOpts {
+                name: "parser",
+                prompt: "write the parser",
+                agent: "codex",
+                model: "o3",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        checkpoint::create(
+            repo,
+            parser.id,
+            "parser",
+            &parser.branch,
+            Path::new(&parser.worktree),
+            None,
+        )
+        .unwrap();
+        let alt = fork::fork(&db, repo, &parser, "alt", Some(1)).unwrap();
+        let shelved = task::create(
+            &db,
+            repo,
+            &CreateOpts {
+                name: "shelved",
+                prompt: "maybe later",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        archive::archive(&db, repo, &shelved).unwrap();
+        // A task from before metadata was kept: only its branch and
+        // checkpoints are left
+        let old = task::create(
+            &db,
+            repo,
+            &CreateOpts {
+                name: "old",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        checkpoint::create(
+            repo,
+            old.id,
+            "old",
+            &old.branch,
+            Path::new(&old.worktree),
+            None,
+        )
+        .unwrap();
+        git(repo, &["update-ref", "-d", &meta::meta_ref(old.id)]).unwrap();
+        git(repo, &["worktree", "remove", "--force", &old.worktree]).unwrap();
+
+        let fresh = crate::db::open_memory().unwrap();
+        let recovered = recover(&fresh, repo).unwrap();
+        assert_eq!(recovered.len(), 4);
+
+        let p = task::get_by_name(&fresh, "parser").unwrap().unwrap();
+        assert_eq!(p.id, parser.id);
+        assert_eq!(p.prompt, "write the parser");
+        assert_eq!((p.agent.as_str(), p.model.as_str()), ("codex", "o3"));
+        assert_eq!(checkpoint::list(repo, p.id).unwrap().len(), 1);
+
+        let a = task::get_by_name(&fresh, "alt").unwrap().unwrap();
+        assert_eq!(a.parent_id, Some(parser.id));
+        assert_eq!(a.fork_point, alt.fork_point);
+
+        let s = task::get_archived(&fresh, "shelved").unwrap().unwrap();
+        assert_eq!(s.prompt, "maybe later");
+        assert!(recovered
+            .iter()
+            .any(|r| r.task.name == "shelved" && r.archived));
+
+        let o = task::get_by_name(&fresh, "old").unwrap().unwrap();
+        assert_eq!(o.id, old.id);
+        assert_eq!(o.agent, "claude");
+        assert!(Path::new(&o.worktree).exists());
+        assert!(recovered
+            .iter()
+            .any(|r| r.task.name == "old" && r.from == "branch"));
+
+        // Nothing more to recover the second time
+        assert!(recover(&fresh, repo).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,372 @@
+//! Squash a task branch into a clean history before review.
+//!
+//! The branch is rewritten into one commit, or one per checkpoint, with
+//! messages built from the original prompt, the linked issue and the
+//! checkpoints' Done notes. The tree at the tip never changes, so the
+//! worktree (uncommitted work included) is left alone. The old tip is saved
+//! to `refs/pit/<task-id>/pre-squash` first so the squash can be undone.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use super::checkpoint::{self, Checkpoint};
+use super::handoff;
+use super::task::Task;
+
+/// Longest subject line we generate.
+const SUBJECT_WIDTH: usize = 72;
+
+/// One commit of the rewritten history.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Group {
+    /// Full hashes of the commits folded into this one, oldest first.
+    pub commits: Vec<String>,
+    /// Subjects of those commits.
+    pub subjects: Vec<String>,
+    pub message: String,
+}
+
+/// What `apply` would do to a task branch.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    /// Commit the branch forked from; the new history starts here.
+    pub base: String,
+    /// Current branch tip.
+    pub tip: String,
+    pub groups: Vec<Group>,
+}
+
+impl Plan {
+    pub fn commit_count(&self) -> usize {
+        self.groups.iter().map(|g| g.commits.len()).sum()
+    }
+}
+
+fn pre_squash_ref(task_id: i64) -> String {
+    format!("{}pre-squash", checkpoint::task_refs(task_id))
+}
+
+/// Work out the rewritten history. With `by_checkpoint`, commits are grouped
+/// at each checkpoint instead of folded into a single commit.
+pub fn plan(repo_root: &Path, task: &Task, by_checkpoint: bool) -> Result<Plan> {
+    let main = super::git_info::detect_main_branch(repo_root)?;
+    let base = git(repo_root, &["merge-base", &main, &task.branch])?;
+    let tip = git(repo_root, &["rev-parse", &task.branch])?;
+    let log = git(
+        repo_root,
+        &[
+            "log",
+            "--reverse",
+            "--format=%H %s",
+            &format!("{}..{}", base, tip),
+        ],
+    )?;
+    let commits: Vec<(String, String)> = log
+        .lines()
+        .filter_map(|l| l.split_once(' '))
+        .map(|(h, s)| (h.to_string(), s.to_string()))
+        .collect();
+    if commits.is_empty() {
+        bail!("'{}' has no commits to squash", task.branch);
+    }
+
+    // Full hash of the branch tip each checkpoint was taken at
+    let checkpoints: Vec<(String, Checkpoint)> = checkpoint::list(repo_root, task.id)?
+        .into_iter()
+        .filter_map(|c| {
+            let at = git(repo_root, &["rev-parse", &c.commit_hash]).ok()?;
+            Some((at, c))
+        })
+        .collect();
+
+    let mut groups = Vec::new();
+    let mut current = Group::default();
+    // Notes for the current group: Done items of its checkpoints, then the
+    // subjects of commits no checkpoint has described yet
+    let mut notes = Vec::new();
+    let mut uncovered = Vec::new();
+    for (hash, subject) in commits {
+        current.commits.push(hash.clone());
+        current.subjects.push(subject.clone());
+        uncovered.push(subject);
+
+        let here: Vec<&Checkpoint> = checkpoints
+            .iter()
+            .filter(|(at, _)| *at == hash)
+            .map(|(_, c)| c)
+            .collect();
+        if here.is_empty() {
+            continue;
+        }
+        for cp in &here {
+            notes.extend(checkpoint::done_items(&cp.annotation));
+        }
+        uncovered.clear();
+        if by_checkpoint {
+            current.message = group_message(&bullets(&notes));
+            groups.push(std::mem::take(&mut current));
+            notes.clear();
+        }
+    }
+    if !current.commits.is_empty() {
+        notes.append(&mut uncovered);
+        let items = bullets(&notes);
+        current.message = if groups.is_empty() {
+            task_message(task, &items)
+        } else {
+            group_message(&items)
+        };
+        groups.push(current);
+    }
+
+    Ok(Plan { base, tip, groups })
+}
+
+/// Rewrite the branch according to `plan`. Returns the new tip.
+pub fn apply(repo_root: &Path, task: &Task, plan: &Plan) -> Result<String> {
+    let mut parent = plan.base.clone();
+    for group in &plan.groups {
+        let last = group.commits.last().context("empty commit group")?;
+        let tree = format!("{}^{{tree}}", last);
+        parent = git(
+            repo_root,
+            &["commit-tree", &tree, "-p", &parent, "-m", &group.message],
+        )?;
+    }
+    git(
+        repo_root,
+        &["update-ref", &pre_squash_ref(task.id), &plan.tip],
+    )
+    .context("failed to save pre-squash ref")?;
+    git(
+        repo_root,
+        &[
+            "update-ref",
+            "-m",
+            "pit squash",
+            &format!("refs/heads/{}", task.branch),
+            &parent,
+            &plan.tip,
+        ],
+    )
+    .context("branch moved while squashing")?;
+    Ok(parent)
+}
+
+/// Put the branch back to where it was before the last squash.
+pub fn undo(repo_root: &Path, task: &Task) -> Result<()> {
+    let saved = pre_squash_ref(task.id);
+    let old = git(repo_root, &["rev-parse", "--verify", "-q", &saved])
+        .map_err(|_| anyhow::anyhow!("no squash to undo for '{}'", task.name))?;
+    let current = git(repo_root, &["rev-parse", &task.branch])?;
+    let same_tree = git(
+        repo_root,
+        &["diff", "--quiet", &format!("{}..{}", old, current)],
+    )
+    .is_ok();
+    if !same_tree {
+        bail!(
+            "'{}' has changed since it was squashed; reset it to {} by hand",
+            task.branch,
+            &old[..old.len().min(7)]
+        );
+    }
+    git(
+        repo_root,
+        &[
+            "update-ref",
+            "-m",
+            "pit squash --undo",
+            &format!("refs/heads/{}", task.branch),
+            &old,
+            &current,
+        ],
+    )?;
+    git(repo_root, &["update-ref", "-d", &saved])?;
+    Ok(())
+}
+
+/// Message for the whole task: a subject from the prompt, then the work done
+/// and the linked issue.
+fn task_message(task: &Task, items: &[String]) -> String {
+    let mut out = vec![title(task), String::new()];
+    out.extend(items.iter().cloned());
+    if !task.issue_url.is_empty() {
+        out.push(String::new());
+        out.push(format!("Issue: {}", task.issue_url));
+    }
+    out.join("\n").trim_end().to_string()
+}
+
+/// A one-line title for the task's work: the first line of its original
+/// prompt, shortened to a subject line.
+pub fn title(task: &Task) -> String {
+    let prompt = handoff::original_prompt(&task.prompt);
+    let first = prompt
+        .lines()
+        .map(|l| l.trim().trim_start_matches('#').trim())
+        .find(|l| !l.is_empty())
+        .unwrap_or(&task.name);
+    subject_line(first)
+}
+
+/// Message for one checkpoint's worth of commits, titled by its first item.
+fn group_message(items: &[String]) -> String {
+    let title = items
+        .first()
+        .map(|b| b.trim_start_matches("- ").to_string())
+        .unwrap_or_else(|| "Work in progress".to_string());
+    let mut out = vec![subject_line(&title)];
+    if items.len() > 1 {
+        out.push(String::new());
+        out.extend(items.iter().cloned());
+    }
+    out.join("\n")
+}
+
+/// Notes as a deduplicated bullet list. pit's own snapshot and auto-save
+/// commits are left out.
+fn bullets(notes: &[String]) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    for n in notes.iter().filter(|n| !n.starts_with("[pit checkpoint]")) {
+        let item = format!("- {}", n);
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    items
+}
+
+/// Shorten `title` to a subject line, cutting at a word boundary.
+fn subject_line(title: &str) -> String {
+    if title.chars().count() <= SUBJECT_WIDTH {
+        return title.to_string();
+    }
+    let cut: String = title.chars().take(SUBJECT_WIDTH - 1).collect();
+    let cut = match cut.rfind(' ') {
+        Some(i) if i > SUBJECT_WIDTH / 2 => &cut[..i],
+        _ => &cut,
+    };
+    format!("{}…", cut.trim_end())
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::task;
+
+    fn run(dir: &Path, args: &[&str]) {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+    }
+
+    fn setup() -> (tempfile::TempDir, Task) {
+        let dir = tempfile::tempdir().unwrap();
+        run(dir.path(), &["init", "-q"]);
+        run(dir.path(), &["config", "user.email", "test@test.com"]);
+        run(dir.path(), &["config", "user.name", "Test"]);
+        run(dir.path(), &["commit", "--allow-empty", "-m", "init"]);
+        run(dir.path(), &["branch", "-M", "main"]);
+        let db = crate::db::open_memory().unwrap();
+        let t = task::create(
+            &db,
+            dir.path(),
+            &task::CreateOpts {
+                name: "sq",
+                prompt: "Fix the login timeout\n\nAdd a retry.",
+                issue_url: "https://github.com/o/r/issues/7",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (dir, t)
+    }
+
+    fn commit(wt: &Path, file: &str, msg: &str) {
+        std::fs::write(wt.join(file), msg).unwrap();
+        run(wt, &["add", "-A"]);
+        run(wt, &["commit", "-q", "-m", msg]);
+    }
+
+    #[test]
+    fn squash_into_one_commit_and_undo() {
+        let (repo, t) = setup();
+        let wt = Path::new(&t.worktree);
+        commit(wt, "a.txt", "add retry");
+        commit(wt, "b.txt", "[pit checkpoint] auto-save for sq");
+        checkpoint::create(repo.path(), t.id, &t.name, &t.branch, wt, None).unwrap();
+        commit(wt, "c.txt", "add test");
+        std::fs::write(wt.join("wip.txt"), "wip").unwrap();
+
+        let p = plan(repo.path(), &t, false).unwrap();
+        assert_eq!(p.commit_count(), 3);
+        assert_eq!(p.groups.len(), 1);
+        let msg = &p.groups[0].message;
+        assert!(msg.starts_with("Fix the login timeout\n\n"), "{}", msg);
+        assert!(msg.contains("- add retry\n- add test"), "{}", msg);
+        assert!(!msg.contains("auto-save"));
+        assert!(msg.ends_with("Issue: https://github.com/o/r/issues/7"));
+
+        let old_tree = git(repo.path(), &["rev-parse", "pit/sq^{tree}"]).unwrap();
+        apply(repo.path(), &t, &p).unwrap();
+        let count = git(repo.path(), &["rev-list", "--count", "main..pit/sq"]).unwrap();
+        assert_eq!(count, "1");
+        let new_tree = git(repo.path(), &["rev-parse", "pit/sq^{tree}"]).unwrap();
+        assert_eq!(old_tree, new_tree);
+        assert!(wt.join("wip.txt").exists());
+
+        undo(repo.path(), &t).unwrap();
+        assert_eq!(git(repo.path(), &["rev-parse", "pit/sq"]).unwrap(), p.tip);
+        assert!(undo(repo.path(), &t).is_err());
+    }
+
+    #[test]
+    fn by_checkpoint_groups_at_checkpoints() {
+        let (repo, t) = setup();
+        let wt = Path::new(&t.worktree);
+        commit(wt, "a.txt", "one");
+        commit(wt, "b.txt", "two");
+        checkpoint::create(repo.path(), t.id, &t.name, &t.branch, wt, None).unwrap();
+        commit(wt, "c.txt", "three");
+
+        let p = plan(repo.path(), &t, true).unwrap();
+        assert_eq!(p.groups.len(), 2);
+        assert_eq!(p.groups[0].commits.len(), 2);
+        assert_eq!(p.groups[0].message, "one\n\n- one\n- two");
+        assert_eq!(p.groups[1].message, "three");
+    }
+
+    #[test]
+    fn nothing_to_squash() {
+        let (repo, t) = setup();
+        let err = plan(repo.path(), &t, false).unwrap_err().to_string();
+        assert!(err.contains("no commits to squash"), "{}", err);
+    }
+
+    #[test]
+    fn subject_line_cuts_at_word_boundary() {
+        let long = "word ".repeat(30);
+        let s = subject_line(&long);
+        assert!(s.chars().count() <= SUBJECT_WIDTH);
+        assert!(s.ends_with("word…"));
+        assert_eq!(subject_line("short"), "short");
+    }
+}
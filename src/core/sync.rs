@@ -0,0 +1,249 @@
+//! Bring a task branch up to date with its base branch.
+//!
+//! The worktree is checkpointed first, then the branch is rebased onto (or
+//! merged with) the current base inside the worktree. Uncommitted work is
+//! carried over with `--autostash`. On conflicts the operation is either
+//! aborted, leaving everything as it was, or left in progress for the agent
+//! to resolve.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use super::checkpoint;
+use super::git_info;
+use super::task::Task;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Rebase,
+    Merge,
+}
+
+impl Strategy {
+    fn verb(self) -> &'static str {
+        match self {
+            Strategy::Rebase => "rebase",
+            Strategy::Merge => "merge",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The branch already contains the base.
+    UpToDate,
+    /// The branch now contains the base's `behind` new commits.
+    Synced { base: String, behind: usize },
+    /// These files conflict. The rebase/merge was aborted unless
+    /// `keep_conflicts` was set.
+    Conflicts { base: String, files: Vec<String> },
+}
+
+/// How many commits `base` has that `branch` doesn't.
+pub fn behind_count(repo_root: &Path, base: &str, branch: &str) -> usize {
+    git(
+        repo_root,
+        &["rev-list", "--count", &format!("{}..{}", branch, base)],
+    )
+    .ok()
+    .and_then(|n| n.parse().ok())
+    .unwrap_or(0)
+}
+
+/// Checkpoint the task, then rebase or merge it onto the base branch.
+pub fn sync(
+    repo_root: &Path,
+    task: &Task,
+    strategy: Strategy,
+    keep_conflicts: bool,
+) -> Result<Outcome> {
+    let base = git_info::detect_main_branch(repo_root)?;
+    let behind = behind_count(repo_root, &base, &task.branch);
+    if behind == 0 {
+        return Ok(Outcome::UpToDate);
+    }
+
+    let worktree = Path::new(&task.worktree);
+    if checkpoint::changes_since_last(repo_root, task.id, worktree)?.files > 0 {
+        checkpoint::create(repo_root, task.id, &task.name, &task.branch, worktree, None)?;
+    }
+
+    let result = match strategy {
+        Strategy::Rebase => git(worktree, &["rebase", "--autostash", &base]),
+        Strategy::Merge => git(
+            worktree,
+            &["merge", "--autostash", "--no-edit", "--no-ff", &base],
+        ),
+    };
+    let Err(err) = result else {
+        return Ok(Outcome::Synced { base, behind });
+    };
+
+    let files = conflicted_files(worktree);
+    if files.is_empty() {
+        let _ = git(worktree, &[strategy.verb(), "--abort"]);
+        return Err(err.context(format!("{} onto {} failed", strategy.verb(), base)));
+    }
+    if !keep_conflicts {
+        git(worktree, &[strategy.verb(), "--abort"])
+            .with_context(|| format!("failed to abort the {}", strategy.verb()))?;
+    }
+    Ok(Outcome::Conflicts { base, files })
+}
+
+/// Files with unresolved conflicts in `worktree`.
+pub fn conflicted_files(worktree: &Path) -> Vec<String> {
+    git(worktree, &["diff", "--name-only", "--diff-filter=U"])
+        .map(|out| out.lines().map(|l| l.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Prompt asking the agent to finish a rebase/merge that stopped on
+/// conflicts.
+pub fn resolve_prompt(task: &Task, strategy: Strategy, base: &str, files: &[String]) -> String {
+    let finish = match strategy {
+        Strategy::Rebase => {
+            "`git add` each resolved file and run `git rebase --continue`. Repeat for \
+             every commit that stops with conflicts until the rebase is done."
+        }
+        Strategy::Merge => "`git add` each resolved file and run `git commit --no-edit`.",
+    };
+    let mut out = vec![
+        format!(
+            "Branch `{}` was being {} `{}` and stopped on conflicts.",
+            task.branch,
+            match strategy {
+                Strategy::Rebase => "rebased onto",
+                Strategy::Merge => "merged with",
+            },
+            base
+        ),
+        String::new(),
+        "Conflicting files:".to_string(),
+    ];
+    out.extend(files.iter().map(|f| format!("- {}", f)));
+    out.push(String::new());
+    out.push(format!(
+        "Resolve the conflict markers, keeping both the intent of this branch and \
+         the changes from `{}`. Don't make other changes. Then {}",
+        base, finish
+    ));
+    out.push(String::new());
+    out.push(format!(
+        "For context, the task on this branch was:\n\n{}",
+        super::handoff::original_prompt(&task.prompt)
+    ));
+    out.join("\n")
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::task;
+
+    fn setup() -> (tempfile::TempDir, Task) {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@test.com"],
+            &["config", "user.name", "Test"],
+            &["commit", "--allow-empty", "-m", "init"],
+            &["branch", "-M", "main"],
+        ] {
+            git(dir.path(), args).unwrap();
+        }
+        std::fs::write(dir.path().join("shared.txt"), "base\n").unwrap();
+        git(dir.path(), &["add", "shared.txt"]).unwrap();
+        git(dir.path(), &["commit", "-m", "shared"]).unwrap();
+        let db = crate::db::open_memory().unwrap();
+        let t = task::create(
+            &db,
+            dir.path(),
+            &task::CreateOpts {
+                name: "drift",
+                prompt: "Make it faster",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (dir, t)
+    }
+
+    fn commit(dir: &Path, file: &str, content: &str) {
+        std::fs::write(dir.join(file), content).unwrap();
+        git(dir, &["add", file]).unwrap();
+        git(dir, &["commit", "-m", file]).unwrap();
+    }
+
+    #[test]
+    fn rebase_onto_new_base_keeps_uncommitted_work() {
+        let (repo, t) = setup();
+        let wt = Path::new(&t.worktree);
+        assert_eq!(
+            sync(repo.path(), &t, Strategy::Rebase, false).unwrap(),
+            Outcome::UpToDate
+        );
+
+        commit(wt, "feature.txt", "feature\n");
+        std::fs::write(wt.join("wip.txt"), "wip\n").unwrap();
+        commit(repo.path(), "upstream.txt", "upstream\n");
+        commit(repo.path(), "upstream2.txt", "upstream\n");
+        assert_eq!(behind_count(repo.path(), "main", &t.branch), 2);
+
+        let outcome = sync(repo.path(), &t, Strategy::Rebase, false).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Synced {
+                base: "main".into(),
+                behind: 2
+            }
+        );
+        assert_eq!(behind_count(repo.path(), "main", &t.branch), 0);
+        assert!(wt.join("upstream.txt").exists());
+        assert!(wt.join("wip.txt").exists());
+        // The pre-sync state was checkpointed
+        assert_eq!(checkpoint::list(repo.path(), t.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn conflicts_abort_or_stay_for_the_agent() {
+        let (repo, t) = setup();
+        let wt = Path::new(&t.worktree);
+        commit(wt, "shared.txt", "branch\n");
+        commit(repo.path(), "shared.txt", "upstream\n");
+        let before = git(wt, &["rev-parse", "HEAD"]).unwrap();
+
+        let outcome = sync(repo.path(), &t, Strategy::Rebase, false).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Conflicts {
+                base: "main".into(),
+                files: vec!["shared.txt".into()]
+            }
+        );
+        assert_eq!(git(wt, &["rev-parse", "HEAD"]).unwrap(), before);
+        assert!(conflicted_files(wt).is_empty());
+
+        sync(repo.path(), &t, Strategy::Merge, true).unwrap();
+        assert_eq!(conflicted_files(wt), vec!["shared.txt"]);
+        let prompt = resolve_prompt(&t, Strategy::Merge, "main", &conflicted_files(wt));
+        assert!(prompt.contains("merged with `main`"));
+        assert!(prompt.contains("- shared.txt"));
+        assert!(prompt.contains("Make it faster"));
+    }
+}
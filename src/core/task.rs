@@ -44,6 +44,8 @@ pub struct Task {
     pub prompt: String,
     pub issue_url: String,
     pub agent: String,
+    /// Model passed to the agent CLI; empty means the agent's default.
+    pub model: String,
     pub branch: String,
     pub worktree: String,
     pub status: Status,
@@ -52,6 +54,31 @@ pub struct Task {
     pub pid: Option<i64>,
     pub created_at: String,
     pub updated_at: String,
+    /// URL of the task's pull request; empty if none was opened.
+    pub pr_url: String,
+    /// Last known pull request state: "open", "merged" or "closed".
+    pub pr_state: String,
+    /// Result of the last test run: "running", "pass" or "fail"; empty if
+    /// never tested.
+    pub test_status: String,
+    pub test_duration_ms: i64,
+    /// What failed, for a failed run.
+    pub test_summary: String,
+    pub tested_at: String,
+    /// File of the TODO marker the task was harvested from; empty otherwise.
+    pub source_file: String,
+    pub source_line: i64,
+    /// The marker's line, trimmed, to tell when the branch removed it.
+    pub source_marker: String,
+    /// Registered from an existing branch or worktree; pit doesn't delete
+    /// the branch.
+    pub adopted: bool,
+    /// Task this one was forked from.
+    pub parent_id: Option<i64>,
+    /// Where the fork started: "checkpoint #N" or a commit.
+    pub fork_point: String,
+    /// When the task was archived; empty for active tasks.
+    pub archived_at: String,
 }
 
 /// Options for creating a new task.
@@ -62,13 +89,11 @@ pub struct CreateOpts<'a> {
     pub prompt: &'a str,
     pub issue_url: &'a str,
     pub agent: &'a str,
+    pub model: &'a str,
 }
 
-/// Create a new task: git branch + worktree + DB row.
-pub fn create(db: &Connection, repo_root: &Path, opts: &CreateOpts) -> Result<Task> {
-    let name = opts.name;
-    let description = opts.description;
-    // Validate name: no spaces, no slashes, reasonable length
+/// Check a task name: no spaces, no slashes, reasonable length.
+pub fn validate_name(name: &str) -> Result<()> {
     if name.is_empty() {
         bail!("task name cannot be empty");
     }
@@ -81,9 +106,17 @@ pub fn create(db: &Connection, repo_root: &Path, opts: &CreateOpts) -> Result<Ta
     {
         bail!("task name can only contain alphanumeric characters, hyphens, and underscores");
     }
+    Ok(())
+}
+
+/// Create a new task: git branch + worktree + DB row.
+pub fn create(db: &Connection, repo_root: &Path, opts: &CreateOpts) -> Result<Task> {
+    let name = opts.name;
+    validate_name(name)?;
+    ensure_name_free(db, name)?;
 
     let branch = format!("pit/{}", name);
-    let worktree_path = repo_root.join(".pit").join("worktrees").join(name);
+    let worktree_path = super::worktrees::path(repo_root, name);
     let worktree_str = worktree_path
         .to_str()
         .context("worktree path is not valid UTF-8")?
@@ -121,39 +154,119 @@ pub fn create(db: &Connection, repo_root: &Path, opts: &CreateOpts) -> Result<Ta
         bail!("git worktree add failed: {}", stderr.trim());
     }
 
+    insert(db, opts, &branch, &worktree_str, false)
+}
+
+/// Add the DB row for a task whose branch and worktree already exist.
+/// `adopted` tasks keep their branch when deleted.
+pub fn insert(
+    db: &Connection,
+    opts: &CreateOpts,
+    branch: &str,
+    worktree: &str,
+    adopted: bool,
+) -> Result<Task> {
     let agent = if opts.agent.is_empty() {
         "claude"
     } else {
         opts.agent
     };
 
-    // Insert into database
     db.execute(
-        "INSERT INTO tasks (name, description, prompt, issue_url, agent, branch, worktree)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        "INSERT INTO tasks (name, description, prompt, issue_url, agent, model, branch, worktree,
+         adopted)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         params![
-            name,
-            description,
+            opts.name,
+            opts.description,
             opts.prompt,
             opts.issue_url,
             agent,
+            opts.model,
             branch,
-            worktree_str
+            worktree,
+            adopted
+        ],
+    )
+    .with_context(|| format!("failed to insert task '{}'", opts.name))?;
+
+    let id = db.last_insert_rowid();
+    save_meta(db, id);
+    get(db, id)?.context("task disappeared after insert")
+}
+
+/// Put back a task row recovered from git, keeping its original ID (when
+/// known) so its checkpoints stay attached. The fork parent is left unset.
+pub fn insert_recovered(db: &Connection, meta: &super::meta::Meta) -> Result<Task> {
+    let agent = if meta.agent.is_empty() {
+        "claude"
+    } else {
+        meta.agent.as_str()
+    };
+    let id = (meta.id > 0).then_some(meta.id);
+
+    db.execute(
+        "INSERT INTO tasks (id, name, description, prompt, issue_url, agent, model, branch,
+         worktree, session_id, created_at, pr_url, pr_state, source_file, source_line,
+         source_marker, adopted, fork_point, archived_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
+         COALESCE(NULLIF(?11, ''), datetime('now')), ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        params![
+            id,
+            meta.name,
+            meta.description,
+            meta.prompt,
+            meta.issue_url,
+            agent,
+            meta.model,
+            meta.branch,
+            meta.worktree,
+            meta.session_id,
+            meta.created_at,
+            meta.pr_url,
+            meta.pr_state,
+            meta.source_file,
+            meta.source_line,
+            meta.source_marker,
+            meta.adopted,
+            meta.fork_point,
+            meta.archived_at
         ],
     )
-    .with_context(|| format!("failed to insert task '{}'", name))?;
+    .with_context(|| format!("failed to insert task '{}'", meta.name))?;
 
     let id = db.last_insert_rowid();
+    save_meta(db, id);
     get(db, id)?.context("task disappeared after insert")
 }
 
-/// List all tasks, ordered by creation time.
+/// Columns read by [`row_to_task`], in order.
+const COLUMNS: &str = "id, name, description, prompt, issue_url, agent, branch, worktree, status,
+    session_id, tmux_session, pid, created_at, updated_at, model, pr_url, pr_state,
+    test_status, test_duration_ms, test_summary, tested_at, source_file, source_line,
+    source_marker, adopted, parent_id, fork_point, archived_at";
+
+/// List all active tasks, ordered by creation time. Archived tasks are left
+/// out; see [`list_archived`].
 pub fn list(db: &Connection) -> Result<Vec<Task>> {
-    let mut stmt = db.prepare(
-        "SELECT id, name, description, prompt, issue_url, agent, branch, worktree, status,
-                session_id, tmux_session, pid, created_at, updated_at
-         FROM tasks ORDER BY created_at ASC",
-    )?;
+    let mut stmt = db.prepare(&format!(
+        "SELECT {} FROM tasks WHERE archived_at = '' ORDER BY created_at ASC",
+        COLUMNS
+    ))?;
+
+    let tasks = stmt
+        .query_map([], row_to_task)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(tasks)
+}
+
+/// List archived tasks, most recently archived first.
+pub fn list_archived(db: &Connection) -> Result<Vec<Task>> {
+    let mut stmt = db.prepare(&format!(
+        "SELECT {} FROM tasks WHERE archived_at != '' ORDER BY archived_at DESC, id DESC",
+        COLUMNS
+    ))?;
 
     let tasks = stmt
         .query_map([], row_to_task)?
@@ -164,29 +277,59 @@ pub fn list(db: &Connection) -> Result<Vec<Task>> {
 
 /// Get a task by ID.
 pub fn get(db: &Connection, id: i64) -> Result<Option<Task>> {
-    let mut stmt = db.prepare(
-        "SELECT id, name, description, prompt, issue_url, agent, branch, worktree, status,
-                session_id, tmux_session, pid, created_at, updated_at
-         FROM tasks WHERE id = ?1",
-    )?;
+    let mut stmt = db.prepare(&format!("SELECT {} FROM tasks WHERE id = ?1", COLUMNS))?;
 
     let task = stmt.query_row(params![id], row_to_task).optional()?;
     Ok(task)
 }
 
-/// Get a task by name.
+/// Get an active task by name.
 pub fn get_by_name(db: &Connection, name: &str) -> Result<Option<Task>> {
-    let mut stmt = db.prepare(
-        "SELECT id, name, description, prompt, issue_url, agent, branch, worktree, status,
-                session_id, tmux_session, pid, created_at, updated_at
-         FROM tasks WHERE name = ?1",
-    )?;
+    let mut stmt = db.prepare(&format!(
+        "SELECT {} FROM tasks WHERE name = ?1 AND archived_at = ''",
+        COLUMNS
+    ))?;
+
+    let task = stmt.query_row(params![name], row_to_task).optional()?;
+    Ok(task)
+}
+
+/// Get an archived task by name.
+pub fn get_archived(db: &Connection, name: &str) -> Result<Option<Task>> {
+    let mut stmt = db.prepare(&format!(
+        "SELECT {} FROM tasks WHERE name = ?1 AND archived_at != ''",
+        COLUMNS
+    ))?;
 
     let task = stmt.query_row(params![name], row_to_task).optional()?;
     Ok(task)
 }
 
-/// Delete a task: remove worktree, branch, and DB row.
+/// Names of all tasks, archived ones included.
+pub fn names(db: &Connection) -> Result<Vec<String>> {
+    let mut stmt = db.prepare("SELECT name FROM tasks ORDER BY id")?;
+    let names = stmt
+        .query_map([], |r| r.get(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
+/// Fail if a task, active or archived, already has this name.
+pub fn ensure_name_free(db: &Connection, name: &str) -> Result<()> {
+    if get_archived(db, name)?.is_some() {
+        bail!(
+            "task '{}' is archived — restore it with `pit restore {}` or pick another name",
+            name,
+            name
+        );
+    }
+    if get_by_name(db, name)?.is_some() {
+        bail!("task '{}' already exists", name);
+    }
+    Ok(())
+}
+
+/// Delete a task: remove worktree, branch, checkpoints and DB row.
 pub fn delete(db: &Connection, repo_root: &Path, id: i64) -> Result<()> {
     let task = get(db, id)?.context("task not found")?;
 
@@ -194,17 +337,26 @@ pub fn delete(db: &Connection, repo_root: &Path, id: i64) -> Result<()> {
         bail!("cannot delete a running task — stop it first");
     }
 
-    // Remove the git worktree
-    let _ = Command::new("git")
-        .args(["worktree", "remove", "--force", &task.worktree])
-        .current_dir(repo_root)
-        .output();
+    // Remove the git worktree; an adopted task's own worktree outside the
+    // worktree root is left alone
+    let managed = super::worktrees::is_managed(repo_root, Path::new(&task.worktree));
+    if !task.adopted || managed {
+        let _ = Command::new("git")
+            .args(["worktree", "remove", "--force", &task.worktree])
+            .current_dir(repo_root)
+            .output();
+    }
 
-    // Delete the git branch
-    let _ = Command::new("git")
-        .args(["branch", "-D", &task.branch])
-        .current_dir(repo_root)
-        .output();
+    // Delete the git branch, unless it was adopted
+    if !task.adopted {
+        let _ = Command::new("git")
+            .args(["branch", "-D", &task.branch])
+            .current_dir(repo_root)
+            .output();
+    }
+
+    // Checkpoints go with the task
+    let _ = super::checkpoint::remove_all(repo_root, id);
 
     // Remove the DB row
     db.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
@@ -224,6 +376,149 @@ pub fn set_status(db: &Connection, id: i64, status: &Status) -> Result<()> {
     Ok(())
 }
 
+/// Fields to change in [`update`]. `None` leaves a field as it is.
+#[derive(Debug, Default)]
+pub struct UpdateOpts<'a> {
+    pub description: Option<&'a str>,
+    pub prompt: Option<&'a str>,
+    pub issue_url: Option<&'a str>,
+    pub agent: Option<&'a str>,
+    pub model: Option<&'a str>,
+}
+
+/// Update a task's prompt, agent and metadata.
+///
+/// Switching to a different agent clears `session_id`, since a session from
+/// one agent can't be resumed by another.
+pub fn update(db: &Connection, id: i64, opts: &UpdateOpts) -> Result<Task> {
+    let current = get(db, id)?.with_context(|| format!("task {} not found", id))?;
+
+    let agent = match opts.agent {
+        Some("") => "claude",
+        Some(a) => a,
+        None => current.agent.as_str(),
+    };
+    let session_id = if agent == current.agent {
+        current.session_id.as_deref()
+    } else {
+        None
+    };
+
+    db.execute(
+        "UPDATE tasks SET description = ?1, prompt = ?2, issue_url = ?3, agent = ?4,
+         model = ?5, session_id = ?6, updated_at = datetime('now')
+         WHERE id = ?7",
+        params![
+            opts.description.unwrap_or(&current.description),
+            opts.prompt.unwrap_or(&current.prompt),
+            opts.issue_url.unwrap_or(&current.issue_url),
+            agent,
+            opts.model.unwrap_or(&current.model),
+            session_id,
+            id
+        ],
+    )?;
+
+    save_meta(db, id);
+    get(db, id)?.context("task disappeared after update")
+}
+
+/// A task's editable fields, as parsed from an edit document.
+#[derive(Debug, Default, PartialEq)]
+pub struct Document {
+    pub description: String,
+    pub issue_url: String,
+    pub agent: String,
+    pub model: String,
+    pub prompt: String,
+}
+
+impl Document {
+    pub fn as_update(&self) -> UpdateOpts<'_> {
+        UpdateOpts {
+            description: Some(&self.description),
+            prompt: Some(&self.prompt),
+            issue_url: Some(&self.issue_url),
+            agent: Some(&self.agent),
+            model: Some(&self.model),
+        }
+    }
+}
+
+/// Render a task as a document for `$EDITOR`: metadata in `+++` front
+/// matter, prompt as the body.
+pub fn to_document(task: &Task) -> String {
+    format!(
+        "+++\n\
+         # Editing task '{}'. Save and quit to apply, or clear the file to cancel.\n\
+         description = \"{}\"\n\
+         issue_url = \"{}\"\n\
+         agent = \"{}\"\n\
+         model = \"{}\"\n\
+         +++\n\
+         {}\n",
+        task.name,
+        escape(&task.description),
+        escape(&task.issue_url),
+        escape(&task.agent),
+        escape(&task.model),
+        task.prompt
+    )
+}
+
+/// Parse a document produced by [`to_document`]. Returns `None` if the
+/// document is empty (edit cancelled).
+pub fn parse_document(text: &str) -> Option<Document> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    let (front, body) = super::template::split_front_matter(text);
+    let field = |key: &str| {
+        front
+            .lines()
+            .filter_map(|l| l.split_once('='))
+            .find(|(k, _)| k.trim() == key)
+            .map(|(_, v)| unquote(v.trim()))
+            .unwrap_or_default()
+    };
+    Some(Document {
+        description: field("description"),
+        issue_url: field("issue_url"),
+        agent: field("agent"),
+        model: field("model"),
+        prompt: body.trim().to_string(),
+    })
+}
+
+/// Quote-safe front matter value: `\`, `"` and newlines are escaped.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Undo [`escape`] on a value, with or without its quotes.
+fn unquote(v: &str) -> String {
+    let Some(inner) = v.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return v.to_string();
+    };
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
 /// Store the tmux session name and PID for a running task.
 pub fn set_running(
     db: &Connection,
@@ -238,9 +533,113 @@ pub fn set_running(
          WHERE id = ?4",
         params![tmux_session, pid, session_id, id],
     )?;
+    save_meta(db, id);
+    Ok(())
+}
+
+/// Record the outcome of a test run ("running", "pass" or "fail").
+pub fn set_test(
+    db: &Connection,
+    id: i64,
+    status: &str,
+    duration_ms: i64,
+    summary: &str,
+) -> Result<()> {
+    db.execute(
+        "UPDATE tasks SET test_status = ?1, test_duration_ms = ?2, test_summary = ?3,
+         tested_at = datetime('now') WHERE id = ?4",
+        params![status, duration_ms, summary, id],
+    )?;
+    Ok(())
+}
+
+/// Record the TODO marker a task was harvested from.
+pub fn set_source(db: &Connection, id: i64, file: &str, line: u32, marker: &str) -> Result<()> {
+    db.execute(
+        "UPDATE tasks SET source_file = ?1, source_line = ?2, source_marker = ?3 WHERE id = ?4",
+        params![file, line, marker, id],
+    )?;
+    save_meta(db, id);
+    Ok(())
+}
+
+/// Make sure new tasks get IDs above `id`. Refs in git are keyed by task
+/// ID, so a fresh DB must not hand out the IDs of tasks it lost.
+pub fn reserve_ids(db: &Connection, id: i64) -> Result<()> {
+    let updated = db.execute(
+        "UPDATE sqlite_sequence SET seq = MAX(seq, ?1) WHERE name = 'tasks'",
+        params![id],
+    )?;
+    if updated == 0 && id > 0 {
+        db.execute(
+            "INSERT INTO sqlite_sequence (name, seq) VALUES ('tasks', ?1)",
+            params![id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Point a task at its worktree's new location.
+pub fn set_worktree(db: &Connection, id: i64, worktree: &str) -> Result<()> {
+    db.execute(
+        "UPDATE tasks SET worktree = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![worktree, id],
+    )?;
+    save_meta(db, id);
     Ok(())
 }
 
+/// Record the task a fork came from and where it started.
+pub fn set_parent(db: &Connection, id: i64, parent_id: i64, fork_point: &str) -> Result<()> {
+    db.execute(
+        "UPDATE tasks SET parent_id = ?1, fork_point = ?2 WHERE id = ?3",
+        params![parent_id, fork_point, id],
+    )?;
+    save_meta(db, id);
+    Ok(())
+}
+
+/// Mark a task archived (now) or active again.
+pub fn set_archived(db: &Connection, id: i64, archived: bool) -> Result<()> {
+    db.execute(
+        "UPDATE tasks SET archived_at = CASE WHEN ?1 THEN datetime('now') ELSE '' END,
+         updated_at = datetime('now') WHERE id = ?2",
+        params![archived, id],
+    )?;
+    save_meta(db, id);
+    Ok(())
+}
+
+/// Record a task's pull request and its state.
+pub fn set_pr(db: &Connection, id: i64, url: &str, state: &str) -> Result<()> {
+    db.execute(
+        "UPDATE tasks SET pr_url = ?1, pr_state = ?2, updated_at = datetime('now')
+         WHERE id = ?3",
+        params![url, state, id],
+    )?;
+    save_meta(db, id);
+    Ok(())
+}
+
+/// Mirror a task's metadata into git (see [`super::meta`]). Best effort: the
+/// DB stays the source of truth.
+///
+/// Written from the checkout the DB lives in (`<repo>/.pit/pit.db`), so
+/// archived tasks and tasks that lost their worktree are mirrored too; an
+/// in-memory DB falls back to the task's worktree.
+fn save_meta(db: &Connection, id: i64) {
+    let Ok(Some(task)) = get(db, id) else {
+        return;
+    };
+    let repo_root = db
+        .path()
+        .filter(|p| !p.is_empty())
+        .and_then(|p| Path::new(p).parent()?.parent())
+        .filter(|r| r.join(".git").exists());
+    let dir = repo_root.unwrap_or(Path::new(&task.worktree));
+    let _ = super::meta::save(dir, &task);
+}
+
 /// Use rusqlite's optional extension.
 use rusqlite::OptionalExtension;
 
@@ -272,6 +671,20 @@ fn row_to_task(row: &rusqlite::Row) -> rusqlite::Result<Task> {
         pid: row.get(11)?,
         created_at: row.get(12)?,
         updated_at: row.get(13)?,
+        model: row.get(14)?,
+        pr_url: row.get(15)?,
+        pr_state: row.get(16)?,
+        test_status: row.get(17)?,
+        test_duration_ms: row.get(18)?,
+        test_summary: row.get(19)?,
+        tested_at: row.get(20)?,
+        source_file: row.get(21)?,
+        source_line: row.get(22)?,
+        source_marker: row.get(23)?,
+        adopted: row.get(24)?,
+        parent_id: row.get(25)?,
+        fork_point: row.get(26)?,
+        archived_at: row.get(27)?,
     })
 }
 
@@ -332,6 +745,7 @@ mod tests {
                 prompt: "find and fix the login timeout",
                 issue_url: "https://linear.app/123",
                 agent: "claude",
+                model: "",
             },
         )
         .unwrap();
@@ -366,6 +780,7 @@ mod tests {
                 prompt: "",
                 issue_url: "",
                 agent: "codex",
+                model: "o3",
             },
         )
         .unwrap();
@@ -374,6 +789,7 @@ mod tests {
         // Re-read from DB to confirm persistence
         let t = get(&db, task.id).unwrap().unwrap();
         assert_eq!(t.agent, "codex");
+        assert_eq!(t.model, "o3");
     }
 
     #[test]
@@ -388,6 +804,7 @@ mod tests {
                 prompt: "",
                 issue_url: "",
                 agent: "",
+                model: "",
             },
         )
         .unwrap();
@@ -406,6 +823,7 @@ mod tests {
                 prompt: "do the thing",
                 issue_url: "",
                 agent: "amp",
+                model: "",
             },
         )
         .unwrap();
@@ -527,4 +945,113 @@ mod tests {
         assert_eq!(t.pid, Some(12345));
         assert_eq!(t.session_id.as_deref(), Some("sess-abc"));
     }
+
+    #[test]
+    fn update_changes_only_given_fields() {
+        let (repo, db) = setup();
+        let task = create(&db, repo.path(), &opts("edit-me", "old desc")).unwrap();
+        set_running(&db, task.id, "pit-edit-me", None, Some("sess-1")).unwrap();
+
+        let t = update(
+            &db,
+            task.id,
+            &UpdateOpts {
+                prompt: Some("new prompt"),
+                issue_url: Some("https://github.com/o/r/issues/1"),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(t.prompt, "new prompt");
+        assert_eq!(t.issue_url, "https://github.com/o/r/issues/1");
+        assert_eq!(t.description, "old desc");
+        assert_eq!(t.agent, "claude");
+        // Same agent: session kept
+        assert_eq!(t.session_id.as_deref(), Some("sess-1"));
+    }
+
+    #[test]
+    fn update_agent_clears_session() {
+        let (repo, db) = setup();
+        let task = create(&db, repo.path(), &opts("switch", "")).unwrap();
+        set_running(&db, task.id, "pit-switch", None, Some("sess-1")).unwrap();
+
+        let t = update(
+            &db,
+            task.id,
+            &UpdateOpts {
+                agent: Some("codex"),
+                model: Some("o3"),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(t.agent, "codex");
+        assert_eq!(t.model, "o3");
+        assert!(t.session_id.is_none());
+    }
+
+    #[test]
+    fn update_missing_task_fails() {
+        let db = crate::db::open_memory().unwrap();
+        assert!(update(&db, 42, &UpdateOpts::default()).is_err());
+    }
+
+    #[test]
+    fn document_roundtrip() {
+        let (repo, db) = setup();
+        let task = create(
+            &db,
+            repo.path(),
+            &CreateOpts {
+                name: "doc",
+                description: "line one\nline two",
+                prompt: "Fix it.\n\n```rust\nfn main() {}\n```",
+                issue_url: "https://linear.app/t/issue/ENG-1",
+                agent: "pi",
+                model: "",
+            },
+        )
+        .unwrap();
+
+        let doc = parse_document(&to_document(&task)).unwrap();
+        assert_eq!(doc.description, "line one\nline two");
+        assert_eq!(doc.issue_url, "https://linear.app/t/issue/ENG-1");
+        assert_eq!(doc.agent, "pi");
+        assert_eq!(doc.prompt, task.prompt);
+
+        let edited = to_document(&task).replace("agent = \"pi\"", "agent = \"codex\"");
+        let t = update(&db, task.id, &parse_document(&edited).unwrap().as_update()).unwrap();
+        assert_eq!(t.agent, "codex");
+        assert_eq!(t.prompt, task.prompt);
+    }
+
+    #[test]
+    fn document_roundtrip_keeps_quotes_backslashes_and_newlines() {
+        let (repo, db) = setup();
+        let task = create(
+            &db,
+            repo.path(),
+            &CreateOpts {
+                name: "quotes",
+                description: "Rename to \"bar\"\nin C:\\src\\ and \\n literally",
+                prompt: "p",
+                issue_url: "https://example.com/?q=\"x\"",
+                agent: "claude",
+                model: "",
+            },
+        )
+        .unwrap();
+        let doc = parse_document(&to_document(&task)).unwrap();
+        assert_eq!(doc.description, task.description);
+        assert_eq!(doc.issue_url, task.issue_url);
+        assert_eq!(doc.agent, "claude");
+        assert_eq!(doc.model, "");
+        assert_eq!(doc.prompt, "p");
+    }
+
+    #[test]
+    fn empty_document_cancels() {
+        assert!(parse_document("  \n").is_none());
+    }
 }
@@ -0,0 +1,386 @@
+//! Task templates — reusable prompt bodies with variables.
+//!
+//! Templates live in `.pit/templates/<name>.md`. An optional front matter
+//! block between `+++` lines sets the agent, model and hooks; the rest of
+//! the file is the prompt body:
+//!
+//! ```text
+//! +++
+//! agent = "claude"
+//! model = "sonnet"
+//!
+//! [hooks]
+//! setup = "cargo fetch"
+//! +++
+//! Fix {issue.id}: {issue.title}
+//!
+//! {issue.description}
+//!
+//! Add a regression test and run `cargo test`. Don't touch the public API.
+//! ```
+//!
+//! Variables are written `{name}`. Built-ins are `{name}`, `{branch}`,
+//! `{base}`, `{issue.id}`, `{issue.title}`, `{issue.description}` and
+//! `{issue.url}`; anything else comes from `--var key=value`.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::issues::Issue;
+
+const FRONT_MATTER_DELIM: &str = "+++";
+
+/// A parsed task template.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Template {
+    pub name: String,
+    /// Agent to use; empty means "don't override".
+    pub agent: String,
+    /// Model to pass to the agent; empty means the agent's default.
+    pub model: String,
+    /// Shell command run in the new worktree right after creation.
+    pub setup_hook: String,
+    /// Prompt body, with `{variables}` still in place.
+    pub body: String,
+}
+
+/// Directory holding a repo's templates.
+pub fn dir(repo_root: &Path) -> PathBuf {
+    repo_root.join(".pit").join("templates")
+}
+
+/// Names of all templates in the repo, sorted.
+pub fn list(repo_root: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir(repo_root))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let path = e.path();
+                    if path.extension().and_then(|x| x.to_str()) != Some("md") {
+                        return None;
+                    }
+                    path.file_stem()
+                        .and_then(|s| s.to_str())
+                        .map(|s| s.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Load a template by name.
+pub fn load(repo_root: &Path, name: &str) -> Result<Template> {
+    let path = dir(repo_root).join(format!("{}.md", name));
+    if !path.exists() {
+        let available = list(repo_root);
+        if available.is_empty() {
+            bail!(
+                "template '{}' not found (no templates in {})",
+                name,
+                dir(repo_root).display()
+            );
+        }
+        bail!(
+            "template '{}' not found (available: {})",
+            name,
+            available.join(", ")
+        );
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(parse(name, &content))
+}
+
+/// Parse template file contents.
+pub fn parse(name: &str, content: &str) -> Template {
+    let (front, body) = split_front_matter(content);
+    let meta = super::config::parse(front);
+    let field = |key: &str| meta.get(key).cloned().unwrap_or_default();
+    Template {
+        name: name.to_string(),
+        agent: field("agent"),
+        model: field("model"),
+        setup_hook: field("hooks.setup"),
+        body: body.trim().to_string(),
+    }
+}
+
+/// Split `+++`-delimited front matter from the body.
+/// Returns ("", content) when there is no front matter.
+pub fn split_front_matter(content: &str) -> (&str, &str) {
+    let trimmed = content.trim_start();
+    let Some(rest) = trimmed.strip_prefix(FRONT_MATTER_DELIM) else {
+        return ("", content);
+    };
+    let Some(rest) = rest
+        .strip_prefix('\n')
+        .or_else(|| rest.strip_prefix("\r\n"))
+    else {
+        return ("", content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FRONT_MATTER_DELIM {
+            return (&rest[..offset], &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    ("", content)
+}
+
+/// Build the variable map for a task.
+pub fn variables(
+    name: &str,
+    base: &str,
+    issue: Option<&Issue>,
+    user: &[(String, String)],
+) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    vars.insert("name".to_string(), name.to_string());
+    vars.insert("branch".to_string(), format!("pit/{}", name));
+    vars.insert("base".to_string(), base.to_string());
+    let (id, title, description, url) = match issue {
+        Some(i) => (
+            i.identifier.clone(),
+            i.title.clone(),
+            i.description.clone(),
+            i.url.clone(),
+        ),
+        None => Default::default(),
+    };
+    vars.insert("issue.id".to_string(), id);
+    vars.insert("issue.title".to_string(), title);
+    vars.insert("issue.description".to_string(), description);
+    vars.insert("issue.url".to_string(), url);
+    for (k, v) in user {
+        vars.insert(k.clone(), v.clone());
+    }
+    vars
+}
+
+/// Parse a `key=value` pair from `--var`.
+pub fn parse_var(s: &str) -> Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .with_context(|| format!("invalid --var '{}': expected key=value", s))?;
+    let key = key.trim();
+    if key.is_empty() {
+        bail!("invalid --var '{}': empty key", s);
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Substitute `{variables}` in `text`. Unknown placeholders are left as-is,
+/// as is anything in braces that isn't a plain identifier (e.g. code).
+pub fn render(text: &str, vars: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) if is_var_name(&after[..end]) => {
+                let key = &after[..end];
+                match vars.get(key) {
+                    Some(v) => out.push_str(v),
+                    None => out.push_str(&rest[start..start + end + 2]),
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Placeholders in `text` that have no value in `vars`.
+pub fn unresolved(text: &str, vars: &HashMap<String, String>) -> Vec<String> {
+    let mut missing = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) if is_var_name(&after[..end]) => {
+                let key = &after[..end];
+                if !vars.contains_key(key) && !missing.iter().any(|m| m == key) {
+                    missing.push(key.to_string());
+                }
+                rest = &after[end + 1..];
+            }
+            _ => rest = after,
+        }
+    }
+    missing
+}
+
+fn is_var_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Run the template's setup hook (if any) in the task's worktree.
+pub fn run_setup_hook(template: &Template, worktree: &Path) -> Result<()> {
+    if template.setup_hook.trim().is_empty() {
+        return Ok(());
+    }
+    let output = Command::new("sh")
+        .args(["-c", &template.setup_hook])
+        .current_dir(worktree)
+        .output()
+        .context("failed to run setup hook")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!(
+            "setup hook for template '{}' failed: {}",
+            template.name,
+            stderr.trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::issues::{IssueContext, Provider};
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_with_front_matter() {
+        let content = "+++\nagent = \"codex\"\nmodel = \"o3\"\n\n[hooks]\nsetup = \"make deps\"\n+++\nFix {issue.title}\n";
+        let t = parse("bugfix", content);
+        assert_eq!(t.name, "bugfix");
+        assert_eq!(t.agent, "codex");
+        assert_eq!(t.model, "o3");
+        assert_eq!(t.setup_hook, "make deps");
+        assert_eq!(t.body, "Fix {issue.title}");
+    }
+
+    #[test]
+    fn parse_without_front_matter() {
+        let t = parse("plain", "Just a prompt.\n");
+        assert_eq!(t.agent, "");
+        assert_eq!(t.body, "Just a prompt.");
+    }
+
+    #[test]
+    fn unterminated_front_matter_is_body() {
+        let t = parse("x", "+++\nagent = \"codex\"\nno end");
+        assert_eq!(t.agent, "");
+        assert!(t.body.starts_with("+++"));
+    }
+
+    #[test]
+    fn render_substitutes_known_vars() {
+        let v = vars(&[("name", "fix-it"), ("issue.title", "Crash")]);
+        assert_eq!(
+            render("{name}: {issue.title} ({unknown})", &v),
+            "fix-it: Crash ({unknown})"
+        );
+    }
+
+    #[test]
+    fn render_leaves_code_braces_alone() {
+        let v = vars(&[("name", "x")]);
+        let text = "fn main() { println!(\"{}\", 1); } {name}";
+        assert_eq!(render(text, &v), "fn main() { println!(\"{}\", 1); } x");
+    }
+
+    #[test]
+    fn unresolved_lists_missing_vars_once() {
+        let v = vars(&[("name", "x")]);
+        assert_eq!(
+            unresolved("{name} {module} {module} {x y}", &v),
+            vec!["module".to_string()]
+        );
+    }
+
+    #[test]
+    fn variables_include_issue_and_user_values() {
+        let issue = Issue {
+            provider: Provider::GitHub,
+            identifier: "#7".to_string(),
+            title: "Crash".to_string(),
+            description: "Stack trace".to_string(),
+            state: "open".to_string(),
+            url: "https://github.com/o/r/issues/7".to_string(),
+            context: IssueContext::default(),
+        };
+        let v = variables(
+            "fix-7",
+            "main",
+            Some(&issue),
+            &[("module".to_string(), "auth".to_string())],
+        );
+        assert_eq!(v["branch"], "pit/fix-7");
+        assert_eq!(v["base"], "main");
+        assert_eq!(v["issue.id"], "#7");
+        assert_eq!(v["issue.description"], "Stack trace");
+        assert_eq!(v["module"], "auth");
+    }
+
+    #[test]
+    fn variables_without_issue_are_empty() {
+        let v = variables("t", "main", None, &[]);
+        assert_eq!(v["issue.title"], "");
+    }
+
+    #[test]
+    fn parse_var_splits_on_first_equals() {
+        assert_eq!(
+            parse_var("cmd=a=b").unwrap(),
+            ("cmd".to_string(), "a=b".to_string())
+        );
+        assert!(parse_var("novalue").is_err());
+        assert!(parse_var("=x").is_err());
+    }
+
+    #[test]
+    fn list_and_load_from_templates_dir() {
+        let repo = tempfile::tempdir().unwrap();
+        let d = dir(repo.path());
+        std::fs::create_dir_all(&d).unwrap();
+        std::fs::write(d.join("bugfix.md"), "+++\nagent = \"pi\"\n+++\nBody").unwrap();
+        std::fs::write(d.join("docs.md"), "Docs").unwrap();
+        std::fs::write(d.join("notes.txt"), "ignored").unwrap();
+
+        assert_eq!(list(repo.path()), vec!["bugfix", "docs"]);
+        assert_eq!(load(repo.path(), "bugfix").unwrap().agent, "pi");
+        let err = load(repo.path(), "nope").unwrap_err().to_string();
+        assert!(err.contains("available: bugfix, docs"), "{}", err);
+    }
+
+    #[test]
+    fn setup_hook_runs_in_worktree() {
+        let wt = tempfile::tempdir().unwrap();
+        let t = Template {
+            name: "t".to_string(),
+            setup_hook: "touch hooked".to_string(),
+            ..Default::default()
+        };
+        run_setup_hook(&t, wt.path()).unwrap();
+        assert!(wt.path().join("hooked").exists());
+
+        let failing = Template {
+            setup_hook: "exit 3".to_string(),
+            ..t
+        };
+        assert!(run_setup_hook(&failing, wt.path()).is_err());
+    }
+}
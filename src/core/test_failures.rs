@@ -0,0 +1,473 @@
+//! Turn failing tests into tasks.
+//!
+//! Parses test runner output into individual failures: `cargo test`, pytest,
+//! and JUnit XML reports (jest-junit, pytest `--junitxml`, and most CI tools).
+//! Each failure, or each file's failures when grouped, becomes one task whose
+//! prompt carries the test name, its location and the failure output.
+
+use std::collections::HashSet;
+
+/// Most output lines of a single failure put in a prompt.
+const OUTPUT_LINES: usize = 60;
+
+/// One failing test.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Failure {
+    pub name: String,
+    /// Source file of the failure, when the runner reports one.
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub output: String,
+}
+
+impl Failure {
+    /// "file:line", "file", or empty.
+    pub fn location(&self) -> String {
+        match (&self.file, self.line) {
+            (Some(f), Some(l)) => format!("{}:{}", f, l),
+            (Some(f), None) => f.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Failures found in test runner output, trying each known format.
+pub fn parse(output: &str) -> Vec<Failure> {
+    if output.contains("<testsuite") || output.contains("<testcase") {
+        return parse_junit(output);
+    }
+    let cargo = parse_cargo(output);
+    if !cargo.is_empty() {
+        return cargo;
+    }
+    parse_pytest(output)
+}
+
+/// `cargo test`: one `---- name stdout ----` section per failure.
+pub fn parse_cargo(output: &str) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let mut current: Option<Failure> = None;
+    for line in output.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" stdout ----"))
+        {
+            failures.extend(current.take());
+            current = Some(Failure {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(f) = current.as_mut() else {
+            continue;
+        };
+        // The list of failed tests follows the last section
+        if line == "failures:" {
+            failures.extend(current.take());
+            continue;
+        }
+        if f.file.is_none() {
+            if let Some((file, line_no)) = cargo_location(line) {
+                f.file = Some(file);
+                f.line = line_no;
+            }
+        }
+        f.output.push_str(line);
+        f.output.push('\n');
+    }
+    failures.extend(current);
+    for f in &mut failures {
+        f.output = f.output.trim_end().to_string();
+    }
+    failures
+}
+
+/// The location in a panic line, in both the current
+/// (`panicked at src/lib.rs:10:5:`) and the older
+/// (`panicked at 'msg', src/lib.rs:10:5`) format.
+fn cargo_location(line: &str) -> Option<(String, Option<u32>)> {
+    let rest = &line[line.find("panicked at ")? + "panicked at ".len()..];
+    let loc = if rest.starts_with('\'') {
+        &rest[rest.rfind("', ")? + 3..]
+    } else {
+        rest
+    };
+    let loc = loc.trim().trim_end_matches(':');
+    let mut parts = loc.split(':');
+    let file = parts.next()?.to_string();
+    let line_no = parts.next().and_then(|l| l.parse().ok());
+    Some((file, line_no))
+}
+
+/// pytest: sections headed `____ name ____` inside the FAILURES block, each
+/// ending with a `path.py:line: Error` location.
+pub fn parse_pytest(output: &str) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let mut in_failures = false;
+    let mut current: Option<Failure> = None;
+    for line in output.lines() {
+        if line.starts_with('=') {
+            failures.extend(current.take());
+            in_failures = line.contains(" FAILURES ") || line.contains(" ERRORS ");
+            continue;
+        }
+        if !in_failures {
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed.starts_with("___") && trimmed.ends_with("___") {
+            failures.extend(current.take());
+            let name = trimmed.trim_matches('_').trim();
+            current = Some(Failure {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(f) = current.as_mut() else {
+            continue;
+        };
+        if let Some((file, line_no)) = pytest_location(line) {
+            f.file = Some(file);
+            f.line = Some(line_no);
+        }
+        f.output.push_str(line);
+        f.output.push('\n');
+    }
+    failures.extend(current);
+    for f in &mut failures {
+        f.output = f.output.trim().to_string();
+    }
+    failures
+}
+
+/// `tests/test_math.py:5: AssertionError` → (`tests/test_math.py`, 5).
+fn pytest_location(line: &str) -> Option<(String, u32)> {
+    let (file, rest) = line.split_once(".py:")?;
+    if file.contains(' ') {
+        return None;
+    }
+    let (line_no, _) = rest.split_once(':')?;
+    Some((format!("{}.py", file), line_no.parse().ok()?))
+}
+
+/// JUnit XML: every `<testcase>` with a `<failure>` or `<error>` inside.
+pub fn parse_junit(xml: &str) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find("<testcase") {
+        rest = &rest[start..];
+        let tag_end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..tag_end];
+        // A self-closing testcase passed
+        let (body, next) = if tag.ends_with('/') {
+            ("", tag_end)
+        } else {
+            match rest.find("</testcase>") {
+                Some(end) => (&rest[tag_end..end], end),
+                None => (&rest[tag_end..], rest.len()),
+            }
+        };
+        rest = &rest[next..];
+
+        let Some(fail_start) = body.find("<failure").or_else(|| body.find("<error")) else {
+            continue;
+        };
+        let name = attr(tag, "name").unwrap_or_default();
+        let class = attr(tag, "classname").unwrap_or_default();
+        let fail = &body[fail_start..];
+        let fail_tag_end = fail.find('>').unwrap_or(fail.len());
+        let message = attr(&fail[..fail_tag_end], "message").unwrap_or_default();
+        let text = fail[fail_tag_end..]
+            .trim_start_matches('>')
+            .split("</")
+            .next()
+            .unwrap_or("");
+        let text = unescape(
+            text.trim()
+                .trim_start_matches("<![CDATA[")
+                .trim_end_matches("]]>"),
+        );
+        // The message is often the text's first line; otherwise keep both
+        let output = if text.contains(&message) {
+            text
+        } else {
+            format!("{}\n{}", message, text)
+        };
+        failures.push(Failure {
+            name: if class.is_empty() || name.starts_with(&class) {
+                name
+            } else {
+                format!("{} {}", class, name)
+            },
+            file: attr(tag, "file"),
+            line: attr(tag, "line").and_then(|l| l.parse().ok()),
+            output: output.trim().to_string(),
+        });
+    }
+    failures
+}
+
+/// Value of `key="…"` in an XML start tag.
+fn attr(tag: &str, key: &str) -> Option<String> {
+    let pattern = format!(" {}=\"", key);
+    let start = tag.find(&pattern)? + pattern.len();
+    let end = tag[start..].find('"')?;
+    Some(unescape(&tag[start..start + end]))
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+/// Failures for one task: a single test, or all failures in one file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub failures: Vec<Failure>,
+}
+
+/// One group per failure, or per file with `by_file`. Task names are unique
+/// among themselves and `existing`.
+pub fn group(failures: Vec<Failure>, by_file: bool, existing: &[String]) -> Vec<Group> {
+    let mut groups: Vec<(String, Vec<Failure>)> = Vec::new();
+    for f in failures {
+        let key = match (&f.file, by_file) {
+            (Some(file), true) => file_stem(file),
+            _ => last_segment(&f.name),
+        };
+        match groups
+            .iter_mut()
+            .find(|(k, _)| by_file && f.file.is_some() && *k == key)
+        {
+            Some((_, list)) => list.push(f),
+            None => groups.push((key, vec![f])),
+        }
+    }
+
+    let mut taken: HashSet<String> = existing.iter().cloned().collect();
+    groups
+        .into_iter()
+        .map(|(key, failures)| {
+            let base = format!("fix-{}", slug(&key));
+            let mut name = base.clone();
+            let mut n = 2;
+            while taken.contains(&name) {
+                name = format!("{}-{}", base, n);
+                n += 1;
+            }
+            taken.insert(name.clone());
+            Group { name, failures }
+        })
+        .collect()
+}
+
+/// Prompt asking the agent to fix a group's failures.
+pub fn prompt(group: &Group, test_cmd: &str) -> String {
+    let mut out = Vec::new();
+    match &group.failures[..] {
+        [f] => {
+            let at = f.location();
+            out.push(if at.is_empty() {
+                format!("Fix the failing test `{}`.", f.name)
+            } else {
+                format!("Fix the failing test `{}` ({}).", f.name, at)
+            });
+        }
+        failures => {
+            out.push(format!("Fix these {} failing tests:", failures.len()));
+            for f in failures {
+                let at = f.location();
+                out.push(if at.is_empty() {
+                    format!("- `{}`", f.name)
+                } else {
+                    format!("- `{}` ({})", f.name, at)
+                });
+            }
+        }
+    }
+    out.push(String::new());
+    out.push(format!("They fail when running `{}`:", test_cmd));
+    for f in &group.failures {
+        out.push(String::new());
+        if group.failures.len() > 1 {
+            out.push(format!("`{}`:", f.name));
+        }
+        out.push("```".to_string());
+        let lines: Vec<&str> = f.output.lines().collect();
+        out.extend(lines.iter().take(OUTPUT_LINES).map(|l| l.to_string()));
+        if lines.len() > OUTPUT_LINES {
+            out.push(format!("… ({} more lines)", lines.len() - OUTPUT_LINES));
+        }
+        out.push("```".to_string());
+    }
+    out.push(String::new());
+    out.push(format!(
+        "Find the cause and fix the code, or the test if the test itself is \
+         wrong. Run `{}` to confirm the fix without breaking other tests.",
+        test_cmd
+    ));
+    out.join("\n")
+}
+
+/// `tests::parser::handles_empty` → `handles_empty`.
+fn last_segment(name: &str) -> String {
+    name.rsplit([':', '.', ' ', '/'])
+        .find(|s| !s.is_empty())
+        .unwrap_or(name)
+        .to_string()
+}
+
+/// `tests/test_math.py` → `test_math`.
+fn file_stem(path: &str) -> String {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    file.split('.').next().unwrap_or(file).to_string()
+}
+
+/// Lowercase, alphanumerics and single hyphens, at most 40 characters.
+fn slug(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out: String = out.trim_matches('-').chars().take(40).collect();
+    match out.trim_end_matches('-') {
+        "" => "test".to_string(),
+        s => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO: &str = "\
+running 3 tests
+test parser::ok ... ok
+test parser::handles_empty ... FAILED
+test math::adds ... FAILED
+
+failures:
+
+---- parser::handles_empty stdout ----
+
+thread 'parser::handles_empty' panicked at src/parser.rs:42:9:
+assertion `left == right` failed
+  left: 1
+ right: 0
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- math::adds stdout ----
+thread 'math::adds' panicked at 'attempt to add with overflow', src/math.rs:7:5
+
+
+failures:
+    math::adds
+    parser::handles_empty
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored
+";
+
+    const PYTEST: &str = "\
+============================= test session starts ==============================
+collected 3 items
+
+tests/test_math.py .FF                                                    [100%]
+
+=================================== FAILURES ===================================
+___________________________________ test_add ___________________________________
+
+    def test_add():
+>       assert add(1, 2) == 4
+E       assert 3 == 4
+
+tests/test_math.py:5: AssertionError
+_____________________________ TestDiv.test_by_zero _____________________________
+
+self = <test_math.TestDiv object>
+
+>       div(1, 0)
+E       ZeroDivisionError: division by zero
+
+tests/test_math.py:12: ZeroDivisionError
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_add - assert 3 == 4
+========================= 2 failed, 1 passed in 0.03s ==========================
+";
+
+    const JUNIT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="cart" tests="3" failures="1" errors="1">
+    <testcase classname="cart" name="cart adds items" time="0.01"/>
+    <testcase classname="cart" name="cart totals &amp; taxes" file="src/cart.test.js" time="0.02">
+      <failure message="expected 10 to be 12">Error: expected 10 to be 12
+    at Object.&lt;anonymous&gt; (src/cart.test.js:14:5)</failure>
+    </testcase>
+    <testcase classname="checkout" name="pays" time="0.01">
+      <error message="TypeError: card is undefined"><![CDATA[at pay (src/checkout.js:3:9)]]></error>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+
+    #[test]
+    fn parses_cargo_test_output() {
+        let failures = parse(CARGO);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].name, "parser::handles_empty");
+        assert_eq!(failures[0].location(), "src/parser.rs:42");
+        assert!(failures[0].output.contains("left: 1"));
+        assert!(!failures[0].output.contains("math::adds"));
+        assert_eq!(failures[1].location(), "src/math.rs:7");
+    }
+
+    #[test]
+    fn parses_pytest_output() {
+        let failures = parse(PYTEST);
+        let names: Vec<&str> = failures.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["test_add", "TestDiv.test_by_zero"]);
+        assert_eq!(failures[0].location(), "tests/test_math.py:5");
+        assert!(failures[0].output.contains("E       assert 3 == 4"));
+        assert_eq!(failures[1].location(), "tests/test_math.py:12");
+    }
+
+    #[test]
+    fn parses_junit_xml() {
+        let failures = parse(JUNIT);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].name, "cart totals & taxes");
+        assert_eq!(failures[0].file.as_deref(), Some("src/cart.test.js"));
+        assert!(failures[0].output.contains("Object.<anonymous>"));
+        assert_eq!(failures[1].name, "checkout pays");
+        assert_eq!(
+            failures[1].output,
+            "TypeError: card is undefined\nat pay (src/checkout.js:3:9)"
+        );
+    }
+
+    #[test]
+    fn groups_and_names_tasks() {
+        let failures = parse(PYTEST);
+        let singles = group(failures.clone(), false, &["fix-test-add".to_string()]);
+        let names: Vec<&str> = singles.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["fix-test-add-2", "fix-test-by-zero"]);
+
+        let by_file = group(failures, true, &[]);
+        assert_eq!(by_file.len(), 1);
+        assert_eq!(by_file[0].name, "fix-test-math");
+        let p = prompt(&by_file[0], "pytest");
+        assert!(p.starts_with("Fix these 2 failing tests:\n- `test_add` (tests/test_math.py:5)"));
+        assert!(p.contains("`TestDiv.test_by_zero`:\n```\nself = <test_math.TestDiv object>"));
+        assert!(p.contains("Run `pytest` to confirm"));
+    }
+}
@@ -83,6 +83,17 @@ fn base_args() -> Vec<String> {
     args
 }
 
+/// Target for exactly the session `name`. Without the `=`, tmux falls back
+/// to prefix matching, so `pit-api` would hit `pit-api-v2`.
+fn session_target(name: &str) -> String {
+    format!("={}", name)
+}
+
+/// Target for the active pane of exactly the session `name`.
+fn pane_target(name: &str) -> String {
+    format!("={}:", name)
+}
+
 /// Check if tmux is available on the system.
 pub fn is_available() -> bool {
     Command::new("tmux")
@@ -95,7 +106,7 @@ pub fn is_available() -> bool {
 /// Check if a tmux session exists.
 pub fn session_exists(name: &str) -> bool {
     let mut args = base_args();
-    args.extend(["has-session".into(), "-t".into(), name.into()]);
+    args.extend(["has-session".into(), "-t".into(), session_target(name)]);
     Command::new("tmux")
         .args(&args)
         .output()
@@ -150,7 +161,7 @@ pub fn create_session_with_cmd(name: &str, cwd: &str, cmd: &str) -> Result<()> {
 /// Send keys to a tmux session (typically a command + Enter).
 pub fn send_keys(name: &str, keys: &[&str]) -> Result<()> {
     let mut args = base_args();
-    args.extend(["send-keys".into(), "-t".into(), name.into()]);
+    args.extend(["send-keys".into(), "-t".into(), pane_target(name)]);
     for k in keys {
         args.push(k.to_string());
     }
@@ -167,7 +178,7 @@ pub fn send_keys(name: &str, keys: &[&str]) -> Result<()> {
 /// Attach to a tmux session (interactive — takes over the terminal).
 pub fn attach(name: &str) -> Result<std::process::ExitStatus> {
     let mut args = base_args();
-    args.extend(["attach".into(), "-t".into(), name.into()]);
+    args.extend(["attach".into(), "-t".into(), session_target(name)]);
     let status = Command::new("tmux")
         .args(&args)
         .stdin(std::process::Stdio::inherit())
@@ -180,7 +191,7 @@ pub fn attach(name: &str) -> Result<std::process::ExitStatus> {
 /// Kill a tmux session.
 pub fn kill_session(name: &str) -> Result<()> {
     let mut args = base_args();
-    args.extend(["kill-session".into(), "-t".into(), name.into()]);
+    args.extend(["kill-session".into(), "-t".into(), session_target(name)]);
     let _ = Command::new("tmux").args(&args).output();
     Ok(())
 }
@@ -208,6 +219,28 @@ pub fn list_sessions() -> Result<Vec<String>> {
     Ok(sessions)
 }
 
+/// List all pit tmux sessions with the directory each was started in.
+pub fn list_sessions_with_paths() -> Result<Vec<(String, PathBuf)>> {
+    let mut args = base_args();
+    args.extend([
+        "list-sessions".into(),
+        "-F".into(),
+        "#{session_name}\t#{session_path}".into(),
+    ]);
+    let output = Command::new("tmux").args(&args).output()?;
+
+    if !output.status.success() {
+        return Ok(vec![]);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .map(|(name, path)| (name.to_string(), PathBuf::from(path)))
+        .collect())
+}
+
 /// Capture the last N lines from a tmux pane (for status preview).
 pub fn capture_pane(name: &str, lines: usize) -> Result<String> {
     let start = format!("-{}", lines);
@@ -215,7 +248,7 @@ pub fn capture_pane(name: &str, lines: usize) -> Result<String> {
     args.extend([
         "capture-pane".into(),
         "-t".into(),
-        name.into(),
+        pane_target(name),
         "-p".into(),
         "-S".into(),
         start,
@@ -235,6 +268,11 @@ pub fn session_name(task_name: &str) -> String {
     format!("pit-{}", task_name)
 }
 
+/// Get the tmux session name for a task's shell.
+pub fn shell_session_name(task_name: &str) -> String {
+    format!("pit-shell-{}", task_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,6 +334,26 @@ mod tests {
         kill_server(&sock);
     }
 
+    #[test]
+    fn targets_match_session_names_exactly() {
+        let sock = test_socket();
+        assert!(create(&sock, "pit-shell-api-v2"));
+
+        assert!(exists(&sock, "pit-shell-api"), "tmux prefix-matches");
+        assert!(!exists(&sock, &session_target("pit-shell-api")));
+        let _ = tmux_cmd(&sock)
+            .args(["kill-session", "-t", &session_target("pit-shell-api")])
+            .output();
+        assert!(exists(&sock, "pit-shell-api-v2"));
+        let captured = tmux_cmd(&sock)
+            .args(["capture-pane", "-p", "-t", &pane_target("pit-shell-api-v2")])
+            .output()
+            .unwrap();
+        assert!(captured.status.success());
+
+        kill_server(&sock);
+    }
+
     #[test]
     fn kill_session_removes_it() {
         let sock = test_socket();
@@ -0,0 +1,269 @@
+//! Where task worktrees live.
+//!
+//! By default each task's worktree is `.pit/worktrees/<name>` inside the main
+//! checkout. The `worktree_root` config key moves new worktrees elsewhere:
+//! `~` is the home directory, a relative path is relative to the repo root
+//! (`../myrepo-worktrees` for a sibling directory), and `{repo}` is replaced
+//! with an ID for the repo, so one root can be shared by every repo
+//! (`~/.local/share/pit/worktrees/{repo}`). [`migrate`] moves existing
+//! worktrees to the configured root.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use super::config;
+use super::task::{self, Status};
+use super::tmux;
+
+/// Where worktrees go when `worktree_root` isn't set.
+pub fn default_root(repo_root: &Path) -> PathBuf {
+    repo_root.join(".pit").join("worktrees")
+}
+
+/// The repo's worktree root.
+pub fn root(repo_root: &Path) -> PathBuf {
+    match config::get_for_repo(repo_root, "worktree_root") {
+        Some(value) if !value.trim().is_empty() => expand(repo_root, value.trim()),
+        _ => default_root(repo_root),
+    }
+}
+
+/// Where a new task's worktree goes.
+pub fn path(repo_root: &Path, name: &str) -> PathBuf {
+    root(repo_root).join(name)
+}
+
+/// Directories pit creates worktrees in: the configured root and the
+/// default one, which may still hold worktrees from before it was set.
+pub fn roots(repo_root: &Path) -> Vec<PathBuf> {
+    let mut roots = vec![root(repo_root)];
+    let default = default_root(repo_root);
+    if roots[0] != default {
+        roots.push(default);
+    }
+    roots
+}
+
+/// Whether pit created the worktree at `path` (as opposed to an adopted
+/// task's own worktree).
+pub fn is_managed(repo_root: &Path, path: &Path) -> bool {
+    roots(repo_root).iter().any(|r| path.starts_with(r))
+}
+
+/// Resolve a `worktree_root` value.
+pub fn expand(repo_root: &Path, value: &str) -> PathBuf {
+    let value = value.replace("{repo}", &repo_id(repo_root));
+    let path = match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join(rest.trim_start_matches('/')),
+        _ => repo_root.join(&value),
+    };
+    normalize(&path)
+}
+
+/// `<dir name>-<hash of the path>`: readable, and different for two
+/// checkouts with the same name.
+pub fn repo_id(repo_root: &Path) -> String {
+    // FNV-1a, so the ID doesn't change between builds
+    let hash = repo_root
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+    let name = repo_root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+    format!("{}-{:08x}", name, hash as u32)
+}
+
+/// Drop `.` and `..` without touching the filesystem, so `starts_with`
+/// works on paths that don't exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// What [`migrate`] did with one task's worktree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Move {
+    Moved {
+        task: String,
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Skipped {
+        task: String,
+        reason: String,
+    },
+}
+
+/// Move every task's worktree to where [`path`] puts it now, and update the
+/// task's `worktree`. Archived tasks and tasks that lost their worktree only
+/// get the new path. Running tasks and adopted tasks' own worktrees are left
+/// alone. With `dry_run`, nothing is changed.
+pub fn migrate(db: &Connection, repo_root: &Path, dry_run: bool) -> Result<Vec<Move>> {
+    let mut tasks = task::list(db)?;
+    tasks.extend(task::list_archived(db)?);
+    let mut moves = Vec::new();
+    for t in tasks {
+        let from = PathBuf::from(&t.worktree);
+        let to = path(repo_root, &t.name);
+        if from == to {
+            continue;
+        }
+        let skip = |reason: String| Move::Skipped {
+            task: t.name.clone(),
+            reason,
+        };
+        if t.adopted && !is_managed(repo_root, &from) {
+            moves.push(skip("adopted with its own worktree".to_string()));
+            continue;
+        }
+        if t.status == Status::Running {
+            moves.push(skip("running — stop it first".to_string()));
+            continue;
+        }
+        if tmux::session_exists(&tmux::shell_session_name(&t.name)) {
+            moves.push(skip(format!(
+                "its shell session {} is open",
+                tmux::shell_session_name(&t.name)
+            )));
+            continue;
+        }
+        if to.exists() {
+            moves.push(skip(format!("{} already exists", to.display())));
+            continue;
+        }
+        let to_str = to.to_str().context("worktree path is not valid UTF-8")?;
+
+        if !dry_run {
+            if t.archived_at.is_empty() && from.exists() {
+                if let Some(parent) = to.parent() {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("failed to create {}", parent.display()))?;
+                }
+                if let Err(e) = git(repo_root, &["worktree", "move", &t.worktree, to_str]) {
+                    moves.push(skip(e.to_string()));
+                    continue;
+                }
+            }
+            task::set_worktree(db, t.id, to_str)?;
+        }
+        moves.push(Move::Moved {
+            task: t.name.clone(),
+            from,
+            to,
+        });
+    }
+
+    // The old default root is empty once everything has moved out
+    if !dry_run && root(repo_root) != default_root(repo_root) {
+        let _ = std::fs::remove_dir(default_root(repo_root));
+    }
+    Ok(moves)
+}
+
+/// Run git in `dir` and return trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use task::CreateOpts;
+
+    /// A repo at `<tmp>/app`, so the sibling directory stays in `<tmp>`.
+    fn setup() -> (tempfile::TempDir, PathBuf, Connection) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("app");
+        std::fs::create_dir(&repo).unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "test@test.com"],
+            &["config", "user.name", "Test"],
+            &["commit", "--allow-empty", "-m", "init"],
+            &["branch", "-M", "main"],
+        ] {
+            git(&repo, args).unwrap();
+        }
+        (dir, repo, crate::db::open_memory().unwrap())
+    }
+
+    #[test]
+    fn expand_resolves_home_sibling_and_repo_id() {
+        let repo = Path::new("/src/app");
+        assert_eq!(expand(repo, "../app-wt"), PathBuf::from("/src/app-wt"));
+        assert_eq!(expand(repo, "/srv/wt"), PathBuf::from("/srv/wt"));
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand(repo, "~/wt"), home.join("wt"));
+
+        let shared = expand(repo, "/wt/{repo}");
+        assert!(shared.to_string_lossy().starts_with("/wt/app-"));
+        assert_ne!(shared, expand(Path::new("/other/app"), "/wt/{repo}"));
+        assert_eq!(shared, expand(repo, "/wt/{repo}"));
+    }
+
+    #[test]
+    fn migrate_moves_worktrees_to_the_configured_root() {
+        let (dir, repo, db) = setup();
+        let t = task::create(
+            &db,
+            &repo,
+            &CreateOpts {
+                name: "parser",
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let old = PathBuf::from(&t.worktree);
+        assert!(old.starts_with(default_root(&repo)));
+        std::fs::write(old.join("wip.txt"), "wip").unwrap();
+
+        config::set_in(
+            &config::repo_config_path(&repo),
+            "worktree_root",
+            "../app-worktrees",
+        )
+        .unwrap();
+        let new = dir.path().join("app-worktrees").join("parser");
+        assert_eq!(path(&repo, "parser"), new);
+        assert!(is_managed(&repo, &old));
+
+        let planned = migrate(&db, &repo, true).unwrap();
+        assert!(matches!(&planned[..], [Move::Moved { to, .. }] if *to == new));
+        assert!(old.exists());
+
+        migrate(&db, &repo, false).unwrap();
+        assert!(!old.exists());
+        assert_eq!(std::fs::read_to_string(new.join("wip.txt")).unwrap(), "wip");
+        let moved = task::get(&db, t.id).unwrap().unwrap();
+        assert_eq!(PathBuf::from(&moved.worktree), new);
+        assert!(git(&new, &["status", "--porcelain"]).is_ok());
+        assert!(!default_root(&repo).exists());
+        assert!(migrate(&db, &repo, false).unwrap().is_empty());
+    }
+}
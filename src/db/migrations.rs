@@ -7,6 +7,14 @@ const MIGRATIONS: &[(i64, &str, &str)] = &[
     (1, "initial schema", MIGRATION_001),
     (2, "add prompt and issue_url", MIGRATION_002),
     (3, "add agent column", MIGRATION_003),
+    (4, "add model column", MIGRATION_004),
+    (5, "add pull request columns", MIGRATION_005),
+    (6, "add merge queue", MIGRATION_006),
+    (7, "add test result columns", MIGRATION_007),
+    (8, "add harvested marker columns", MIGRATION_008),
+    (9, "add adopted column", MIGRATION_009),
+    (10, "add fork parent columns", MIGRATION_010),
+    (11, "add archived_at column", MIGRATION_011),
 ];
 
 const MIGRATION_001: &str = "
@@ -35,6 +43,50 @@ const MIGRATION_003: &str = "
 ALTER TABLE tasks ADD COLUMN agent TEXT NOT NULL DEFAULT 'claude';
 ";
 
+const MIGRATION_004: &str = "
+ALTER TABLE tasks ADD COLUMN model TEXT NOT NULL DEFAULT '';
+";
+
+const MIGRATION_005: &str = "
+ALTER TABLE tasks ADD COLUMN pr_url TEXT NOT NULL DEFAULT '';
+ALTER TABLE tasks ADD COLUMN pr_state TEXT NOT NULL DEFAULT '';
+";
+
+const MIGRATION_006: &str = "
+CREATE TABLE merge_queue (
+    task_id     INTEGER PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
+    position    INTEGER NOT NULL,
+    error       TEXT    NOT NULL DEFAULT '',
+    queued_at   TEXT    NOT NULL DEFAULT (datetime('now'))
+);
+";
+
+const MIGRATION_007: &str = "
+ALTER TABLE tasks ADD COLUMN test_status TEXT NOT NULL DEFAULT '';
+ALTER TABLE tasks ADD COLUMN test_duration_ms INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN test_summary TEXT NOT NULL DEFAULT '';
+ALTER TABLE tasks ADD COLUMN tested_at TEXT NOT NULL DEFAULT '';
+";
+
+const MIGRATION_008: &str = "
+ALTER TABLE tasks ADD COLUMN source_file TEXT NOT NULL DEFAULT '';
+ALTER TABLE tasks ADD COLUMN source_line INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN source_marker TEXT NOT NULL DEFAULT '';
+";
+
+const MIGRATION_009: &str = "
+ALTER TABLE tasks ADD COLUMN adopted INTEGER NOT NULL DEFAULT 0;
+";
+
+const MIGRATION_010: &str = "
+ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks(id) ON DELETE SET NULL;
+ALTER TABLE tasks ADD COLUMN fork_point TEXT NOT NULL DEFAULT '';
+";
+
+const MIGRATION_011: &str = "
+ALTER TABLE tasks ADD COLUMN archived_at TEXT NOT NULL DEFAULT '';
+";
+
 /// Run all pending migrations inside a transaction.
 pub fn run(conn: &Connection) -> Result<()> {
     // Ensure the schema_version table exists
@@ -82,7 +134,7 @@ mod tests {
         let version: i64 = conn
             .query_row("SELECT MAX(version) FROM schema_version", [], |r| r.get(0))
             .unwrap();
-        assert_eq!(version, 3);
+        assert_eq!(version, 11);
     }
 
     #[test]
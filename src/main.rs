@@ -2,14 +2,32 @@ mod core;
 mod db;
 mod tui;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
-
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+use crate::core::adopt;
+use crate::core::archive;
+use crate::core::checks;
+use crate::core::doctor;
+use crate::core::editor;
+use crate::core::fork;
+use crate::core::handoff;
+use crate::core::harvest;
+use crate::core::issues;
+use crate::core::overlap;
+use crate::core::pr;
 use crate::core::project::Project;
+use crate::core::queue;
 use crate::core::reap;
+use crate::core::repair;
+use crate::core::squash;
+use crate::core::sync;
 use crate::core::task;
+use crate::core::template;
+use crate::core::test_failures;
 use crate::core::tmux;
+use crate::core::worktrees;
 
 #[derive(Parser)]
 #[command(
@@ -28,26 +46,71 @@ enum Commands {
     Init,
 
     /// Create a new task (git branch + worktree)
-    New {
-        /// Task name (alphanumeric, hyphens, underscores)
-        name: String,
-        /// Description of what the agent should do
-        #[arg(short, long, default_value = "")]
-        description: String,
+    New(NewArgs),
+
+    /// Start a new task from another task's current state or a checkpoint
+    Fork {
+        /// Task to fork
+        task: String,
+        /// Name of the new task
+        new_name: String,
+        /// Start from this checkpoint instead of the current state
+        #[arg(long, value_name = "N")]
+        at_checkpoint: Option<usize>,
+    },
+
+    /// Register an existing branch or worktree as a task
+    Adopt {
+        /// Branch to adopt (kept as is)
+        #[arg(required_unless_present = "worktree")]
+        branch: Option<String>,
+        /// Task name (default: the last part of the branch name)
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Existing worktree to adopt instead of creating one
+        #[arg(short, long)]
+        worktree: Option<PathBuf>,
         /// Prompt to send to the agent on first launch
         #[arg(short, long, default_value = "")]
         prompt: String,
-        /// Link to an issue (GitHub, Linear, etc.)
+        /// Agent to use [default: claude]
         #[arg(short, long, default_value = "")]
-        issue: String,
-        /// Agent to use (claude, codex, amp, aider, custom)
-        #[arg(short, long, default_value = "claude")]
         agent: String,
+        /// Model to pass to the agent
+        #[arg(short, long, default_value = "")]
+        model: String,
+    },
+
+    /// Create tasks from TODO(agent) comments in the repo
+    Harvest {
+        /// Extended regex the marker comments match [default: config
+        /// harvest_pattern, or TODO\(agent\)]
+        #[arg(long)]
+        pattern: Option<String>,
+        /// Create tasks for these markers from the list (e.g. 1,3-5)
+        #[arg(long, conflicts_with = "all")]
+        pick: Option<String>,
+        /// Create tasks for every marker that doesn't have one yet
+        #[arg(long)]
+        all: bool,
+        /// Agent for the new tasks [default: claude]
+        #[arg(short, long)]
+        agent: Option<String>,
+        /// Model to pass to the agent
+        #[arg(short, long, default_value = "")]
+        model: String,
+        /// Show harvested tasks and whether their marker is still there
+        #[arg(long, conflicts_with_all = ["pick", "all"])]
+        status: bool,
     },
 
     /// List all tasks
     #[command(alias = "ls")]
-    List,
+    List {
+        /// List archived tasks instead
+        #[arg(long)]
+        archived: bool,
+    },
 
     /// Show task status (with live tmux reaping)
     Status,
@@ -77,6 +140,27 @@ enum Commands {
         name: String,
     },
 
+    /// Edit a task's prompt, agent and metadata (opens $EDITOR without flags)
+    Edit(EditArgs),
+
+    /// Hand a task over to a different agent with a generated handoff brief
+    Reassign {
+        /// Task name
+        name: String,
+        /// Agent to switch to
+        #[arg(short, long)]
+        agent: String,
+        /// Model for the new agent (default: the agent's own default)
+        #[arg(short, long, default_value = "")]
+        model: String,
+        /// Start the new agent right away (default: only if the task was running)
+        #[arg(long)]
+        run: bool,
+        /// Print the handoff brief without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Delete a task (removes worktree and branch)
     #[command(alias = "rm")]
     Delete {
@@ -84,10 +168,63 @@ enum Commands {
         name: String,
     },
 
-    /// Save a checkpoint of a task's current state
+    /// Archive a task: remove its worktree but keep its branch, checkpoints
+    /// and metadata
+    Archive {
+        /// Task name
+        #[arg(required_unless_present = "purge_older_than")]
+        name: Option<String>,
+        /// Delete tasks archived more than DAYS days ago instead
+        #[arg(long, value_name = "DAYS", conflicts_with = "name")]
+        purge_older_than: Option<u32>,
+    },
+
+    /// Bring back an archived task
+    Restore {
+        /// Task name
+        name: String,
+    },
+
+    /// Check pit's tools, config and task state for problems
+    Doctor,
+
+    /// Fix or prune what `pit doctor` finds, and show disk usage per worktree
+    Gc {
+        /// Show what would be done without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show where task worktrees live (or move them to the configured root)
+    Worktrees {
+        #[command(subcommand)]
+        action: Option<WorktreesAction>,
+    },
+
+    /// Recover tasks that are in git but missing from the database
+    Repair {
+        /// Start from an empty database (the old one is kept as pit.db.bak)
+        #[arg(long)]
+        rebuild: bool,
+    },
+
+    /// Save a checkpoint of a task's current state (or show/diff checkpoints)
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Checkpoint {
+        #[command(subcommand)]
+        action: Option<CheckpointAction>,
+        /// Task name
+        #[arg(required = true)]
+        name: Option<String>,
+    },
+
+    /// List a task's checkpoints
+    Checkpoints {
         /// Task name
         name: String,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Rollback a task to the last checkpoint (or a specific one)
@@ -95,8 +232,87 @@ enum Commands {
         /// Task name
         name: String,
         /// Checkpoint number to rollback to (default: latest)
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "undo")]
         to: Option<usize>,
+        /// Undo the last rollback
+        #[arg(long)]
+        undo: bool,
+        /// Pick hunks interactively (all files unless paths are given)
+        #[arg(short, long, conflicts_with = "undo")]
+        patch: bool,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+        /// Restore only these paths (relative to the worktree)
+        #[arg(last = true, conflicts_with = "undo")]
+        paths: Vec<String>,
+    },
+
+    /// Squash a task branch into one commit (or one per checkpoint)
+    Squash {
+        /// Task name
+        name: String,
+        /// Keep one commit per checkpoint instead of a single commit
+        #[arg(long)]
+        by_checkpoint: bool,
+        /// Squash without the preview prompt
+        #[arg(short, long)]
+        yes: bool,
+        /// Put the branch back the way it was before the last squash
+        #[arg(long, conflicts_with_all = ["by_checkpoint", "yes"])]
+        undo: bool,
+    },
+
+    /// Push a task branch and open a GitHub pull request for it
+    Pr {
+        /// Task name
+        name: String,
+        /// Remote to push to (default: config pr.remote, or origin)
+        #[arg(short, long)]
+        remote: Option<String>,
+        /// Open the pull request as a draft
+        #[arg(long)]
+        draft: bool,
+    },
+
+    /// Run the configured lint and test commands in a task's worktree
+    Test {
+        /// Task name
+        name: String,
+        /// Don't print the commands' output
+        #[arg(short, long)]
+        quiet: bool,
+    },
+
+    /// Rebase a task branch onto the latest base branch
+    Sync {
+        /// Task name
+        name: String,
+        /// Merge the base into the branch instead of rebasing
+        #[arg(long)]
+        merge: bool,
+        /// On conflicts, launch the task's agent to resolve them
+        #[arg(long)]
+        resolve: bool,
+    },
+
+    /// List active tasks that change the same files and would conflict
+    Conflicts {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Queue tasks and land them onto the base branch one at a time
+    QueueMerge {
+        /// Tasks to add to the end of the queue
+        names: Vec<String>,
+        /// Show the queue without landing anything
+        #[arg(long, conflicts_with = "clear")]
+        list: bool,
+        /// Empty the queue
+        #[arg(long)]
+        clear: bool,
     },
 
     /// Watch live output from a running task
@@ -115,6 +331,42 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum CheckpointAction {
+    /// Show a checkpoint's annotation and changes
+    Show {
+        /// Task name
+        name: String,
+        /// Checkpoint number
+        index: usize,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Diff two checkpoints
+    Diff {
+        /// Task name
+        name: String,
+        /// Checkpoint to diff from
+        from: usize,
+        /// Checkpoint to diff to
+        to: usize,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorktreesAction {
+    /// Move existing worktrees to the worktree root (config worktree_root)
+    Migrate {
+        /// Show what would move without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum ConfigAction {
     /// Set a config value (e.g. pit config set linear.api_key <key>)
@@ -123,16 +375,25 @@ enum ConfigAction {
         key: String,
         /// Value to set
         value: String,
+        /// Store in this repo's .pit/config.toml instead
+        #[arg(long)]
+        repo: bool,
     },
     /// Get a config value
     Get {
         /// Config key
         key: String,
+        /// Include this repo's .pit/config.toml
+        #[arg(long)]
+        repo: bool,
     },
     /// Remove a config value
     Unset {
         /// Config key
         key: String,
+        /// Remove from this repo's .pit/config.toml instead
+        #[arg(long)]
+        repo: bool,
     },
     /// List all config values
     #[command(alias = "ls")]
@@ -141,28 +402,195 @@ enum ConfigAction {
     Path,
 }
 
+#[derive(Args)]
+struct NewArgs {
+    /// Task name (alphanumeric, hyphens, underscores)
+    #[arg(required_unless_present = "from_test_failures")]
+    name: Option<String>,
+    /// Description of what the agent should do
+    #[arg(short, long, default_value = "")]
+    description: String,
+    /// Prompt to send to the agent on first launch
+    #[arg(short, long, default_value = "")]
+    prompt: String,
+    /// Link to an issue (GitHub, Linear, etc.)
+    #[arg(short, long, default_value = "")]
+    issue: String,
+    /// Agent to use (claude, codex, amp, aider, custom) [default: claude]
+    #[arg(short, long)]
+    agent: Option<String>,
+    /// Model to pass to the agent
+    #[arg(short, long)]
+    model: Option<String>,
+    /// Template from .pit/templates/ to build the prompt from
+    #[arg(short, long)]
+    template: Option<String>,
+    /// Template variable (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    vars: Vec<String>,
+    /// Run the repo's test_command and create a task per failing test
+    #[arg(long, conflicts_with_all = ["name", "prompt", "issue", "template"])]
+    from_test_failures: bool,
+    /// JUnit XML report the test command writes, parsed instead of its output
+    #[arg(long, value_name = "PATH", requires = "from_test_failures")]
+    junit: Option<PathBuf>,
+    /// One task per failing test file instead of per test
+    #[arg(long, requires = "from_test_failures")]
+    group: bool,
+    /// Launch each new task's agent right away
+    #[arg(long, requires = "from_test_failures")]
+    run: bool,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    /// Task name
+    name: String,
+    /// New description
+    #[arg(short, long)]
+    description: Option<String>,
+    /// New prompt
+    #[arg(short, long)]
+    prompt: Option<String>,
+    /// New issue link
+    #[arg(short, long)]
+    issue: Option<String>,
+    /// New agent (clears the saved session if it changes)
+    #[arg(short, long)]
+    agent: Option<String>,
+    /// New model
+    #[arg(short, long)]
+    model: Option<String>,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
         None => cmd_dashboard()?,
         Some(Commands::Init) => cmd_init()?,
-        Some(Commands::New {
+        Some(Commands::New(args)) => cmd_new(args)?,
+        Some(Commands::Fork {
+            task,
+            new_name,
+            at_checkpoint,
+        }) => cmd_fork(&task, &new_name, at_checkpoint)?,
+        Some(Commands::Adopt {
+            branch,
             name,
-            description,
+            worktree,
             prompt,
-            issue,
             agent,
-        }) => cmd_new(&name, &description, &prompt, &issue, &agent)?,
-        Some(Commands::List) => cmd_list()?,
+            model,
+        }) => cmd_adopt(
+            branch.as_deref(),
+            worktree.as_deref(),
+            &task::CreateOpts {
+                name: name.as_deref().unwrap_or(""),
+                prompt: &prompt,
+                agent: &agent,
+                model: &model,
+                ..Default::default()
+            },
+        )?,
+        Some(Commands::Harvest {
+            pattern,
+            pick,
+            all,
+            agent,
+            model,
+            status,
+        }) => {
+            if status {
+                cmd_harvest_status()?
+            } else {
+                let agent = agent.as_deref().unwrap_or("claude");
+                cmd_harvest(pattern, pick.as_deref(), all, agent, &model)?
+            }
+        }
+        Some(Commands::List { archived }) => {
+            if archived {
+                cmd_list_archived()?
+            } else {
+                cmd_list()?
+            }
+        }
         Some(Commands::Status) => cmd_status()?,
         Some(Commands::Run { name }) => cmd_run(&name)?,
         Some(Commands::Stop { name }) => cmd_stop(&name)?,
         Some(Commands::Diff { name }) => cmd_diff(&name)?,
         Some(Commands::Shell { name }) => cmd_shell(&name)?,
+        Some(Commands::Edit(args)) => cmd_edit(args)?,
+        Some(Commands::Reassign {
+            name,
+            agent,
+            model,
+            run,
+            dry_run,
+        }) => cmd_reassign(&name, &agent, &model, run, dry_run)?,
         Some(Commands::Delete { name }) => cmd_delete(&name)?,
-        Some(Commands::Checkpoint { name }) => cmd_checkpoint(&name)?,
-        Some(Commands::Rollback { name, to }) => cmd_rollback(&name, to)?,
+        Some(Commands::Archive {
+            name,
+            purge_older_than,
+        }) => match (name, purge_older_than) {
+            (_, Some(days)) => cmd_archive_purge(days)?,
+            (Some(name), None) => cmd_archive(&name)?,
+            (None, None) => unreachable!("clap requires a name or --purge-older-than"),
+        },
+        Some(Commands::Restore { name }) => cmd_restore(&name)?,
+        Some(Commands::Repair { rebuild }) => cmd_repair(rebuild)?,
+        Some(Commands::Doctor) => cmd_doctor()?,
+        Some(Commands::Gc { dry_run }) => cmd_gc(dry_run)?,
+        Some(Commands::Worktrees { action }) => match action {
+            Some(WorktreesAction::Migrate { dry_run }) => cmd_worktrees_migrate(dry_run)?,
+            None => cmd_worktrees()?,
+        },
+        Some(Commands::Checkpoint { action, name }) => match action {
+            Some(CheckpointAction::Show { name, index, json }) => {
+                cmd_checkpoint_show(&name, index, json)?
+            }
+            Some(CheckpointAction::Diff {
+                name,
+                from,
+                to,
+                json,
+            }) => cmd_checkpoint_diff(&name, from, to, json)?,
+            None => cmd_checkpoint(&name.unwrap_or_default())?,
+        },
+        Some(Commands::Checkpoints { name, json }) => cmd_checkpoints(&name, json)?,
+        Some(Commands::Rollback {
+            name,
+            to,
+            undo,
+            patch,
+            json,
+            paths,
+        }) => {
+            if patch || !paths.is_empty() {
+                cmd_restore_paths(&name, to, paths, patch, json)?
+            } else {
+                cmd_rollback(&name, to, undo, json)?
+            }
+        }
+        Some(Commands::Squash {
+            name,
+            by_checkpoint,
+            yes,
+            undo,
+        }) => cmd_squash(&name, by_checkpoint, yes, undo)?,
+        Some(Commands::Pr {
+            name,
+            remote,
+            draft,
+        }) => cmd_pr(&name, remote, draft)?,
+        Some(Commands::Test { name, quiet }) => cmd_test(&name, quiet)?,
+        Some(Commands::Sync {
+            name,
+            merge,
+            resolve,
+        }) => cmd_sync(&name, merge, resolve)?,
+        Some(Commands::Conflicts { json }) => cmd_conflicts(json)?,
+        Some(Commands::QueueMerge { names, list, clear }) => cmd_queue_merge(&names, list, clear)?,
         Some(Commands::Watch { name, lines }) => cmd_watch(&name, lines)?,
         Some(Commands::Config { action }) => cmd_config(action)?,
     }
@@ -183,17 +611,77 @@ fn cmd_init() -> Result<()> {
     Ok(())
 }
 
-fn cmd_new(name: &str, description: &str, prompt: &str, issue: &str, agent: &str) -> Result<()> {
+fn cmd_new(args: NewArgs) -> Result<()> {
+    if args.from_test_failures {
+        return cmd_new_from_test_failures(&args);
+    }
+    let name = args.name.as_deref().unwrap_or_default();
     let project = open_project()?;
+    let user_vars = args
+        .vars
+        .iter()
+        .map(|v| template::parse_var(v))
+        .collect::<Result<Vec<_>>>()?;
+    let tpl = match &args.template {
+        Some(name) => Some(template::load(&project.repo_root, name)?),
+        None => None,
+    };
+
+    // Pull in the issue's full context (comments, links…) when one is given.
+    // A fetch failure shouldn't stop the task from being created.
+    let fetched = if args.issue.is_empty() {
+        None
+    } else {
+        match issues::fetch(&args.issue) {
+            Ok(i) => Some(i),
+            Err(e) => {
+                eprintln!("warning: could not fetch issue: {:#}", e);
+                None
+            }
+        }
+    };
+
+    let mut prompt = args.prompt.clone();
+    if let Some(tpl) = &tpl {
+        if prompt.is_empty() {
+            prompt = tpl.body.clone();
+        }
+        let base = get_main_branch(&project.repo_root).unwrap_or_else(|_| "main".to_string());
+        let vars = template::variables(name, &base, fetched.as_ref(), &user_vars);
+        let missing = template::unresolved(&prompt, &vars);
+        if !missing.is_empty() {
+            let hint: Vec<String> = missing.iter().map(|m| format!("--var {}=…", m)).collect();
+            anyhow::bail!(
+                "template '{}' needs values for: {}",
+                tpl.name,
+                hint.join(" ")
+            );
+        }
+        prompt = template::render(&prompt, &vars);
+    }
+    let prompt = match &fetched {
+        Some(i) => issues::prompt_for(&prompt, i),
+        None => prompt,
+    };
+
+    let template_agent = tpl.as_ref().map(|t| t.agent.as_str()).unwrap_or("");
+    let agent = args.agent.as_deref().unwrap_or(match template_agent {
+        "" => "claude",
+        a => a,
+    });
+    let template_model = tpl.as_ref().map(|t| t.model.as_str()).unwrap_or("");
+    let model = args.model.as_deref().unwrap_or(template_model);
+
     let t = task::create(
         &project.db,
         &project.repo_root,
         &task::CreateOpts {
             name,
-            description,
-            prompt,
-            issue_url: issue,
+            description: &args.description,
+            prompt: &prompt,
+            issue_url: &args.issue,
             agent,
+            model,
         },
     )?;
     println!(
@@ -201,12 +689,213 @@ fn cmd_new(name: &str, description: &str, prompt: &str, issue: &str, agent: &str
         t.name, t.branch, t.agent
     );
     println!("  worktree: {}", t.worktree);
+    if !t.model.is_empty() {
+        println!("  model: {}", t.model);
+    }
+    if let Some(i) = &fetched {
+        let path = issues::write_context_file(Path::new(&t.worktree), i)?;
+        println!("  context: {}", path.display());
+    }
+    if let Some(tpl) = &tpl {
+        println!("  template: {}", tpl.name);
+        if let Err(e) = template::run_setup_hook(tpl, Path::new(&t.worktree)) {
+            eprintln!("warning: {:#}", e);
+        }
+    }
     if !t.prompt.is_empty() {
         println!("  prompt: {}", t.prompt);
     }
     Ok(())
 }
 
+fn cmd_new_from_test_failures(args: &NewArgs) -> Result<()> {
+    let project = open_project()?;
+    let Some(test_cmd) = checks::Commands::from_config(&project.repo_root).test else {
+        anyhow::bail!(
+            "no test_command configured (pit config set --repo test_command \"cargo test\")"
+        );
+    };
+
+    println!("Running '{}'...", test_cmd);
+    let (code, output) = checks::run_one(&test_cmd, &project.repo_root, false)?;
+    if code == Some(0) {
+        println!("All tests pass — no tasks created");
+        return Ok(());
+    }
+    let failures = match &args.junit {
+        Some(path) => {
+            let xml = std::fs::read_to_string(project.repo_root.join(path))
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            test_failures::parse_junit(&xml)
+        }
+        None => test_failures::parse(&output),
+    };
+    if failures.is_empty() {
+        print!("{}", output);
+        anyhow::bail!(
+            "'{}' failed but no failing tests were recognized (for other runners, write a JUnit report and pass --junit <path>)",
+            test_cmd
+        );
+    }
+
+    let existing = task::names(&project.db)?;
+    let groups = test_failures::group(failures, args.group, &existing);
+    let agent = args.agent.as_deref().unwrap_or("claude");
+    let model = args.model.as_deref().unwrap_or("");
+    println!("{} failing test group(s):", groups.len());
+    for g in &groups {
+        let tests: Vec<&str> = g.failures.iter().map(|f| f.name.as_str()).collect();
+        let description = match &args.description[..] {
+            "" => format!("Fix failing: {}", tests.join(", ")),
+            d => d.to_string(),
+        };
+        let t = task::create(
+            &project.db,
+            &project.repo_root,
+            &task::CreateOpts {
+                name: &g.name,
+                description: &description,
+                prompt: &test_failures::prompt(g, &test_cmd),
+                issue_url: "",
+                agent,
+                model,
+            },
+        )?;
+        let at = g.failures[0].location();
+        if at.is_empty() {
+            println!("  ✓ {}  {}", t.name, tests.join(", "));
+        } else {
+            println!("  ✓ {}  {} ({})", t.name, tests.join(", "), at);
+        }
+    }
+    if args.run {
+        for g in &groups {
+            cmd_run(&g.name)?;
+        }
+    } else {
+        println!("Start them with: pit run <name>, or from the dashboard");
+    }
+    Ok(())
+}
+
+fn cmd_fork(source: &str, name: &str, at: Option<usize>) -> Result<()> {
+    let project = open_project()?;
+    let source = task::get_by_name(&project.db, source)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", source))?;
+    let t = fork::fork(&project.db, &project.repo_root, &source, name, at)?;
+    println!(
+        "Forked '{}' into '{}' at {} (agent: {})",
+        source.name, t.name, t.fork_point, t.agent
+    );
+    println!("  worktree: {}", t.worktree);
+    println!("  Start it with: pit run {}", t.name);
+    Ok(())
+}
+
+fn cmd_adopt(branch: Option<&str>, worktree: Option<&Path>, opts: &task::CreateOpts) -> Result<()> {
+    let project = open_project()?;
+    let t = adopt::adopt(&project.db, &project.repo_root, branch, worktree, opts)?;
+    println!(
+        "Adopted branch '{}' as task '{}' (agent: {})",
+        t.branch, t.name, t.agent
+    );
+    println!("  worktree: {}", t.worktree);
+    println!("  Start it with: pit run {}", t.name);
+    Ok(())
+}
+
+fn cmd_harvest(
+    pattern: Option<String>,
+    pick: Option<&str>,
+    all: bool,
+    agent: &str,
+    model: &str,
+) -> Result<()> {
+    let project = open_project()?;
+    let pattern = pattern.unwrap_or_else(|| harvest::pattern(&project.repo_root));
+    let markers = harvest::scan(&project.repo_root, &pattern)?;
+    if markers.is_empty() {
+        println!("No markers matching '{}'", pattern);
+        return Ok(());
+    }
+    let tasks = task::list(&project.db)?;
+    for (i, m) in markers.iter().enumerate() {
+        let taken = match harvest::harvested_by(m, &tasks) {
+            Some(t) => format!("  (task {})", t.name),
+            None => String::new(),
+        };
+        println!(
+            "{:>3}. {}:{}  {}{}",
+            i + 1,
+            m.file,
+            m.line,
+            m.summary(),
+            taken
+        );
+    }
+
+    let selection = match pick {
+        Some(p) => p.to_string(),
+        None if all => format!("1-{}", markers.len()),
+        None if std::io::IsTerminal::is_terminal(&std::io::stdin()) => {
+            print!("Create tasks for (e.g. 1,3-5 or 'all'; Enter for none): ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            match answer.trim() {
+                "all" | "a" => format!("1-{}", markers.len()),
+                a => a.to_string(),
+            }
+        }
+        None => {
+            println!("Create tasks with: pit harvest --pick 1,3-5 (or --all)");
+            return Ok(());
+        }
+    };
+
+    let mut created = 0;
+    for i in harvest::parse_selection(&selection, markers.len())? {
+        let m = &markers[i];
+        if let Some(t) = harvest::harvested_by(m, &tasks) {
+            // Explicit picks are reported, --all skips silently
+            if pick.is_some() {
+                println!("  · {}:{} already has task '{}'", m.file, m.line, t.name);
+            }
+            continue;
+        }
+        let t = harvest::create(&project.db, &project.repo_root, m, agent, model)?;
+        println!("✓ Created task '{}' from {}:{}", t.name, m.file, m.line);
+        created += 1;
+    }
+    if created > 0 {
+        println!("Start them with: pit run <name>, or from the dashboard");
+    }
+    Ok(())
+}
+
+fn cmd_harvest_status() -> Result<()> {
+    let project = open_project()?;
+    let tasks: Vec<task::Task> = task::list(&project.db)?
+        .into_iter()
+        .filter(|t| !t.source_file.is_empty())
+        .collect();
+    if tasks.is_empty() {
+        println!("No harvested tasks. Create some with: pit harvest");
+        return Ok(());
+    }
+    for t in &tasks {
+        let (icon, state) = match harvest::marker_present(t) {
+            Some(false) => ("✓", "marker removed"),
+            _ => ("·", "marker still there"),
+        };
+        println!(
+            "{} {:<30} {}:{}  {}",
+            icon, t.name, t.source_file, t.source_line, state
+        );
+    }
+    Ok(())
+}
+
 fn cmd_list() -> Result<()> {
     let project = open_project()?;
     reap::reap_dead(&project.db, &project.repo_root)?;
@@ -226,6 +915,33 @@ fn cmd_list() -> Result<()> {
     Ok(())
 }
 
+fn cmd_list_archived() -> Result<()> {
+    let project = open_project()?;
+    let tasks = task::list_archived(&project.db)?;
+
+    if tasks.is_empty() {
+        println!("No archived tasks.");
+        return Ok(());
+    }
+
+    println!("{:<4} {:<20} {:<20} KEPT AT", "ID", "NAME", "ARCHIVED");
+    println!("{}", "-".repeat(70));
+    for t in &tasks {
+        println!(
+            "{:<4} {:<20} {:<20} {}",
+            t.id,
+            t.name,
+            t.archived_at,
+            archive::archive_ref(&t.name)
+        );
+    }
+    println!(
+        "\n{} archived task(s) — bring one back with: pit restore <name>",
+        tasks.len()
+    );
+    Ok(())
+}
+
 fn cmd_status() -> Result<()> {
     let project = open_project()?;
     let reaped = reap::reap_dead(&project.db, &project.repo_root)?;
@@ -287,7 +1003,7 @@ fn cmd_shell(name: &str) -> Result<()> {
     let t = task::get_by_name(&project.db, name)?
         .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
 
-    let tmux_name = format!("pit-shell-{}", t.name);
+    let tmux_name = tmux::shell_session_name(&t.name);
 
     if !tmux::session_exists(&tmux_name) {
         let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
@@ -370,6 +1086,7 @@ fn cmd_checkpoint(name: &str) -> Result<()> {
         .and_then(|name| tmux::capture_pane(name, 50).ok());
     let idx = core::checkpoint::create(
         &project.repo_root,
+        t.id,
         &t.name,
         &t.branch,
         worktree,
@@ -377,7 +1094,7 @@ fn cmd_checkpoint(name: &str) -> Result<()> {
     )?;
 
     // Show the new checkpoint with its annotation
-    let checkpoints = core::checkpoint::list(&project.repo_root, &t.name)?;
+    let checkpoints = core::checkpoint::list(&project.repo_root, t.id)?;
     println!("✓ Checkpoint #{} saved for '{}'\n", idx, name);
 
     if let Some(cp) = checkpoints.iter().find(|c| c.index == idx) {
@@ -389,17 +1106,107 @@ fn cmd_checkpoint(name: &str) -> Result<()> {
     // Summary of all checkpoints
     println!("All checkpoints:");
     for cp in &checkpoints {
+        let dirty = if cp.dirty { " +uncommitted" } else { "" };
+        let auto = if cp.auto { " (auto)" } else { "" };
         let marker = if cp.index == idx { " ← new" } else { "" };
         println!(
-            "  #{}: {} {}  {}{}",
-            cp.index, cp.commit_hash, cp.message, cp.timestamp, marker
+            "  #{}: {} {}{}{}  {}{}",
+            cp.index, cp.commit_hash, cp.message, dirty, auto, cp.timestamp, marker
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_checkpoints(name: &str, json: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+    let checkpoints = core::checkpoint::list(&project.repo_root, t.id)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checkpoints)?);
+        return Ok(());
+    }
+    if checkpoints.is_empty() {
+        println!(
+            "No checkpoints for '{}'. Save one with: pit checkpoint {}",
+            name, name
+        );
+        return Ok(());
+    }
+    for cp in &checkpoints {
+        let dirty = if cp.dirty { " +uncommitted" } else { "" };
+        let auto = if cp.auto { " (auto)" } else { "" };
+        println!(
+            "#{:<3} {} {}{}{}  {}",
+            cp.index, cp.commit_hash, cp.message, dirty, auto, cp.timestamp
         );
     }
+    Ok(())
+}
+
+fn cmd_checkpoint_show(name: &str, index: usize, json: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+    let checkpoints = core::checkpoint::list(&project.repo_root, t.id)?;
+    let pos = checkpoints
+        .iter()
+        .position(|c| c.index == index)
+        .ok_or_else(|| anyhow::anyhow!("checkpoint {} not found", index))?;
+    let cp = &checkpoints[pos];
+
+    // Changes since the previous checkpoint (or since the branch point)
+    let prev = pos.checked_sub(1).map(|p| checkpoints[p].refname.as_str());
+    let stat = core::checkpoint::diff_stat(&project.repo_root, prev, &cp.refname)?;
+
+    if json {
+        let mut value = serde_json::to_value(cp)?;
+        value["stat"] = stat.into();
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+    println!("Checkpoint #{} of '{}' ({})", cp.index, name, cp.timestamp);
+    println!("  {} {}", cp.commit_hash, cp.message);
+    if cp.dirty {
+        println!("  includes uncommitted changes");
+    }
+    println!();
+    if !cp.annotation.is_empty() {
+        println!("{}\n", cp.annotation);
+    }
+    print!("{}", stat);
+    Ok(())
+}
 
+fn cmd_checkpoint_diff(name: &str, from: usize, to: usize, json: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+    let a = core::checkpoint::get(&project.repo_root, t.id, from)?;
+    let b = core::checkpoint::get(&project.repo_root, t.id, to)?;
+
+    if json {
+        let stat = core::checkpoint::diff_stat(&project.repo_root, Some(&a.refname), &b.refname)?;
+        let patch = core::checkpoint::diff(&project.repo_root, Some(&a.refname), &b.refname)?;
+        let value = serde_json::json!({
+            "from": from,
+            "to": to,
+            "stat": stat,
+            "patch": patch,
+        });
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+    print!(
+        "{}",
+        core::checkpoint::diff(&project.repo_root, Some(&a.refname), &b.refname)?
+    );
     Ok(())
 }
 
-fn cmd_rollback(name: &str, target: Option<usize>) -> Result<()> {
+fn cmd_rollback(name: &str, target: Option<usize>, undo: bool, json: bool) -> Result<()> {
     let project = open_project()?;
     let t = task::get_by_name(&project.db, name)?
         .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
@@ -412,27 +1219,392 @@ fn cmd_rollback(name: &str, target: Option<usize>) -> Result<()> {
     }
 
     let worktree = std::path::Path::new(&t.worktree);
-    let idx = core::checkpoint::rollback(&project.repo_root, &t.name, worktree, target)?;
-    println!("✓ Rolled back '{}' to checkpoint #{}", name, idx);
+    if undo {
+        core::checkpoint::undo_rollback(&project.repo_root, t.id, &t.name, worktree)?;
+        if json {
+            let value = serde_json::json!({ "task": name, "undone": true });
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        } else {
+            println!("✓ Undid the last rollback of '{}'", name);
+        }
+        return Ok(());
+    }
+
+    let idx = core::checkpoint::rollback(&project.repo_root, t.id, &t.name, worktree, target)?;
+    if json {
+        let value = serde_json::json!({ "task": name, "checkpoint": idx });
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else {
+        println!("✓ Rolled back '{}' to checkpoint #{}", name, idx);
+        println!("  Undo with: pit rollback {} --undo", name);
+    }
 
     Ok(())
 }
 
-fn cmd_watch(name: &str, num_lines: usize) -> Result<()> {
+fn cmd_restore_paths(
+    name: &str,
+    target: Option<usize>,
+    mut paths: Vec<String>,
+    patch: bool,
+    json: bool,
+) -> Result<()> {
     let project = open_project()?;
     let t = task::get_by_name(&project.db, name)?
         .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
 
-    if t.status != task::Status::Running {
-        anyhow::bail!("task '{}' is not running (status: {})", name, t.status);
+    if t.status == task::Status::Running {
+        anyhow::bail!(
+            "task '{}' is running — stop it first before restoring files",
+            name
+        );
+    }
+    if paths.is_empty() {
+        paths.push(".".to_string());
     }
 
-    let tmux_name = t
-        .tmux_session
-        .as_deref()
-        .ok_or_else(|| anyhow::anyhow!("task '{}' has no tmux session", name))?;
-
-    // Stream output in a loop until Ctrl-C
+    let (idx, safety) =
+        core::checkpoint::restore_paths(&project.repo_root, &t, target, &paths, patch)?;
+    if json {
+        let value = serde_json::json!({
+            "task": name,
+            "checkpoint": idx,
+            "paths": paths,
+            "safety_checkpoint": safety,
+        });
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else {
+        println!(
+            "✓ Restored {} from checkpoint #{} of '{}'",
+            paths.join(", "),
+            idx,
+            name
+        );
+        if let Some(s) = safety {
+            println!("  Previous state saved as checkpoint #{}", s);
+        }
+        println!("  Undo with: pit rollback {} --undo", name);
+    }
+
+    Ok(())
+}
+
+fn cmd_squash(name: &str, by_checkpoint: bool, yes: bool, undo: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+
+    if t.status == task::Status::Running {
+        anyhow::bail!(
+            "task '{}' is running — stop it first before squashing",
+            name
+        );
+    }
+
+    if undo {
+        squash::undo(&project.repo_root, &t)?;
+        println!("✓ Restored '{}' to its history before the squash", t.branch);
+        return Ok(());
+    }
+
+    let mut plan = squash::plan(&project.repo_root, &t, by_checkpoint)?;
+    loop {
+        println!(
+            "Squash {} commit(s) on {} into {}:\n",
+            plan.commit_count(),
+            t.branch,
+            plan.groups.len()
+        );
+        for (i, group) in plan.groups.iter().enumerate() {
+            println!("── Commit {} of {} ──", i + 1, plan.groups.len());
+            for (hash, subject) in group.commits.iter().zip(&group.subjects) {
+                println!("  {} {}", &hash[..7.min(hash.len())], subject);
+            }
+            println!();
+            for line in group.message.lines() {
+                println!("  │ {}", line);
+            }
+            println!();
+        }
+        if yes {
+            break;
+        }
+
+        print!("Squash? [y]es / [e]dit messages / [N]o: ");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => break,
+            "e" | "edit" => {
+                for group in &mut plan.groups {
+                    let edited = editor::edit(&group.message, "txt")?;
+                    let edited = edited.trim();
+                    if edited.is_empty() {
+                        anyhow::bail!("empty commit message — squash aborted");
+                    }
+                    group.message = edited.to_string();
+                }
+            }
+            _ => {
+                println!("Aborted.");
+                return Ok(());
+            }
+        }
+    }
+
+    let tip = squash::apply(&project.repo_root, &t, &plan)?;
+    println!(
+        "✓ Squashed {} commit(s) on {} into {} ({})",
+        plan.commit_count(),
+        t.branch,
+        plan.groups.len(),
+        &tip[..7.min(tip.len())]
+    );
+    println!("  Undo with: pit squash {} --undo", name);
+    Ok(())
+}
+
+fn cmd_pr(name: &str, remote: Option<String>, draft: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+    let remote = remote.unwrap_or_else(pr::remote_name);
+    let api = pr::api_base();
+
+    pr::push(&project.repo_root, &remote, &t.branch)?;
+    println!("✓ Pushed {} to {}", t.branch, remote);
+
+    // Already opened: the push updated it, just refresh its state
+    if !t.pr_url.is_empty() {
+        let state = match pr::fetch(&api, &t.pr_url) {
+            Ok(p) => p.state,
+            Err(e) => {
+                eprintln!("  Could not refresh pull request state: {}", e);
+                t.pr_state.clone()
+            }
+        };
+        task::set_pr(&project.db, t.id, &t.pr_url, &state)?;
+        println!("  {} [{}]", t.pr_url, state);
+        return Ok(());
+    }
+
+    let (owner, repo) = pr::remote_repo(&project.repo_root, &remote)?;
+    let opened = match pr::find(&api, &owner, &repo, &t.branch)? {
+        Some(existing) => existing,
+        None => pr::create(
+            &api,
+            &owner,
+            &repo,
+            &pr::NewPullRequest {
+                title: pr::title(&t),
+                body: pr::body(&project.repo_root, &t),
+                head: t.branch.clone(),
+                base: core::git_info::detect_main_branch(&project.repo_root)?,
+                draft,
+            },
+        )?,
+    };
+    task::set_pr(&project.db, t.id, &opened.url, &opened.state)?;
+    println!("✓ Pull request #{}: {}", opened.number, opened.url);
+    Ok(())
+}
+
+fn cmd_test(name: &str, quiet: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+
+    let commands = checks::Commands::from_config(&project.repo_root);
+    task::set_test(&project.db, t.id, "running", 0, "")?;
+    let report = match checks::run(&commands, Path::new(&t.worktree), !quiet) {
+        Ok(report) => report,
+        Err(e) => {
+            task::set_test(&project.db, t.id, "", 0, "")?;
+            return Err(e);
+        }
+    };
+    checks::record(&project.db, t.id, &report)?;
+
+    let took = checks::format_duration(report.duration_ms);
+    if report.passed {
+        println!("✓ '{}' passed in {}", name, took);
+        return Ok(());
+    }
+    println!("✗ '{}' failed in {}", name, took);
+    for line in report.summary.lines() {
+        println!("  {}", line);
+    }
+    anyhow::bail!("checks failed for '{}'", name);
+}
+
+fn cmd_sync(name: &str, merge: bool, resolve: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+
+    if t.status == task::Status::Running {
+        anyhow::bail!("task '{}' is running — stop it first before syncing", name);
+    }
+
+    let strategy = if merge {
+        sync::Strategy::Merge
+    } else {
+        sync::Strategy::Rebase
+    };
+    match sync::sync(&project.repo_root, &t, strategy, resolve)? {
+        sync::Outcome::UpToDate => println!("'{}' is already up to date", name),
+        sync::Outcome::Synced { base, behind } => println!(
+            "✓ {} {} onto {} ({} new commit(s))",
+            if merge { "Merged" } else { "Rebased" },
+            t.branch,
+            base,
+            behind
+        ),
+        sync::Outcome::Conflicts { base, files } => {
+            println!("✗ {} conflicts with {} in:", t.branch, base);
+            for f in &files {
+                println!("  {}", f);
+            }
+            if !resolve {
+                println!("Nothing was changed.");
+                println!(
+                    "  Resolve with the agent: pit sync {}{} --resolve",
+                    name,
+                    if merge { " --merge" } else { "" }
+                );
+                return Ok(());
+            }
+
+            // A fresh session with the conflict brief; the task's own
+            // prompt stays as it was.
+            let mut resolver = t.clone();
+            resolver.prompt = sync::resolve_prompt(&t, strategy, &base, &files);
+            resolver.session_id = None;
+            let (agent_cmd, session_id) = tui::build_agent_cmd(&resolver);
+            let tmux_name = tmux::session_name(&t.name);
+            tmux::create_session_with_cmd(&tmux_name, &t.worktree, &agent_cmd)?;
+            task::set_running(&project.db, t.id, &tmux_name, None, Some(&session_id))?;
+            println!(
+                "Started {} to resolve the conflicts (tmux: {})",
+                t.agent, tmux_name
+            );
+            println!("  Attach with: tmux -L pit attach -t {}", tmux_name);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_conflicts(json: bool) -> Result<()> {
+    let project = open_project()?;
+    let tasks = task::list(&project.db)?;
+    let overlaps = overlap::analyze(&project.repo_root, &tasks);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&overlaps)?);
+        return Ok(());
+    }
+    if overlaps.is_empty() {
+        println!("No active tasks change the same files.");
+        return Ok(());
+    }
+    for o in &overlaps {
+        if o.conflicts.is_empty() {
+            println!("~ {} ↔ {}  both change: {}", o.a, o.b, o.files.join(", "));
+        } else {
+            println!(
+                "✗ {} ↔ {}  would conflict in: {}",
+                o.a,
+                o.b,
+                o.conflicts.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_queue_merge(names: &[String], list: bool, clear: bool) -> Result<()> {
+    let project = open_project()?;
+    if clear {
+        queue::clear(&project.db)?;
+        println!("Merge queue cleared");
+        return Ok(());
+    }
+    for name in names {
+        let t = task::get_by_name(&project.db, name)?
+            .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+        queue::add(&project.db, t.id)?;
+    }
+
+    let entries = queue::list(&project.db)?;
+    if entries.is_empty() {
+        println!("Merge queue is empty. Add tasks with: pit queue-merge <names…>");
+        return Ok(());
+    }
+    if list {
+        for (i, entry) in entries.iter().enumerate() {
+            let name = task::get(&project.db, entry.task_id)?
+                .map(|t| t.name)
+                .unwrap_or_default();
+            if entry.error.is_empty() {
+                println!("{}. {}", i + 1, name);
+            } else {
+                println!("{}. {}  ✗ {}", i + 1, name, entry.error);
+            }
+        }
+        return Ok(());
+    }
+
+    let checks = checks::Commands::from_config(&project.repo_root);
+    if checks.is_empty() {
+        println!("No test_command configured — merging without tests");
+    }
+    let base = core::git_info::detect_main_branch(&project.repo_root)?;
+    let results = queue::run(
+        &project.db,
+        &project.repo_root,
+        &checks,
+        |t, outcome| match outcome {
+            None => println!("── {} ──", t.name),
+            Some(queue::Outcome::Merged { commits }) => {
+                println!(
+                    "✓ Merged {} into {} ({} commit(s))",
+                    t.branch, base, commits
+                )
+            }
+            Some(queue::Outcome::Failed(error)) => println!("✗ {}: {}", t.name, error),
+        },
+    )?;
+
+    let failed = results
+        .iter()
+        .filter(|(_, o)| matches!(o, queue::Outcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        anyhow::bail!(
+            "{} of {} task(s) failed to land and are still queued (retry with: pit queue-merge)",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+fn cmd_watch(name: &str, num_lines: usize) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+
+    if t.status != task::Status::Running {
+        anyhow::bail!("task '{}' is not running (status: {})", name, t.status);
+    }
+
+    let tmux_name = t
+        .tmux_session
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("task '{}' has no tmux session", name))?;
+
+    // Stream output in a loop until Ctrl-C
     println!("Watching '{}' (Ctrl-C to stop)\n", name);
 
     loop {
@@ -465,21 +1637,356 @@ fn cmd_watch(name: &str, num_lines: usize) -> Result<()> {
     Ok(())
 }
 
+fn cmd_edit(args: EditArgs) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, &args.name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", args.name))?;
+
+    let no_flags = args.description.is_none()
+        && args.prompt.is_none()
+        && args.issue.is_none()
+        && args.agent.is_none()
+        && args.model.is_none();
+
+    let updated = if no_flags {
+        editor::ensure_interactive()?;
+        let text = editor::edit(&task::to_document(&t), "md")?;
+        let Some(doc) = task::parse_document(&text) else {
+            println!("Edit cancelled.");
+            return Ok(());
+        };
+        task::update(&project.db, t.id, &doc.as_update())?
+    } else {
+        task::update(
+            &project.db,
+            t.id,
+            &task::UpdateOpts {
+                description: args.description.as_deref(),
+                prompt: args.prompt.as_deref(),
+                issue_url: args.issue.as_deref(),
+                agent: args.agent.as_deref(),
+                model: args.model.as_deref(),
+            },
+        )?
+    };
+
+    println!("Updated task '{}' (agent: {})", updated.name, updated.agent);
+    if t.session_id.is_some() && updated.session_id.is_none() {
+        println!("  agent changed — the next launch starts a new session");
+    }
+    if t.status == task::Status::Running && updated.prompt != t.prompt {
+        println!("  note: the running agent won't see the new prompt until it is relaunched");
+    }
+    Ok(())
+}
+
+fn cmd_reassign(name: &str, agent: &str, model: &str, run: bool, dry_run: bool) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+
+    if agent == t.agent {
+        anyhow::bail!("task '{}' already uses {}", name, agent);
+    }
+
+    let tmux_name = tmux::session_name(&t.name);
+    let was_running = tmux::session_exists(&tmux_name);
+    let transcript = if was_running {
+        tmux::capture_pane(&tmux_name, 200).ok()
+    } else {
+        None
+    };
+    let brief = handoff::build(&project.repo_root, &t, agent, transcript.as_deref());
+
+    if dry_run {
+        println!("{}", brief);
+        return Ok(());
+    }
+
+    if was_running {
+        // Save the old agent's work and last output before stopping it
+        let idx = core::checkpoint::create(
+            &project.repo_root,
+            t.id,
+            &t.name,
+            &t.branch,
+            Path::new(&t.worktree),
+            transcript.as_deref(),
+        )
+        .context("failed to checkpoint before stopping the agent")?;
+        println!("✓ Checkpoint #{} saved for '{}'", idx, name);
+        tmux::kill_session(&tmux_name)?;
+        task::set_status(&project.db, t.id, &task::Status::Idle)?;
+    }
+
+    let updated = task::update(
+        &project.db,
+        t.id,
+        &task::UpdateOpts {
+            prompt: Some(&brief),
+            agent: Some(agent),
+            model: Some(model),
+            ..Default::default()
+        },
+    )?;
+    println!(
+        "Reassigned task '{}' from {} to {} ({} line handoff brief)",
+        name,
+        t.agent,
+        updated.agent,
+        brief.lines().count()
+    );
+
+    if run || was_running {
+        cmd_run(name)?;
+    } else {
+        println!("  Start it with: pit run {}", name);
+    }
+    Ok(())
+}
+
 fn cmd_delete(name: &str) -> Result<()> {
     let project = open_project()?;
+    if let Some(t) = task::get_archived(&project.db, name)? {
+        archive::delete(&project.db, &project.repo_root, &t)?;
+        println!("Deleted archived task '{}'", name);
+        return Ok(());
+    }
     let t = task::get_by_name(&project.db, name)?
         .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
 
-    // Kill tmux session if running
+    // Kill tmux sessions: the agent's if running, and any shell
     if let Some(ref tmux_name) = t.tmux_session {
         tmux::kill_session(tmux_name)?;
     }
+    tmux::kill_session(&tmux::shell_session_name(&t.name))?;
     if t.status == task::Status::Running {
         task::set_status(&project.db, t.id, &task::Status::Idle)?;
     }
 
     task::delete(&project.db, &project.repo_root, t.id)?;
-    println!("Deleted task '{}'", name);
+    if t.adopted {
+        println!("Deleted task '{}' (kept branch '{}')", name, t.branch);
+    } else {
+        println!("Deleted task '{}'", name);
+    }
+    Ok(())
+}
+
+fn cmd_archive(name: &str) -> Result<()> {
+    let project = open_project()?;
+    let t = task::get_by_name(&project.db, name)?
+        .ok_or_else(|| anyhow::anyhow!("task '{}' not found", name))?;
+
+    if let Some(ref tmux_name) = t.tmux_session {
+        tmux::kill_session(tmux_name)?;
+    }
+    tmux::kill_session(&tmux::shell_session_name(&t.name))?;
+    if t.status == task::Status::Running {
+        task::set_status(&project.db, t.id, &task::Status::Idle)?;
+    }
+    let t = task::get(&project.db, t.id)?.context("task disappeared")?;
+
+    archive::archive(&project.db, &project.repo_root, &t)?;
+    println!(
+        "Archived '{}' (branch kept at {})",
+        name,
+        archive::archive_ref(name)
+    );
+    println!("  Restore it with: pit restore {}", name);
+    Ok(())
+}
+
+fn cmd_archive_purge(days: u32) -> Result<()> {
+    let project = open_project()?;
+    let purged = archive::purge(&project.db, &project.repo_root, days)?;
+    if purged.is_empty() {
+        println!("No tasks archived more than {} day(s) ago.", days);
+        return Ok(());
+    }
+    for name in &purged {
+        println!("  ✗ {}", name);
+    }
+    println!("Purged {} archived task(s)", purged.len());
+    Ok(())
+}
+
+fn cmd_restore(name: &str) -> Result<()> {
+    let project = open_project()?;
+    let Some(t) = task::get_archived(&project.db, name)? else {
+        if task::get_by_name(&project.db, name)?.is_some() {
+            anyhow::bail!("task '{}' is not archived", name);
+        }
+        anyhow::bail!("no archived task '{}' (see: pit list --archived)", name);
+    };
+
+    let t = archive::restore(&project.db, &project.repo_root, &t)?;
+    println!("Restored '{}' at {}", name, t.worktree);
+    Ok(())
+}
+
+fn cmd_repair(rebuild: bool) -> Result<()> {
+    let project = if rebuild {
+        let cwd = PathBuf::from(".").canonicalize()?;
+        let repo_root = Project::find_repo_root(&cwd)?;
+        let (project, backup) = Project::reset_db(&repo_root)?;
+        if let Some(backup) = backup {
+            println!("Moved the old database to {}", backup.display());
+        }
+        project
+    } else {
+        open_project()?
+    };
+
+    let recovered = repair::recover(&project.db, &project.repo_root)?;
+    if recovered.is_empty() {
+        println!("Nothing to recover — every task in git has a row.");
+        return Ok(());
+    }
+    for r in &recovered {
+        let archived = if r.archived { ", archived" } else { "" };
+        println!(
+            "  ✓ {:<20} (from {}{}, agent: {})",
+            r.task.name, r.from, archived, r.task.agent
+        );
+    }
+    println!("Recovered {} task(s)", recovered.len());
+    Ok(())
+}
+
+fn cmd_doctor() -> Result<()> {
+    let project = open_project()?;
+    let tasks = task::list(&project.db)?;
+
+    println!("Environment");
+    let checks = doctor::environment(&project.repo_root, &tasks);
+    for c in &checks {
+        let icon = match c.level {
+            doctor::Level::Ok => "✓",
+            doctor::Level::Warn => "!",
+            doctor::Level::Error => "✗",
+        };
+        println!("  {} {}", icon, c.message);
+    }
+
+    println!("\nTasks");
+    let limit = doctor::build_dir_limit(&project.repo_root);
+    let problems = doctor::find(&project.db, &project.repo_root, limit)?;
+    if problems.is_empty() {
+        println!("  ✓ worktrees, branches, sessions and refs are consistent");
+    }
+    for p in &problems {
+        println!("  ! {}", p.describe(&project.repo_root));
+    }
+
+    if !problems.is_empty() {
+        println!(
+            "\n{} problem(s) — fix them with: pit gc (preview with --dry-run)",
+            problems.len()
+        );
+    }
+    let failed = checks
+        .iter()
+        .filter(|c| c.level == doctor::Level::Error)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+fn cmd_gc(dry_run: bool) -> Result<()> {
+    let project = open_project()?;
+    reap::reap_dead(&project.db, &project.repo_root)?;
+    let tasks = task::list(&project.db)?;
+
+    let usage = doctor::worktree_usage(&tasks);
+    if !usage.is_empty() {
+        println!("Disk usage by worktree:");
+        for (name, bytes) in &usage {
+            println!("  {:<30} {:>10}", name, doctor::human_size(*bytes));
+        }
+        let total = usage.iter().map(|(_, b)| b).sum();
+        println!("  {:<30} {:>10}\n", "total", doctor::human_size(total));
+    }
+
+    let limit = doctor::build_dir_limit(&project.repo_root);
+    let problems = doctor::find(&project.db, &project.repo_root, limit)?;
+    if problems.is_empty() {
+        println!("Nothing to clean up.");
+        return Ok(());
+    }
+    let mut fixed = 0;
+    for p in &problems {
+        match doctor::fix(&project.db, &project.repo_root, p, dry_run)? {
+            doctor::Outcome::Fixed(what) if dry_run => println!("  would {}", what),
+            doctor::Outcome::Fixed(what) => {
+                fixed += 1;
+                println!("  ✓ {}", what)
+            }
+            doctor::Outcome::Skipped(why) => println!("  · {}", why),
+        }
+    }
+    if dry_run {
+        println!("\nDry run — nothing changed. Run pit gc to apply.");
+    } else {
+        println!("\nFixed {} problem(s)", fixed);
+    }
+    Ok(())
+}
+
+fn cmd_worktrees() -> Result<()> {
+    let project = open_project()?;
+    let root = worktrees::root(&project.repo_root);
+    println!("Worktree root: {}", root.display());
+    let tasks = task::list(&project.db)?;
+    let elsewhere: Vec<&task::Task> = tasks
+        .iter()
+        .filter(|t| Path::new(&t.worktree) != worktrees::path(&project.repo_root, &t.name))
+        .collect();
+    for t in &elsewhere {
+        println!("  {:<30} {}", t.name, t.worktree);
+    }
+    if elsewhere.is_empty() {
+        println!("All {} task(s) have their worktree there.", tasks.len());
+    } else {
+        println!(
+            "\n{} task(s) elsewhere — move them with: pit worktrees migrate",
+            elsewhere.len()
+        );
+    }
+    Ok(())
+}
+
+fn cmd_worktrees_migrate(dry_run: bool) -> Result<()> {
+    let project = open_project()?;
+    reap::reap_dead(&project.db, &project.repo_root)?;
+    let moves = worktrees::migrate(&project.db, &project.repo_root, dry_run)?;
+    if moves.is_empty() {
+        println!(
+            "Every worktree is already in {}",
+            worktrees::root(&project.repo_root).display()
+        );
+        return Ok(());
+    }
+    let mut moved = 0;
+    for m in &moves {
+        match m {
+            worktrees::Move::Moved { task, from, to } => {
+                let verb = if dry_run { "would move" } else { "✓" };
+                println!("  {} {}: {} → {}", verb, task, from.display(), to.display());
+                moved += 1;
+            }
+            worktrees::Move::Skipped { task, reason } => {
+                println!("  · leave {}: {}", task, reason)
+            }
+        }
+    }
+    if dry_run {
+        println!("\nDry run — nothing changed. Run pit worktrees migrate to apply.");
+    } else {
+        println!("\nMoved {} worktree(s)", moved);
+    }
     Ok(())
 }
 
@@ -487,16 +1994,33 @@ fn cmd_config(action: ConfigAction) -> Result<()> {
     use crate::core::config;
 
     match action {
-        ConfigAction::Set { key, value } => {
-            config::set(&key, &value)?;
+        ConfigAction::Set { key, value, repo } => {
+            if repo {
+                let path = config::repo_config_path(&open_project()?.repo_root);
+                config::set_in(&path, &key, &value)?;
+            } else {
+                config::set(&key, &value)?;
+            }
             println!("Set {} = {}", key, mask_secret(&key, &value));
         }
-        ConfigAction::Get { key } => match config::get(&key) {
-            Some(value) => println!("{} = {}", key, mask_secret(&key, &value)),
-            None => println!("{} is not set", key),
-        },
-        ConfigAction::Unset { key } => {
-            config::unset(&key)?;
+        ConfigAction::Get { key, repo } => {
+            let value = if repo {
+                config::get_for_repo(&open_project()?.repo_root, &key)
+            } else {
+                config::get(&key)
+            };
+            match value {
+                Some(value) => println!("{} = {}", key, mask_secret(&key, &value)),
+                None => println!("{} is not set", key),
+            }
+        }
+        ConfigAction::Unset { key, repo } => {
+            if repo {
+                let path = config::repo_config_path(&open_project()?.repo_root);
+                config::unset_in(&path, &key)?;
+            } else {
+                config::unset(&key)?;
+            }
             println!("Removed {}", key);
         }
         ConfigAction::List => {
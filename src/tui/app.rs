@@ -1,14 +1,25 @@
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use ratatui::DefaultTerminal;
+use std::sync::mpsc;
 use std::time::Duration;
 
+use crate::core::archive;
+use crate::core::autocheckpoint;
 use crate::core::checkpoint;
+use crate::core::checks;
 use crate::core::git_info::{self, TaskGitInfo};
+use crate::core::harvest;
+use crate::core::history;
 use crate::core::names;
+use crate::core::overlap;
+use crate::core::pr;
 use crate::core::project::Project;
+use crate::core::queue;
 use crate::core::reap;
+use crate::core::sync;
 use crate::core::task::{self, CreateOpts, Task};
+use crate::core::template;
 use crate::core::tmux;
 
 use super::ui;
@@ -18,6 +29,8 @@ pub enum Mode {
     Normal,
     NewTask,
     IssuePicker,
+    Checkpoints,
+    Harvest,
 }
 
 /// Which view layout is active.
@@ -42,6 +55,7 @@ pub enum ModalField {
     Name,
     Prompt,
     Agent,
+    Template,
     Issue,
     AutoApprove,
 }
@@ -51,6 +65,7 @@ impl ModalField {
         ModalField::Name,
         ModalField::Prompt,
         ModalField::Agent,
+        ModalField::Template,
         ModalField::AutoApprove,
         ModalField::Issue,
     ];
@@ -74,6 +89,8 @@ impl ModalField {
 #[derive(Debug, Clone)]
 pub struct ModalState {
     pub field: ModalField,
+    /// Task being edited; `None` when creating a new task.
+    pub editing: Option<i64>,
     pub name: String,
     pub prompt: String,
     /// Cursor position within the prompt string (byte offset)
@@ -81,6 +98,19 @@ pub struct ModalState {
     /// Scroll offset for the prompt textarea (first visible line)
     pub prompt_scroll: usize,
     pub agent: String,
+    /// Selected template name ("" = none).
+    pub template: String,
+    /// Templates available in `.pit/templates/`.
+    pub templates: Vec<String>,
+    /// Recent prompts (oldest first) for Up/Down recall.
+    pub history: Vec<String>,
+    /// Which history entry is shown; `None` = the user's own draft.
+    history_pos: Option<usize>,
+    /// The draft to restore when scrolling back past the newest entry.
+    history_draft: String,
+    /// Body the selected template put in the prompt, so switching templates
+    /// only replaces the prompt if the user hasn't edited it.
+    template_body: String,
     pub issue: String,
     pub auto_approve: bool,
     /// Status message after fetching an issue (e.g. "✓ ENG-42: Fix login" or "✗ not found")
@@ -92,17 +122,88 @@ pub struct ModalState {
     pub picker_status: Option<String>,
 }
 
+/// A destructive checkpoint action waiting for y/n.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointConfirm {
+    /// Roll back to the checkpoint with this index.
+    Rollback(usize),
+    /// Restore the files picked in the browser from this checkpoint.
+    RestoreFiles(usize),
+    /// Restore the state saved before the last rollback.
+    Undo,
+}
+
+/// State for the checkpoint browser.
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointBrowser {
+    pub task_id: i64,
+    pub task_name: String,
+    pub checkpoints: Vec<checkpoint::Checkpoint>,
+    /// Position in `checkpoints` (oldest first).
+    pub selected: usize,
+    /// Position marked as the "from" side of a diff.
+    pub mark: Option<usize>,
+    /// Diff being previewed; `None` shows the selected checkpoint's annotation.
+    pub preview: Option<Vec<String>>,
+    /// Heading for the preview, e.g. "#1 → #3".
+    pub preview_title: String,
+    pub scroll: u16,
+    pub confirm: Option<CheckpointConfirm>,
+    /// Whether a rollback can be undone.
+    pub can_undo: bool,
+    /// Files that differ from the selected checkpoint, with whether each is
+    /// picked for restoring; `None` when the file picker is closed.
+    pub files: Option<Vec<(String, bool)>>,
+    pub file_cursor: usize,
+}
+
+/// A TODO marker in the harvest picker.
+#[derive(Debug, Clone)]
+pub struct HarvestItem {
+    pub marker: harvest::Marker,
+    /// Task already created from the marker.
+    pub task: Option<String>,
+    pub picked: bool,
+}
+
+/// State for the TODO marker picker (shown in `Mode::Harvest`).
+#[derive(Debug, Clone, Default)]
+pub struct HarvestPicker {
+    pub pattern: String,
+    pub items: Vec<HarvestItem>,
+    pub cursor: usize,
+}
+
+/// How often the dashboard checks running tasks for automatic checkpoints.
+const AUTO_CHECKPOINT_EVERY: Duration = Duration::from_secs(30);
+
+/// How often the dashboard re-fetches the state of open pull requests.
+const PR_REFRESH_EVERY: Duration = Duration::from_secs(5 * 60);
+
+/// How often the dashboard recounts how far each task is behind its base.
+const BEHIND_REFRESH_EVERY: Duration = Duration::from_secs(30);
+
+/// How often the dashboard re-checks active tasks for overlapping changes.
+const OVERLAP_REFRESH_EVERY: Duration = Duration::from_secs(30);
+
 const AGENTS: &[&str] = &["claude", "pi", "codex", "amp", "aider", "goose", "custom"];
 
 impl ModalState {
     fn new(existing_names: &[String]) -> Self {
         ModalState {
             field: ModalField::Name,
+            editing: None,
             name: names::generate(existing_names),
             prompt: String::new(),
             prompt_cursor: 0,
             prompt_scroll: 0,
             agent: "claude".to_string(),
+            template: String::new(),
+            templates: Vec::new(),
+            history: Vec::new(),
+            history_pos: None,
+            history_draft: String::new(),
+            template_body: String::new(),
             issue: String::new(),
             auto_approve: false,
             issue_status: None,
@@ -113,8 +214,23 @@ impl ModalState {
         }
     }
 
+    /// Pre-fill the modal from an existing task for editing.
+    fn for_task(task: &Task) -> Self {
+        let mut m = ModalState::new(&[]);
+        m.editing = Some(task.id);
+        m.field = ModalField::Prompt;
+        m.name = task.name.clone();
+        m.prompt = task.prompt.clone();
+        m.prompt_cursor = m.prompt.len();
+        m.agent = task.agent.clone();
+        m.issue = task.issue_url.clone();
+        m
+    }
+
     fn active_text_mut(&mut self) -> Option<&mut String> {
         match self.field {
+            // Renaming would mean moving the branch and worktree.
+            ModalField::Name if self.editing.is_some() => None,
             ModalField::Name => Some(&mut self.name),
             ModalField::Prompt => Some(&mut self.prompt),
             _ => None,
@@ -136,6 +252,35 @@ impl ModalState {
         }
     }
 
+    /// Replace the prompt text and put the cursor at the end.
+    pub fn set_prompt(&mut self, prompt: String) {
+        self.prompt = prompt;
+        self.prompt_cursor = self.prompt.len();
+        self.prompt_scroll = 0;
+    }
+
+    /// Step through prompt history: `older` goes back in time. Returns
+    /// false when there's nothing further in that direction.
+    fn recall_history(&mut self, older: bool) -> bool {
+        let next = match (self.history_pos, older) {
+            (None, true) if !self.history.is_empty() => {
+                self.history_draft = self.prompt.clone();
+                Some(self.history.len() - 1)
+            }
+            (Some(i), true) if i > 0 => Some(i - 1),
+            (Some(i), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+            _ => return false,
+        };
+        self.history_pos = next;
+        let text = match next {
+            Some(i) => self.history[i].clone(),
+            None => std::mem::take(&mut self.history_draft),
+        };
+        self.set_prompt(text);
+        true
+    }
+
     fn cycle_agent(&mut self, forward: bool) {
         let idx = AGENTS.iter().position(|a| *a == self.agent).unwrap_or(0);
         let next = if forward {
@@ -145,6 +290,44 @@ impl ModalState {
         };
         self.agent = AGENTS[next].to_string();
     }
+
+    /// Select the next/previous template ("" = none) and apply it: set the
+    /// agent and, unless the user has edited it, replace the prompt.
+    fn cycle_template(&mut self, forward: bool, repo_root: &std::path::Path) {
+        let count = self.templates.len() + 1;
+        let idx = self
+            .templates
+            .iter()
+            .position(|t| *t == self.template)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let next = if forward {
+            (idx + 1) % count
+        } else {
+            (idx + count - 1) % count
+        };
+        self.template = if next == 0 {
+            String::new()
+        } else {
+            self.templates[next - 1].clone()
+        };
+
+        let tpl = if self.template.is_empty() {
+            template::Template::default()
+        } else {
+            match template::load(repo_root, &self.template) {
+                Ok(t) => t,
+                Err(_) => return,
+            }
+        };
+        if !tpl.agent.is_empty() {
+            self.agent = tpl.agent.clone();
+        }
+        if self.prompt.trim().is_empty() || self.prompt == self.template_body {
+            self.set_prompt(tpl.body.clone());
+        }
+        self.template_body = tpl.body;
+    }
 }
 
 pub struct App {
@@ -183,6 +366,34 @@ pub struct App {
     pub show_live_output: bool,
     /// Cached live output lines from tmux capture-pane.
     pub live_output: Vec<String>,
+    /// Checkpoint browser state (shown in `Mode::Checkpoints`).
+    pub checkpoints: CheckpointBrowser,
+    /// When running tasks were last checked for an automatic checkpoint.
+    pub auto_checkpoint_at: Option<std::time::Instant>,
+    /// Automatic checkpoints being taken in the background: how many were
+    /// taken.
+    pub auto_checkpoint_job: Option<mpsc::Receiver<Result<usize>>>,
+    /// When open pull requests were last checked for merges/closes.
+    pub pr_refresh_at: Option<std::time::Instant>,
+    /// Pull request states being fetched in the background: (task id, PR
+    /// URL, new state) for each one that changed.
+    pub pr_job: Option<mpsc::Receiver<Vec<(i64, String, String)>>>,
+    /// Commits on the base branch missing from each task's branch, by task id.
+    pub behind: std::collections::HashMap<i64, usize>,
+    /// When `behind` was last recounted.
+    pub behind_at: Option<std::time::Instant>,
+    /// Pairs of active tasks changing the same files.
+    pub overlaps: Vec<overlap::Overlap>,
+    /// When `overlaps` was last recomputed.
+    pub overlaps_at: Option<std::time::Instant>,
+    /// Overlaps being recomputed in the background.
+    pub overlaps_job: Option<mpsc::Receiver<Vec<overlap::Overlap>>>,
+    /// The merge queue, in landing order.
+    pub queue: Vec<queue::Entry>,
+    /// TODO marker picker state.
+    pub harvest: HarvestPicker,
+    /// Whether each harvested task's TODO marker is still in its worktree.
+    pub markers_present: std::collections::HashMap<i64, bool>,
 }
 
 impl App {
@@ -212,6 +423,19 @@ impl App {
             detail_pane_height: 30,
             show_live_output: false,
             live_output: Vec::new(),
+            checkpoints: CheckpointBrowser::default(),
+            auto_checkpoint_at: None,
+            auto_checkpoint_job: None,
+            pr_refresh_at: None,
+            pr_job: None,
+            behind: std::collections::HashMap::new(),
+            behind_at: None,
+            overlaps: Vec::new(),
+            overlaps_at: None,
+            overlaps_job: None,
+            queue: Vec::new(),
+            harvest: HarvestPicker::default(),
+            markers_present: std::collections::HashMap::new(),
         };
         app.refresh_detail();
         Ok(app)
@@ -220,14 +444,146 @@ impl App {
     fn refresh(&mut self) -> Result<()> {
         let db = crate::db::open(&self.db_path)?;
         reap::reap_dead(&db, &self.repo_root)?;
+        self.auto_checkpoint();
+        self.refresh_pr_states(&db);
         self.tasks = task::list(&db)?;
+        self.queue = queue::list(&db)?;
+        self.markers_present = self
+            .tasks
+            .iter()
+            .filter_map(|t| harvest::marker_present(t).map(|p| (t.id, p)))
+            .collect();
         if !self.tasks.is_empty() && self.selected >= self.tasks.len() {
             self.selected = self.tasks.len() - 1;
         }
+        self.refresh_behind();
+        self.refresh_overlaps();
         self.refresh_detail();
         Ok(())
     }
 
+    /// Recompute which active tasks overlap, at most every
+    /// [`OVERLAP_REFRESH_EVERY`]. The trial merges take a while with many
+    /// tasks, so they run on a background thread and the result is picked
+    /// up on a later tick.
+    fn refresh_overlaps(&mut self) {
+        if let Some(overlaps) = poll(&mut self.overlaps_job) {
+            self.overlaps = overlaps;
+        }
+        if self.overlaps_job.is_some()
+            || self
+                .overlaps_at
+                .is_some_and(|at| at.elapsed() < OVERLAP_REFRESH_EVERY)
+        {
+            return;
+        }
+        self.overlaps_at = Some(std::time::Instant::now());
+        let repo_root = self.repo_root.clone();
+        let tasks = self.tasks.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(overlap::analyze(&repo_root, &tasks));
+        });
+        self.overlaps_job = Some(rx);
+    }
+
+    /// Recount how many commits each task is behind the base branch, at most
+    /// every [`BEHIND_REFRESH_EVERY`].
+    fn refresh_behind(&mut self) {
+        if self
+            .behind_at
+            .is_some_and(|at| at.elapsed() < BEHIND_REFRESH_EVERY)
+        {
+            return;
+        }
+        self.behind_at = Some(std::time::Instant::now());
+        let Ok(base) = git_info::detect_main_branch(&self.repo_root) else {
+            return;
+        };
+        self.behind = self
+            .tasks
+            .iter()
+            .map(|t| (t.id, sync::behind_count(&self.repo_root, &base, &t.branch)))
+            .collect();
+    }
+
+    /// Take automatic checkpoints of running tasks, at most every
+    /// [`AUTO_CHECKPOINT_EVERY`]. Capturing panes and committing snapshots
+    /// takes a while with many tasks, so it runs on a background thread and
+    /// the result is picked up on a later tick. Failures are shown but don't
+    /// stop the loop.
+    fn auto_checkpoint(&mut self) {
+        match poll(&mut self.auto_checkpoint_job) {
+            Some(Ok(n)) if n > 0 && self.mode == Mode::Checkpoints => self.reload_checkpoints(),
+            Some(Err(e)) => self.error = Some(format!("auto-checkpoint failed: {}", e)),
+            _ => {}
+        }
+        if self.auto_checkpoint_job.is_some()
+            || self
+                .auto_checkpoint_at
+                .is_some_and(|at| at.elapsed() < AUTO_CHECKPOINT_EVERY)
+        {
+            return;
+        }
+        self.auto_checkpoint_at = Some(std::time::Instant::now());
+        let policy = autocheckpoint::Policy::from_config();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let repo_root = self.repo_root.clone();
+        let db_path = self.db_path.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = crate::db::open(&db_path)
+                .and_then(|db| autocheckpoint::run(&db, &repo_root, &policy, now));
+            let _ = tx.send(result);
+        });
+        self.auto_checkpoint_job = Some(rx);
+    }
+
+    /// Re-fetch the state of open pull requests, at most every
+    /// [`PR_REFRESH_EVERY`]. The requests run on a background thread and
+    /// their results are stored on a later tick, so a slow network doesn't
+    /// freeze the dashboard. Network errors are ignored until the next try.
+    fn refresh_pr_states(&mut self, db: &rusqlite::Connection) {
+        if let Some(changed) = poll(&mut self.pr_job) {
+            for (id, url, state) in changed {
+                let _ = task::set_pr(db, id, &url, &state);
+            }
+        }
+        if self.pr_job.is_some()
+            || self
+                .pr_refresh_at
+                .is_some_and(|at| at.elapsed() < PR_REFRESH_EVERY)
+        {
+            return;
+        }
+        self.pr_refresh_at = Some(std::time::Instant::now());
+        let open: Vec<(i64, String)> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.pr_url.is_empty() && t.pr_state == "open")
+            .map(|t| (t.id, t.pr_url.clone()))
+            .collect();
+        if open.is_empty() {
+            return;
+        }
+        let api = pr::api_base();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let changed = open
+                .into_iter()
+                .filter_map(|(id, url)| {
+                    let p = pr::fetch(&api, &url).ok()?;
+                    (p.state != "open").then_some((id, url, p.state))
+                })
+                .collect();
+            let _ = tx.send(changed);
+        });
+        self.pr_job = Some(rx);
+    }
+
     /// Update the cached git detail for the currently selected task.
     /// Only re-fetches if the selection changed.
     fn refresh_detail(&mut self) {
@@ -316,6 +672,8 @@ impl App {
                 }
                 result
             }
+            Mode::Checkpoints => self.handle_checkpoints_key(code, modifiers),
+            Mode::Harvest => Ok(self.handle_harvest_key(code)),
         }
     }
 
@@ -329,6 +687,8 @@ impl App {
             (KeyCode::Char('n'), _) => {
                 let existing: Vec<String> = self.tasks.iter().map(|t| t.name.clone()).collect();
                 self.modal = ModalState::new(&existing);
+                self.modal.templates = template::list(&self.repo_root);
+                self.modal.history = history::load(&self.repo_root);
                 self.mode = Mode::NewTask;
                 return Ok(Action::None);
             }
@@ -373,6 +733,14 @@ impl App {
                 self.focus = Pane::TaskList;
                 return Ok(Action::None);
             }
+            (KeyCode::Char('C'), _) => {
+                self.open_checkpoints();
+                return Ok(Action::None);
+            }
+            (KeyCode::Char('H'), _) => {
+                self.open_harvest();
+                return Ok(Action::None);
+            }
             (KeyCode::Esc, _) if self.focus == Pane::Detail => {
                 if self.diff_line.is_some() {
                     self.diff_line = None;
@@ -471,6 +839,19 @@ impl App {
                     Ok(Action::None)
                 }
             }
+            (KeyCode::Char('A'), _) => {
+                if let Some(t) = self.kanban_selected_task() {
+                    Ok(Action::Archive(t.id))
+                } else {
+                    Ok(Action::None)
+                }
+            }
+            (KeyCode::Char('e'), _) => {
+                if let Some(t) = self.kanban_selected_task().cloned() {
+                    self.open_edit_modal(&t);
+                }
+                Ok(Action::None)
+            }
             _ => Ok(Action::None),
         }
     }
@@ -505,6 +886,13 @@ impl App {
                     Ok(Action::None)
                 }
             }
+            (KeyCode::Char('A'), _) => {
+                if let Some(t) = self.tasks.get(self.selected) {
+                    Ok(Action::Archive(t.id))
+                } else {
+                    Ok(Action::None)
+                }
+            }
             (KeyCode::Char('t'), _) => {
                 if let Some(t) = self.tasks.get(self.selected) {
                     Ok(Action::Shell(t.id))
@@ -512,10 +900,39 @@ impl App {
                     Ok(Action::None)
                 }
             }
+            (KeyCode::Char('e'), _) => {
+                if let Some(t) = self.tasks.get(self.selected).cloned() {
+                    self.open_edit_modal(&t);
+                }
+                Ok(Action::None)
+            }
+            (KeyCode::Char('m'), _) => {
+                if let Some(t) = self.tasks.get(self.selected) {
+                    Ok(Action::ToggleQueue(t.id))
+                } else {
+                    Ok(Action::None)
+                }
+            }
+            (KeyCode::Char('M'), _) if !self.queue.is_empty() => Ok(Action::LandQueue),
+            (KeyCode::Char('T'), _) => {
+                if let Some(t) = self.tasks.get(self.selected) {
+                    Ok(Action::Test(t.id))
+                } else {
+                    Ok(Action::None)
+                }
+            }
             _ => Ok(Action::None),
         }
     }
 
+    /// Position (1-based) of a task in the merge queue.
+    pub fn queue_position(&self, task_id: i64) -> Option<usize> {
+        self.queue
+            .iter()
+            .position(|e| e.task_id == task_id)
+            .map(|i| i + 1)
+    }
+
     /// How many files are in the current detail.
     fn file_count(&self) -> usize {
         self.detail.as_ref().map(|d| d.files.len()).unwrap_or(0)
@@ -584,7 +1001,7 @@ impl App {
 
         // Checkpoints section (if any)
         if let Some(task) = self.tasks.get(self.selected) {
-            let cp_count = checkpoint::list(&self.repo_root, &task.name)
+            let cp_count = checkpoint::list(&self.repo_root, task.id)
                 .map(|c| c.len())
                 .unwrap_or(0);
             if cp_count > 0 {
@@ -802,6 +1219,13 @@ impl App {
                     Ok(Action::None)
                 }
             }
+            (KeyCode::Char('A'), _) => {
+                if let Some(t) = self.tasks.get(self.selected) {
+                    Ok(Action::Archive(t.id))
+                } else {
+                    Ok(Action::None)
+                }
+            }
             (KeyCode::Char('c'), _) => {
                 if let Some(t) = self.tasks.get(self.selected) {
                     let worktree = std::path::Path::new(&t.worktree);
@@ -812,6 +1236,7 @@ impl App {
                         .and_then(|name| tmux::capture_pane(name, 50).ok());
                     match checkpoint::create(
                         &self.repo_root,
+                        t.id,
                         &t.name,
                         &t.branch,
                         worktree,
@@ -834,7 +1259,7 @@ impl App {
                         self.error = Some("Stop the task before rolling back".into());
                     } else {
                         let worktree = std::path::Path::new(&t.worktree);
-                        match checkpoint::rollback(&self.repo_root, &t.name, worktree, None) {
+                        match checkpoint::rollback(&self.repo_root, t.id, &t.name, worktree, None) {
                             Ok(idx) => {
                                 self.error = Some(format!("✓ Rolled back to checkpoint #{}", idx));
                                 self.force_refresh_detail();
@@ -888,12 +1313,28 @@ impl App {
                 Ok(Action::None)
             }
 
+            // Template field: left/right to cycle
+            (KeyCode::Left, _) if self.modal.field == ModalField::Template => {
+                self.modal.cycle_template(false, &self.repo_root);
+                Ok(Action::None)
+            }
+            (KeyCode::Right, _) if self.modal.field == ModalField::Template => {
+                self.modal.cycle_template(true, &self.repo_root);
+                Ok(Action::None)
+            }
+
             // Auto-approve: space toggles
             (KeyCode::Char(' '), _) if self.modal.field == ModalField::AutoApprove => {
                 self.modal.auto_approve = !self.modal.auto_approve;
                 Ok(Action::None)
             }
 
+            // Ctrl+E: edit the prompt in $VISUAL / $EDITOR
+            (KeyCode::Char('e'), m) if m.contains(KeyModifiers::CONTROL) => {
+                self.modal.field = ModalField::Prompt;
+                Ok(Action::EditPrompt)
+            }
+
             // Ctrl+L: open Linear issue picker (from any field)
             (KeyCode::Char('l'), m) if m.contains(KeyModifiers::CONTROL) => {
                 self.open_issue_picker();
@@ -952,6 +1393,9 @@ impl App {
                     let col = self.modal.prompt_cursor - (newline_pos + 1);
                     let prev_line_len = newline_pos - line_start;
                     self.modal.prompt_cursor = line_start + col.min(prev_line_len);
+                } else {
+                    // Already on the first line: recall an older prompt
+                    self.modal.recall_history(true);
                 }
                 Ok(Action::None)
             }
@@ -972,6 +1416,9 @@ impl App {
                         .unwrap_or(self.modal.prompt.len());
                     let next_line_len = next_line_end - next_line_start;
                     self.modal.prompt_cursor = next_line_start + col.min(next_line_len);
+                } else {
+                    // On the last line: step back towards the draft
+                    self.modal.recall_history(false);
                 }
                 Ok(Action::None)
             }
@@ -1009,6 +1456,349 @@ impl App {
         }
     }
 
+    fn open_edit_modal(&mut self, task: &Task) {
+        self.modal = ModalState::for_task(task);
+        self.modal.templates = template::list(&self.repo_root);
+        self.modal.history = history::load(&self.repo_root);
+        self.mode = Mode::NewTask;
+    }
+
+    /// Scan the repo for TODO markers and open the picker.
+    fn open_harvest(&mut self) {
+        let pattern = harvest::pattern(&self.repo_root);
+        let markers = match harvest::scan(&self.repo_root, &pattern) {
+            Ok(m) => m,
+            Err(e) => {
+                self.error = Some(format!("✗ {}", e));
+                return;
+            }
+        };
+        if markers.is_empty() {
+            self.error = Some(format!("No markers matching {}", pattern));
+            return;
+        }
+        let items = markers
+            .into_iter()
+            .map(|marker| HarvestItem {
+                task: harvest::harvested_by(&marker, &self.tasks).map(|t| t.name.clone()),
+                marker,
+                picked: false,
+            })
+            .collect();
+        self.harvest = HarvestPicker {
+            pattern,
+            items,
+            cursor: 0,
+        };
+        self.mode = Mode::Harvest;
+    }
+
+    fn handle_harvest_key(&mut self, code: KeyCode) -> Action {
+        let h = &mut self.harvest;
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Normal,
+            KeyCode::Up | KeyCode::Char('k') => h.cursor = h.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if h.cursor + 1 < h.items.len() => {
+                h.cursor += 1;
+            }
+            KeyCode::Char(' ') => match h.items.get_mut(h.cursor) {
+                Some(item) if item.task.is_none() => item.picked = !item.picked,
+                Some(item) => {
+                    self.error = Some(format!(
+                        "Already harvested as {}",
+                        item.task.as_deref().unwrap_or_default()
+                    ));
+                }
+                None => {}
+            },
+            KeyCode::Char('a') => {
+                let all = h
+                    .items
+                    .iter()
+                    .filter(|i| i.task.is_none())
+                    .all(|i| i.picked);
+                h.items
+                    .iter_mut()
+                    .filter(|i| i.task.is_none())
+                    .for_each(|i| i.picked = !all);
+            }
+            KeyCode::Enter => {
+                let mut markers: Vec<harvest::Marker> = h
+                    .items
+                    .iter()
+                    .filter(|i| i.picked)
+                    .map(|i| i.marker.clone())
+                    .collect();
+                // Nothing picked: take the one under the cursor
+                if markers.is_empty() {
+                    match h.items.get(h.cursor) {
+                        Some(i) if i.task.is_none() => markers.push(i.marker.clone()),
+                        _ => {
+                            self.error = Some("Pick markers with Space first".into());
+                            return Action::None;
+                        }
+                    }
+                }
+                self.mode = Mode::Normal;
+                return Action::Harvest(markers);
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn open_checkpoints(&mut self) {
+        let Some(t) = self.tasks.get(self.selected) else {
+            return;
+        };
+        self.checkpoints = CheckpointBrowser {
+            task_id: t.id,
+            task_name: t.name.clone(),
+            ..Default::default()
+        };
+        self.reload_checkpoints();
+        self.checkpoints.selected = self.checkpoints.checkpoints.len().saturating_sub(1);
+        self.mode = Mode::Checkpoints;
+    }
+
+    fn reload_checkpoints(&mut self) {
+        let b = &mut self.checkpoints;
+        b.checkpoints = checkpoint::list(&self.repo_root, b.task_id).unwrap_or_default();
+        b.can_undo = checkpoint::can_undo(&self.repo_root, b.task_id);
+        if b.selected >= b.checkpoints.len() {
+            b.selected = b.checkpoints.len().saturating_sub(1);
+        }
+        if b.mark.is_some_and(|m| m >= b.checkpoints.len()) {
+            b.mark = None;
+        }
+    }
+
+    /// Show the diff from the marked checkpoint (or the one before the
+    /// selection, or the base branch) to the selected checkpoint.
+    fn preview_checkpoint_diff(&mut self) {
+        let b = &mut self.checkpoints;
+        let Some(to) = b.checkpoints.get(b.selected) else {
+            return;
+        };
+        let from = match b.mark {
+            Some(m) if m != b.selected => b.checkpoints.get(m),
+            _ if b.selected > 0 => b.checkpoints.get(b.selected - 1),
+            _ => None,
+        };
+        b.preview_title = match from {
+            Some(f) => format!("#{} → #{}", f.index, to.index),
+            None => format!("base → #{}", to.index),
+        };
+        let diff = checkpoint::diff(
+            &self.repo_root,
+            from.map(|f| f.refname.as_str()),
+            &to.refname,
+        );
+        b.preview = Some(match diff {
+            Ok(d) if d.trim().is_empty() => vec!["(no changes)".to_string()],
+            Ok(d) => d.lines().map(|l| l.to_string()).collect(),
+            Err(e) => vec![format!("✗ {}", e)],
+        });
+        b.scroll = 0;
+    }
+
+    fn handle_checkpoints_key(
+        &mut self,
+        code: KeyCode,
+        _modifiers: KeyModifiers,
+    ) -> Result<Action> {
+        if let Some(confirm) = self.checkpoints.confirm.take() {
+            if matches!(code, KeyCode::Char('y') | KeyCode::Enter) {
+                self.run_checkpoint_action(confirm);
+            }
+            return Ok(Action::None);
+        }
+
+        if self.checkpoints.files.is_some() {
+            self.handle_restore_files_key(code);
+            return Ok(Action::None);
+        }
+
+        let count = self.checkpoints.checkpoints.len();
+        let b = &mut self.checkpoints;
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                if b.preview.is_some() {
+                    b.preview = None;
+                    b.scroll = 0;
+                } else {
+                    self.mode = Mode::Normal;
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') if b.selected > 0 => {
+                b.selected -= 1;
+                self.after_checkpoint_move();
+            }
+            KeyCode::Down | KeyCode::Char('j') if b.selected + 1 < count => {
+                b.selected += 1;
+                self.after_checkpoint_move();
+            }
+            KeyCode::Char(' ') if count > 0 => {
+                b.mark = if b.mark == Some(b.selected) {
+                    None
+                } else {
+                    Some(b.selected)
+                };
+                if b.preview.is_some() {
+                    self.preview_checkpoint_diff();
+                }
+            }
+            KeyCode::Enter | KeyCode::Char('d') if count > 0 => {
+                if b.preview.is_some() {
+                    b.preview = None;
+                    b.scroll = 0;
+                } else {
+                    self.preview_checkpoint_diff();
+                }
+            }
+            KeyCode::PageDown => b.scroll = b.scroll.saturating_add(10),
+            KeyCode::PageUp => b.scroll = b.scroll.saturating_sub(10),
+            KeyCode::Char('R') if count > 0 => {
+                if self.checkpoint_task_running() {
+                    self.error = Some("Stop the task before rolling back".into());
+                } else {
+                    let b = &mut self.checkpoints;
+                    b.confirm = Some(CheckpointConfirm::Rollback(b.checkpoints[b.selected].index));
+                }
+            }
+            KeyCode::Char('f') if count > 0 => self.open_restore_files(),
+            KeyCode::Char('u') => {
+                if !b.can_undo {
+                    self.error = Some("No rollback to undo".into());
+                } else if self.checkpoint_task_running() {
+                    self.error = Some("Stop the task before undoing a rollback".into());
+                } else {
+                    self.checkpoints.confirm = Some(CheckpointConfirm::Undo);
+                }
+            }
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    /// Open the file picker with every path that differs between the
+    /// worktree and the selected checkpoint.
+    fn open_restore_files(&mut self) {
+        let Some(t) = self.tasks.iter().find(|t| t.id == self.checkpoints.task_id) else {
+            return;
+        };
+        let b = &mut self.checkpoints;
+        let cp = &b.checkpoints[b.selected];
+        match checkpoint::changed_paths(std::path::Path::new(&t.worktree), &cp.refname) {
+            Ok(paths) if paths.is_empty() => {
+                self.error = Some(format!("Worktree already matches checkpoint #{}", cp.index));
+            }
+            Ok(paths) => {
+                b.files = Some(paths.into_iter().map(|p| (p, false)).collect());
+                b.file_cursor = 0;
+            }
+            Err(e) => self.error = Some(format!("✗ {}", e)),
+        }
+    }
+
+    fn handle_restore_files_key(&mut self, code: KeyCode) {
+        let running = self.checkpoint_task_running();
+        let b = &mut self.checkpoints;
+        let Some(files) = b.files.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => b.files = None,
+            KeyCode::Up | KeyCode::Char('k') => b.file_cursor = b.file_cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if b.file_cursor + 1 < files.len() => {
+                b.file_cursor += 1;
+            }
+            KeyCode::Char(' ') => {
+                if let Some(f) = files.get_mut(b.file_cursor) {
+                    f.1 = !f.1;
+                }
+            }
+            KeyCode::Char('a') => {
+                let all = files.iter().all(|f| f.1);
+                files.iter_mut().for_each(|f| f.1 = !all);
+            }
+            KeyCode::Enter | KeyCode::Char('R') => {
+                if !files.iter().any(|f| f.1) {
+                    self.error = Some("Pick files with Space first".into());
+                } else if running {
+                    self.error = Some("Stop the task before restoring files".into());
+                } else {
+                    b.confirm = Some(CheckpointConfirm::RestoreFiles(
+                        b.checkpoints[b.selected].index,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn after_checkpoint_move(&mut self) {
+        self.checkpoints.scroll = 0;
+        if self.checkpoints.preview.is_some() {
+            self.preview_checkpoint_diff();
+        }
+    }
+
+    fn checkpoint_task_running(&self) -> bool {
+        self.tasks
+            .iter()
+            .any(|t| t.id == self.checkpoints.task_id && t.status == task::Status::Running)
+    }
+
+    fn run_checkpoint_action(&mut self, action: CheckpointConfirm) {
+        let Some(t) = self
+            .tasks
+            .iter()
+            .find(|t| t.id == self.checkpoints.task_id)
+            .cloned()
+        else {
+            return;
+        };
+        let worktree = std::path::Path::new(&t.worktree);
+        let result = match action {
+            CheckpointConfirm::Rollback(idx) => {
+                checkpoint::rollback(&self.repo_root, t.id, &t.name, worktree, Some(idx))
+                    .map(|idx| format!("✓ Rolled back to checkpoint #{}", idx))
+            }
+            CheckpointConfirm::RestoreFiles(idx) => {
+                let paths: Vec<String> = self
+                    .checkpoints
+                    .files
+                    .take()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|f| f.1)
+                    .map(|f| f.0)
+                    .collect();
+                checkpoint::restore_paths(&self.repo_root, &t, Some(idx), &paths, false).map(
+                    |(idx, _)| {
+                        format!(
+                            "✓ Restored {} file(s) from checkpoint #{}",
+                            paths.len(),
+                            idx
+                        )
+                    },
+                )
+            }
+            CheckpointConfirm::Undo => {
+                checkpoint::undo_rollback(&self.repo_root, t.id, &t.name, worktree)
+                    .map(|_| "✓ Rollback undone".to_string())
+            }
+        };
+        self.error = Some(match result {
+            Ok(msg) => msg,
+            Err(e) => format!("Rollback failed: {}", e),
+        });
+        self.reload_checkpoints();
+        self.checkpoints.preview = None;
+        self.force_refresh_detail();
+    }
+
     fn open_issue_picker(&mut self) {
         if crate::core::config::get("linear.api_key").is_some() {
             self.mode = Mode::IssuePicker;
@@ -1067,8 +1857,9 @@ impl App {
                         issue.identifier, issue.title, issue.state
                     ));
 
-                    // Fill prompt from issue title + description
-                    self.modal.prompt = crate::core::linear::issue_to_prompt(&issue);
+                    // Fill prompt from issue title + description. Comments
+                    // and links are fetched on create, like `pit new --issue`.
+                    self.modal.prompt = crate::core::issues::issue_to_prompt(&issue.clone().into());
                     self.modal.prompt_cursor = self.modal.prompt.len();
                     self.modal.prompt_scroll = 0;
 
@@ -1134,6 +1925,17 @@ impl App {
     }
 
     fn try_submit(&mut self) -> Result<Action> {
+        if let Some(id) = self.modal.editing {
+            self.mode = Mode::Normal;
+            return Ok(Action::UpdateTask {
+                id,
+                prompt: self.modal.prompt.trim().to_string(),
+                issue_url: self.modal.issue.trim().to_string(),
+                agent: self.modal.agent.clone(),
+                template: self.modal.template.clone(),
+            });
+        }
+
         let name = self.modal.name.trim().to_string();
 
         if name.is_empty() {
@@ -1153,6 +1955,7 @@ impl App {
         let prompt = self.modal.prompt.trim().to_string();
         let issue_url = self.modal.issue.trim().to_string();
         let agent = self.modal.agent.clone();
+        let template = self.modal.template.clone();
 
         self.mode = Mode::Normal;
         Ok(Action::CreateTask {
@@ -1160,6 +1963,7 @@ impl App {
             prompt,
             issue_url,
             agent,
+            template,
         })
     }
 }
@@ -1169,15 +1973,52 @@ pub enum Action {
     None,
     Enter(i64),
     Delete(i64),
+    /// Archive the task (worktree removed, branch kept).
+    Archive(i64),
     Shell(i64),
+    /// Add the task to the merge queue, or take it out.
+    ToggleQueue(i64),
+    /// Suspend the TUI and land the merge queue.
+    LandQueue,
+    /// Run the task's checks in the background.
+    Test(i64),
+    /// Create a task for each TODO marker.
+    Harvest(Vec<harvest::Marker>),
+    /// Suspend the TUI and edit the modal's prompt in an external editor.
+    EditPrompt,
     CreateTask {
         name: String,
         prompt: String,
         issue_url: String,
         agent: String,
+        template: String,
+    },
+    UpdateTask {
+        id: i64,
+        prompt: String,
+        issue_url: String,
+        agent: String,
+        template: String,
     },
 }
 
+/// The result of a background job, once it has finished. The job is
+/// cleared when it finishes or its thread goes away.
+fn poll<T>(job: &mut Option<mpsc::Receiver<T>>) -> Option<T> {
+    let result = job.as_ref()?.try_recv();
+    match result {
+        Ok(value) => {
+            *job = None;
+            Some(value)
+        }
+        Err(mpsc::TryRecvError::Empty) => None,
+        Err(mpsc::TryRecvError::Disconnected) => {
+            *job = None;
+            None
+        }
+    }
+}
+
 // --- TUI loop ---
 
 pub fn run(project: &Project) -> Result<()> {
@@ -1217,24 +2058,74 @@ fn run_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
                         handle_delete(app, task_id)?;
                         app.refresh()?;
                     }
+                    Action::Archive(task_id) => {
+                        handle_archive(app, task_id)?;
+                        app.refresh()?;
+                    }
                     Action::Shell(task_id) => {
                         ratatui::restore();
                         handle_shell(app, task_id)?;
                         *terminal = ratatui::init();
                         app.refresh()?;
                     }
+                    Action::ToggleQueue(task_id) => {
+                        handle_toggle_queue(app, task_id)?;
+                        app.refresh()?;
+                    }
+                    Action::Test(task_id) => {
+                        handle_test(app, task_id)?;
+                        app.refresh()?;
+                    }
+                    Action::Harvest(markers) => {
+                        handle_harvest(app, &markers)?;
+                        app.refresh()?;
+                        if !app.tasks.is_empty() {
+                            app.selected = app.tasks.len() - 1;
+                        }
+                    }
+                    Action::LandQueue => {
+                        ratatui::restore();
+                        handle_land_queue(app)?;
+                        *terminal = ratatui::init();
+                        app.refresh()?;
+                        app.force_refresh_detail();
+                    }
+                    Action::EditPrompt => {
+                        ratatui::restore();
+                        let edited = crate::core::editor::edit(&app.modal.prompt, "md");
+                        *terminal = ratatui::init();
+                        match edited {
+                            Ok(text) => {
+                                app.modal.set_prompt(text.trim_end().to_string());
+                                app.modal.update_prompt_scroll();
+                            }
+                            Err(e) => app.error = Some(e.to_string()),
+                        }
+                    }
                     Action::CreateTask {
                         name,
                         prompt,
                         issue_url,
                         agent,
+                        template,
                     } => {
-                        handle_create(app, &name, &prompt, &issue_url, &agent)?;
+                        handle_create(app, &name, &prompt, &issue_url, &agent, &template)?;
                         app.refresh()?;
                         if !app.tasks.is_empty() {
                             app.selected = app.tasks.len() - 1;
                         }
                     }
+                    Action::UpdateTask {
+                        id,
+                        prompt,
+                        issue_url,
+                        agent,
+                        template,
+                    } => {
+                        handle_update(app, id, &prompt, &issue_url, &agent, &template)?;
+                        app.refresh()?;
+                        app.force_refresh_detail();
+                    }
                 }
             }
         }
@@ -1256,25 +2147,170 @@ fn handle_create(
     prompt: &str,
     issue_url: &str,
     agent: &str,
+    template_name: &str,
 ) -> Result<()> {
     let db = crate::db::open(&app.db_path)?;
+    // Saved even if creation fails, so the prompt can be recalled.
+    let _ = history::push(&app.repo_root, prompt);
+
+    // Fetch comments and linked issues so the agent gets the full picture.
+    let fetched = if issue_url.is_empty() {
+        None
+    } else {
+        crate::core::issues::fetch(issue_url).ok()
+    };
+
+    let tpl = if template_name.is_empty() {
+        None
+    } else {
+        match template::load(&app.repo_root, template_name) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                app.error = Some(e.to_string());
+                return Ok(());
+            }
+        }
+    };
+    let mut prompt = prompt.to_string();
+    if let Some(tpl) = &tpl {
+        let base = git_info::detect_main_branch(&app.repo_root).unwrap_or_else(|_| "main".into());
+        let vars = template::variables(name, &base, fetched.as_ref(), &[]);
+        if let Some(e) = missing_vars(tpl, &prompt, &vars) {
+            app.error = Some(e);
+            return Ok(());
+        }
+        prompt = template::render(&prompt, &vars);
+    }
+    let prompt = match &fetched {
+        Some(i) => crate::core::issues::prompt_for(&prompt, i),
+        None => prompt,
+    };
+    let model = tpl.as_ref().map(|t| t.model.as_str()).unwrap_or("");
+
     match task::create(
         &db,
-        &app.repo_root,
-        &CreateOpts {
-            name,
-            description: "",
-            prompt,
-            issue_url,
-            agent,
+        &app.repo_root,
+        &CreateOpts {
+            name,
+            description: "",
+            prompt: &prompt,
+            issue_url,
+            agent,
+            model,
+        },
+    ) {
+        Ok(t) => {
+            let worktree = std::path::Path::new(&t.worktree);
+            if let Some(i) = &fetched {
+                if let Err(e) = crate::core::issues::write_context_file(worktree, i) {
+                    app.error = Some(e.to_string());
+                }
+            }
+            if let Some(tpl) = &tpl {
+                if let Err(e) = template::run_setup_hook(tpl, worktree) {
+                    app.error = Some(e.to_string());
+                }
+            }
+            Ok(())
+        }
+        Err(e) => {
+            app.error = Some(e.to_string());
+            Ok(())
+        }
+    }
+}
+
+/// The error to show when `prompt` has placeholders the template can't
+/// fill. The dashboard has no `--var`, so they have to be typed in.
+fn missing_vars(
+    tpl: &template::Template,
+    prompt: &str,
+    vars: &std::collections::HashMap<String, String>,
+) -> Option<String> {
+    let missing = template::unresolved(prompt, vars);
+    if missing.is_empty() {
+        return None;
+    }
+    let names: Vec<String> = missing.iter().map(|m| format!("{{{}}}", m)).collect();
+    Some(format!(
+        "template '{}' needs values for: {} (replace them in the prompt)",
+        tpl.name,
+        names.join(" ")
+    ))
+}
+
+fn handle_update(
+    app: &mut App,
+    id: i64,
+    prompt: &str,
+    issue_url: &str,
+    agent: &str,
+    template_name: &str,
+) -> Result<()> {
+    let db = crate::db::open(&app.db_path)?;
+    let Some(current) = task::get(&db, id)? else {
+        return Ok(());
+    };
+
+    let tpl = if template_name.is_empty() {
+        None
+    } else {
+        match template::load(&app.repo_root, template_name) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                app.error = Some(e.to_string());
+                return Ok(());
+            }
+        }
+    };
+    // Only fetch when the issue link changed; the context file for the old
+    // link is already in the worktree.
+    let fetched = if !issue_url.is_empty() && issue_url != current.issue_url {
+        crate::core::issues::fetch(issue_url).ok()
+    } else {
+        None
+    };
+
+    let mut prompt = prompt.to_string();
+    if let Some(tpl) = &tpl {
+        let base = git_info::detect_main_branch(&app.repo_root).unwrap_or_else(|_| "main".into());
+        let vars = template::variables(&current.name, &base, fetched.as_ref(), &[]);
+        if let Some(e) = missing_vars(tpl, &prompt, &vars) {
+            app.error = Some(e);
+            return Ok(());
+        }
+        prompt = template::render(&prompt, &vars);
+    }
+    let prompt = match &fetched {
+        Some(i) => crate::core::issues::prompt_for(&prompt, i),
+        None => prompt,
+    };
+    let model = tpl
+        .as_ref()
+        .map(|t| t.model.as_str())
+        .filter(|m| !m.is_empty());
+
+    match task::update(
+        &db,
+        id,
+        &task::UpdateOpts {
+            prompt: Some(&prompt),
+            issue_url: Some(issue_url),
+            agent: Some(agent),
+            model,
+            ..Default::default()
         },
     ) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            app.error = Some(e.to_string());
-            Ok(())
+        Ok(t) => {
+            if let Some(i) = &fetched {
+                let _ =
+                    crate::core::issues::write_context_file(std::path::Path::new(&t.worktree), i);
+            }
+            app.error = Some(format!("✓ Updated {}", t.name));
         }
+        Err(e) => app.error = Some(e.to_string()),
     }
+    Ok(())
 }
 
 /// Build the shell command to launch an agent for a task.
@@ -1301,30 +2337,38 @@ pub fn build_agent_cmd(task: &Task) -> (String, String) {
     }
     let prompt_path = prompt_file.to_string_lossy();
 
+    // Model override goes right after the binary name. Amp has no model
+    // flag and custom commands are taken verbatim.
+    let model = if task.model.is_empty() {
+        String::new()
+    } else {
+        format!(" --model '{}'", task.model.replace('\'', ""))
+    };
+
     let cmd = match task.agent.as_str() {
         "pi" => {
             // Pi coding agent: --continue resumes last session, prompt is positional
             // Pi uses -p for non-interactive (like claude), so prompt is positional
             if is_resume {
-                "pi --continue".to_string()
+                format!("pi{} --continue", model)
             } else if !task.prompt.is_empty() {
-                format!("pi \"$(cat '{}')\"", prompt_path)
+                format!("pi{} \"$(cat '{}')\"", model, prompt_path)
             } else {
-                "pi".to_string()
+                format!("pi{}", model)
             }
         }
         "codex" => {
             if !task.prompt.is_empty() {
-                format!("codex \"$(cat '{}')\"", prompt_path)
+                format!("codex{} \"$(cat '{}')\"", model, prompt_path)
             } else {
-                "codex".to_string()
+                format!("codex{}", model)
             }
         }
         "aider" => {
             if !task.prompt.is_empty() {
-                format!("aider --message \"$(cat '{}')\"", prompt_path)
+                format!("aider{} --message \"$(cat '{}')\"", model, prompt_path)
             } else {
-                "aider".to_string()
+                format!("aider{}", model)
             }
         }
         "amp" => {
@@ -1335,11 +2379,16 @@ pub fn build_agent_cmd(task: &Task) -> (String, String) {
             }
         }
         "goose" => {
-            // Block's Goose agent: prompt via positional arg
+            // Block's Goose agent: prompt via positional arg, model via env
+            let goose = if task.model.is_empty() {
+                "goose".to_string()
+            } else {
+                format!("GOOSE_MODEL='{}' goose", task.model.replace('\'', ""))
+            };
             if !task.prompt.is_empty() {
-                format!("goose \"$(cat '{}')\"", prompt_path)
+                format!("{} \"$(cat '{}')\"", goose, prompt_path)
             } else {
-                "goose".to_string()
+                goose
             }
         }
         "custom" => {
@@ -1352,14 +2401,14 @@ pub fn build_agent_cmd(task: &Task) -> (String, String) {
         // Default: claude (with session resume support)
         _ => {
             if is_resume {
-                format!("claude -r {}", session_id)
+                format!("claude{} -r {}", model, session_id)
             } else if !task.prompt.is_empty() {
                 format!(
-                    "claude --session-id {} \"$(cat '{}')\"",
-                    session_id, prompt_path
+                    "claude{} --session-id {} \"$(cat '{}')\"",
+                    model, session_id, prompt_path
                 )
             } else {
-                format!("claude --session-id {}", session_id)
+                format!("claude{} --session-id {}", model, session_id)
             }
         }
     };
@@ -1401,7 +2450,7 @@ fn handle_shell(app: &mut App, task_id: i64) -> Result<()> {
     let db = crate::db::open(&app.db_path)?;
     let task = task::get(&db, task_id)?.ok_or_else(|| anyhow::anyhow!("task not found"))?;
 
-    let tmux_name = format!("pit-shell-{}", task.name);
+    let tmux_name = tmux::shell_session_name(&task.name);
 
     if !tmux::session_exists(&tmux_name) {
         // Launch a plain shell in the task's worktree
@@ -1413,6 +2462,81 @@ fn handle_shell(app: &mut App, task_id: i64) -> Result<()> {
     Ok(())
 }
 
+fn handle_test(app: &mut App, task_id: i64) -> Result<()> {
+    let db = crate::db::open(&app.db_path)?;
+    let task = task::get(&db, task_id)?.ok_or_else(|| anyhow::anyhow!("task not found"))?;
+    if checks::Commands::from_config(&app.repo_root).is_empty() {
+        app.error =
+            Some("no test_command configured (pit config set --repo test_command …)".into());
+        return Ok(());
+    }
+    if task.test_status != "running" {
+        checks::spawn(&db, &app.repo_root, &task)?;
+    }
+    Ok(())
+}
+
+fn handle_harvest(app: &mut App, markers: &[harvest::Marker]) -> Result<()> {
+    let db = crate::db::open(&app.db_path)?;
+    let mut created = 0;
+    for m in markers {
+        match harvest::create(&db, &app.repo_root, m, "claude", "") {
+            Ok(_) => created += 1,
+            Err(e) => {
+                app.error = Some(format!("✗ {}:{}: {}", m.file, m.line, e));
+                return Ok(());
+            }
+        }
+    }
+    app.error = Some(format!("✓ Created {} task(s) from TODOs", created));
+    Ok(())
+}
+
+fn handle_toggle_queue(app: &mut App, task_id: i64) -> Result<()> {
+    let db = crate::db::open(&app.db_path)?;
+    if app.queue_position(task_id).is_some() {
+        queue::remove(&db, task_id)
+    } else {
+        queue::add(&db, task_id)
+    }
+}
+
+/// Land the merge queue with the test output on the plain terminal, then
+/// wait for Enter before going back to the dashboard.
+fn handle_land_queue(app: &mut App) -> Result<()> {
+    let db = crate::db::open(&app.db_path)?;
+    let checks = checks::Commands::from_config(&app.repo_root);
+    if checks.is_empty() {
+        println!("No test_command configured — merging without tests");
+    }
+    let results = queue::run(&db, &app.repo_root, &checks, |t, outcome| match outcome {
+        None => println!("\n── {} ──", t.name),
+        Some(queue::Outcome::Merged { commits }) => {
+            println!("✓ Merged {} ({} commit(s))", t.branch, commits)
+        }
+        Some(queue::Outcome::Failed(error)) => println!("✗ {}: {}", t.name, error),
+    });
+    match results {
+        Ok(results) => {
+            let failed = results
+                .iter()
+                .filter(|(_, o)| matches!(o, queue::Outcome::Failed(_)))
+                .count();
+            println!(
+                "\n{} merged, {} still queued",
+                results.len() - failed,
+                failed
+            );
+        }
+        Err(e) => println!("\nMerge queue stopped: {}", e),
+    }
+    print!("Press Enter to return to pit ");
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(())
+}
+
 fn handle_delete(app: &mut App, task_id: i64) -> Result<()> {
     let db = crate::db::open(&app.db_path)?;
     let task = task::get(&db, task_id)?.ok_or_else(|| anyhow::anyhow!("task not found"))?;
@@ -1420,6 +2544,7 @@ fn handle_delete(app: &mut App, task_id: i64) -> Result<()> {
     if let Some(ref tmux_name) = task.tmux_session {
         tmux::kill_session(tmux_name)?;
     }
+    tmux::kill_session(&tmux::shell_session_name(&task.name))?;
     if task.status == task::Status::Running {
         task::set_status(&db, task_id, &task::Status::Idle)?;
     }
@@ -1428,6 +2553,31 @@ fn handle_delete(app: &mut App, task_id: i64) -> Result<()> {
     Ok(())
 }
 
+fn handle_archive(app: &mut App, task_id: i64) -> Result<()> {
+    let db = crate::db::open(&app.db_path)?;
+    let task = task::get(&db, task_id)?.ok_or_else(|| anyhow::anyhow!("task not found"))?;
+
+    if let Some(ref tmux_name) = task.tmux_session {
+        tmux::kill_session(tmux_name)?;
+    }
+    tmux::kill_session(&tmux::shell_session_name(&task.name))?;
+    if task.status == task::Status::Running {
+        task::set_status(&db, task_id, &task::Status::Idle)?;
+    }
+    let task = task::get(&db, task_id)?.ok_or_else(|| anyhow::anyhow!("task not found"))?;
+
+    match archive::archive(&db, &app.repo_root, &task) {
+        Ok(()) => {
+            app.error = Some(format!(
+                "✓ Archived '{}' — bring it back with: pit restore {}",
+                task.name, task.name
+            ))
+        }
+        Err(e) => app.error = Some(format!("✗ Archive failed: {}", e)),
+    }
+    Ok(())
+}
+
 // --- Tests ---
 
 #[cfg(test)]
@@ -1459,6 +2609,19 @@ mod tests {
             detail_pane_height: 30,
             show_live_output: false,
             live_output: Vec::new(),
+            checkpoints: CheckpointBrowser::default(),
+            auto_checkpoint_at: None,
+            auto_checkpoint_job: None,
+            pr_refresh_at: None,
+            pr_job: None,
+            behind: std::collections::HashMap::new(),
+            behind_at: None,
+            overlaps: Vec::new(),
+            overlaps_at: None,
+            overlaps_job: None,
+            queue: Vec::new(),
+            harvest: HarvestPicker::default(),
+            markers_present: std::collections::HashMap::new(),
         }
     }
 
@@ -1481,6 +2644,7 @@ mod tests {
             prompt: String::new(),
             issue_url: String::new(),
             agent: "claude".to_string(),
+            model: String::new(),
             branch: format!("pit/{}", name),
             worktree: format!("/tmp/wt/{}", name),
             status,
@@ -1489,6 +2653,19 @@ mod tests {
             pid: None,
             created_at: String::new(),
             updated_at: String::new(),
+            pr_url: String::new(),
+            pr_state: String::new(),
+            test_status: String::new(),
+            test_duration_ms: 0,
+            test_summary: String::new(),
+            tested_at: String::new(),
+            source_file: String::new(),
+            source_line: 0,
+            source_marker: String::new(),
+            adopted: false,
+            parent_id: None,
+            fork_point: String::new(),
+            archived_at: String::new(),
         }
     }
 
@@ -1561,6 +2738,32 @@ mod tests {
         assert!(matches!(action, Action::Delete(1)));
     }
 
+    #[test]
+    fn poll_waits_for_a_background_job() {
+        let (tx, rx) = mpsc::channel();
+        let mut job = Some(rx);
+        assert_eq!(poll(&mut job), None);
+        assert!(job.is_some());
+        tx.send(3).unwrap();
+        assert_eq!(poll(&mut job), Some(3));
+        assert!(job.is_none());
+
+        let (tx, rx) = mpsc::channel::<i32>();
+        let mut job = Some(rx);
+        drop(tx);
+        assert_eq!(poll(&mut job), None);
+        assert!(job.is_none());
+    }
+
+    #[test]
+    fn archive_returns_task_id() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Idle)]);
+        let action = app
+            .handle_key(KeyCode::Char('A'), KeyModifiers::SHIFT)
+            .unwrap();
+        assert!(matches!(action, Action::Archive(1)));
+    }
+
     #[test]
     fn shell_returns_task_id() {
         let mut app = make_app(vec![make_task(1, "a", task::Status::Idle)]);
@@ -1570,6 +2773,75 @@ mod tests {
         assert!(matches!(action, Action::Shell(1)));
     }
 
+    #[test]
+    fn queue_keys_toggle_and_land() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Done)]);
+        let action = app
+            .handle_key(KeyCode::Char('m'), KeyModifiers::NONE)
+            .unwrap();
+        assert!(matches!(action, Action::ToggleQueue(1)));
+
+        // Nothing to land until something is queued
+        let action = app
+            .handle_key(KeyCode::Char('M'), KeyModifiers::NONE)
+            .unwrap();
+        assert!(matches!(action, Action::None));
+        app.queue = vec![queue::Entry {
+            task_id: 1,
+            position: 1,
+            error: String::new(),
+        }];
+        assert_eq!(app.queue_position(1), Some(1));
+        let action = app
+            .handle_key(KeyCode::Char('M'), KeyModifiers::SHIFT)
+            .unwrap();
+        assert!(matches!(action, Action::LandQueue));
+    }
+
+    #[test]
+    fn shift_t_tests_selected_task() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Idle)]);
+        let action = app
+            .handle_key(KeyCode::Char('T'), KeyModifiers::SHIFT)
+            .unwrap();
+        assert!(matches!(action, Action::Test(1)));
+    }
+
+    #[test]
+    fn harvest_picker_creates_picked_markers() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Idle)]);
+        let marker = |line: u32| harvest::Marker {
+            file: "src/lib.rs".into(),
+            line,
+            text: format!("// TODO(agent): job {}", line),
+        };
+        app.harvest = HarvestPicker {
+            pattern: harvest::DEFAULT_PATTERN.into(),
+            items: [None, Some("todo-job-2".to_string()), None]
+                .into_iter()
+                .enumerate()
+                .map(|(i, task)| HarvestItem {
+                    marker: marker(i as u32 + 1),
+                    task,
+                    picked: false,
+                })
+                .collect(),
+            cursor: 0,
+        };
+        app.mode = Mode::Harvest;
+
+        // Already harvested markers can't be picked
+        app.handle_key(KeyCode::Char('a'), KeyModifiers::NONE)
+            .unwrap();
+        let picked: Vec<bool> = app.harvest.items.iter().map(|i| i.picked).collect();
+        assert_eq!(picked, vec![true, false, true]);
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE)
+            .unwrap();
+        let action = app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap();
+        assert!(matches!(action, Action::Harvest(ref m) if m.len() == 1 && m[0].line == 3));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
     #[test]
     fn shell_on_empty_list_is_noop() {
         let mut app = make_app(vec![]);
@@ -1629,6 +2901,9 @@ mod tests {
         app.handle_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
         assert_eq!(app.modal.field, ModalField::Agent);
 
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.field, ModalField::Template);
+
         app.handle_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
         assert_eq!(app.modal.field, ModalField::AutoApprove);
 
@@ -1678,6 +2953,169 @@ mod tests {
         assert_eq!(app.modal.agent, "codex");
     }
 
+    #[test]
+    fn modal_template_cycle_applies_template() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = template::dir(repo.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("bugfix.md"),
+            "+++\nagent = \"codex\"\n+++\nFix {issue.title}",
+        )
+        .unwrap();
+        std::fs::write(dir.join("docs.md"), "Write docs for {name}").unwrap();
+
+        let mut app = make_app(vec![]);
+        app.repo_root = repo.path().to_path_buf();
+        app.handle_key(KeyCode::Char('n'), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.modal.templates, vec!["bugfix", "docs"]);
+        app.modal.field = ModalField::Template;
+
+        app.handle_key(KeyCode::Right, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.template, "bugfix");
+        assert_eq!(app.modal.agent, "codex");
+        assert_eq!(app.modal.prompt, "Fix {issue.title}");
+
+        // Unedited prompt follows the template
+        app.handle_key(KeyCode::Right, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.prompt, "Write docs for {name}");
+
+        // Edited prompt is kept
+        app.modal.prompt.push_str(" please");
+        app.handle_key(KeyCode::Right, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.template, "");
+        assert_eq!(app.modal.prompt, "Write docs for {name} please");
+
+        app.handle_key(KeyCode::Left, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.template, "docs");
+        match app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap() {
+            Action::CreateTask { template, .. } => assert_eq!(template, "docs"),
+            other => panic!("expected CreateTask, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn create_with_unfilled_template_vars_shows_an_error() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = template::dir(repo.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("port.md"), "Port {module} to {target}").unwrap();
+
+        let mut app = make_app(vec![]);
+        app.repo_root = repo.path().to_path_buf();
+        app.db_path = repo.path().join("pit.db");
+        handle_create(
+            &mut app,
+            "port",
+            "Port {module} to {target}",
+            "",
+            "claude",
+            "port",
+        )
+        .unwrap();
+        let error = app.error.clone().unwrap();
+        assert!(error.contains("{module} {target}"), "{}", error);
+        let db = crate::db::open(&app.db_path).unwrap();
+        assert!(task::list(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn edit_modal_prefills_and_submits_update() {
+        let mut t = make_task(7, "fix-login", task::Status::Idle);
+        t.prompt = "old prompt".to_string();
+        t.agent = "pi".to_string();
+        t.issue_url = "https://github.com/o/r/issues/3".to_string();
+        let mut app = make_app(vec![t]);
+
+        app.handle_key(KeyCode::Char('e'), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.mode, Mode::NewTask);
+        assert_eq!(app.modal.editing, Some(7));
+        assert_eq!(app.modal.field, ModalField::Prompt);
+        assert_eq!(app.modal.prompt, "old prompt");
+        assert_eq!(app.modal.prompt_cursor, "old prompt".len());
+        assert_eq!(app.modal.agent, "pi");
+
+        app.handle_key(KeyCode::Char('!'), KeyModifiers::NONE)
+            .unwrap();
+        app.modal.field = ModalField::Agent;
+        app.handle_key(KeyCode::Right, KeyModifiers::NONE).unwrap();
+
+        match app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap() {
+            Action::UpdateTask {
+                id,
+                prompt,
+                agent,
+                issue_url,
+                ..
+            } => {
+                assert_eq!(id, 7);
+                assert_eq!(prompt, "old prompt!");
+                assert_eq!(agent, "codex");
+                assert_eq!(issue_url, "https://github.com/o/r/issues/3");
+            }
+            other => panic!("expected UpdateTask, got {:?}", other),
+        }
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn edit_modal_name_is_read_only() {
+        let mut app = make_app(vec![make_task(1, "keep", task::Status::Idle)]);
+        app.handle_key(KeyCode::Char('e'), KeyModifiers::NONE)
+            .unwrap();
+        app.modal.field = ModalField::Name;
+        app.handle_key(KeyCode::Char('x'), KeyModifiers::NONE)
+            .unwrap();
+        app.handle_key(KeyCode::Backspace, KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.modal.name, "keep");
+    }
+
+    #[test]
+    fn modal_ctrl_e_requests_editor() {
+        let mut app = make_app(vec![]);
+        app.handle_key(KeyCode::Char('n'), KeyModifiers::NONE)
+            .unwrap();
+        app.modal.field = ModalField::Agent;
+        let action = app
+            .handle_key(KeyCode::Char('e'), KeyModifiers::CONTROL)
+            .unwrap();
+        assert!(matches!(action, Action::EditPrompt));
+        assert_eq!(app.modal.field, ModalField::Prompt);
+        assert_eq!(app.mode, Mode::NewTask);
+    }
+
+    #[test]
+    fn modal_up_down_recall_history() {
+        let mut app = make_app(vec![]);
+        app.handle_key(KeyCode::Char('n'), KeyModifiers::NONE)
+            .unwrap();
+        app.modal.history = vec!["older".to_string(), "two\nlines".to_string()];
+        app.modal.field = ModalField::Prompt;
+        app.modal.set_prompt("draft".to_string());
+
+        // Up on the first line recalls the newest entry
+        app.handle_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.prompt, "two\nlines");
+        // Cursor is at the end (second line), so Up moves within the text
+        app.handle_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.prompt, "two\nlines");
+        app.handle_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.prompt, "older");
+        // Nothing older: stays put
+        app.handle_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.prompt, "older");
+
+        app.handle_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.prompt, "two\nlines");
+        // Down past the newest entry restores the draft
+        app.handle_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.modal.prompt, "draft");
+        assert_eq!(app.modal.prompt_cursor, "draft".len());
+    }
+
     #[test]
     fn modal_auto_approve_toggle() {
         let mut app = make_app(vec![]);
@@ -1874,6 +3312,7 @@ mod tests {
             prompt: prompt.to_string(),
             issue_url: String::new(),
             agent: agent.to_string(),
+            model: String::new(),
             branch: "pit/test".to_string(),
             worktree,
             status: task::Status::Idle,
@@ -1882,6 +3321,19 @@ mod tests {
             pid: None,
             created_at: String::new(),
             updated_at: String::new(),
+            pr_url: String::new(),
+            pr_state: String::new(),
+            test_status: String::new(),
+            test_duration_ms: 0,
+            test_summary: String::new(),
+            tested_at: String::new(),
+            source_file: String::new(),
+            source_line: 0,
+            source_marker: String::new(),
+            adopted: false,
+            parent_id: None,
+            fork_point: String::new(),
+            archived_at: String::new(),
         }
     }
 
@@ -1903,6 +3355,26 @@ mod tests {
         assert_eq!(content, "fix bug");
     }
 
+    #[test]
+    fn agent_cmd_passes_model() {
+        let mut task = make_task_with_agent("claude", "fix bug", Some("sess-m"));
+        task.model = "opus".to_string();
+        assert_eq!(build_agent_cmd(&task).0, "claude --model 'opus' -r sess-m");
+
+        task.agent = "aider".to_string();
+        assert!(build_agent_cmd(&task)
+            .0
+            .starts_with("aider --model 'opus' --message"));
+
+        task.agent = "goose".to_string();
+        assert!(build_agent_cmd(&task)
+            .0
+            .starts_with("GOOSE_MODEL='opus' goose "));
+
+        task.agent = "amp".to_string();
+        assert!(!build_agent_cmd(&task).0.contains("opus"));
+    }
+
     #[test]
     fn agent_cmd_claude_resume_session() {
         let task = make_task_with_agent("claude", "fix bug", Some("sess-123"));
@@ -3025,4 +4497,164 @@ mod tests {
         app.refresh_live_output();
         assert!(app.live_output.is_empty());
     }
+
+    // --- Checkpoint browser ---
+
+    fn fake_checkpoints(n: usize) -> Vec<checkpoint::Checkpoint> {
+        (1..=n)
+            .map(|i| checkpoint::Checkpoint {
+                index: i,
+                refname: format!("refs/pit/1/checkpoints/{}", i),
+                commit_hash: format!("abc{}", i),
+                message: format!("change {}", i),
+                timestamp: "1 hour ago".to_string(),
+                annotation: String::new(),
+                dirty: false,
+                auto: false,
+                created: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn restore_file_picker_toggles_and_confirms() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Idle)]);
+        app.mode = Mode::Checkpoints;
+        app.checkpoints.task_id = 1;
+        app.checkpoints.checkpoints = fake_checkpoints(2);
+        app.checkpoints.selected = 0;
+        app.checkpoints.files = Some(vec![("x.rs".into(), false), ("y.rs".into(), false)]);
+
+        // Nothing picked yet
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap();
+        assert!(app.checkpoints.confirm.is_none());
+
+        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE)
+            .unwrap();
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(
+            app.checkpoints.files.as_ref().unwrap()[1],
+            ("y.rs".to_string(), true)
+        );
+        app.handle_key(KeyCode::Char('a'), KeyModifiers::NONE)
+            .unwrap();
+        assert!(app.checkpoints.files.as_ref().unwrap().iter().all(|f| f.1));
+
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap();
+        assert_eq!(
+            app.checkpoints.confirm,
+            Some(CheckpointConfirm::RestoreFiles(1))
+        );
+        app.handle_key(KeyCode::Char('n'), KeyModifiers::NONE)
+            .unwrap();
+
+        app.handle_key(KeyCode::Esc, KeyModifiers::NONE).unwrap();
+        assert!(app.checkpoints.files.is_none());
+        assert_eq!(app.mode, Mode::Checkpoints);
+    }
+
+    #[test]
+    fn shift_c_opens_checkpoint_browser() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Idle)]);
+        app.focus = Pane::Detail;
+
+        app.handle_key(KeyCode::Char('C'), KeyModifiers::SHIFT)
+            .unwrap();
+        assert_eq!(app.mode, Mode::Checkpoints);
+        assert_eq!(app.checkpoints.task_id, 1);
+        assert_eq!(app.checkpoints.task_name, "a");
+
+        app.handle_key(KeyCode::Esc, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn checkpoint_browser_select_mark_and_confirm() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Idle)]);
+        app.mode = Mode::Checkpoints;
+        app.checkpoints.task_id = 1;
+        app.checkpoints.checkpoints = fake_checkpoints(3);
+        app.checkpoints.selected = 2;
+
+        app.handle_key(KeyCode::Char('k'), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.checkpoints.selected, 1);
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.checkpoints.mark, Some(1));
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.checkpoints.mark, None);
+
+        // Rollback asks first; anything but y cancels
+        app.handle_key(KeyCode::Char('R'), KeyModifiers::SHIFT)
+            .unwrap();
+        assert_eq!(
+            app.checkpoints.confirm,
+            Some(CheckpointConfirm::Rollback(2))
+        );
+        app.handle_key(KeyCode::Char('n'), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.checkpoints.confirm, None);
+        assert_eq!(app.mode, Mode::Checkpoints);
+
+        app.handle_key(KeyCode::Char('u'), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.checkpoints.confirm, None);
+        assert_eq!(app.error.as_deref(), Some("No rollback to undo"));
+    }
+
+    #[test]
+    fn checkpoint_browser_refuses_rollback_while_running() {
+        let mut app = make_app(vec![make_task(1, "a", task::Status::Running)]);
+        app.mode = Mode::Checkpoints;
+        app.checkpoints.task_id = 1;
+        app.checkpoints.checkpoints = fake_checkpoints(1);
+
+        app.handle_key(KeyCode::Char('R'), KeyModifiers::SHIFT)
+            .unwrap();
+        assert_eq!(app.checkpoints.confirm, None);
+        assert!(app.error.unwrap().contains("Stop the task"));
+    }
+
+    #[test]
+    fn checkpoint_browser_rolls_back_and_undoes() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path();
+        let git = |args: &[&str]| {
+            let out = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["commit", "--allow-empty", "-m", "v1"]);
+        checkpoint::create(dir, 1, "a", "HEAD", dir, None).unwrap();
+        git(&["commit", "--allow-empty", "-m", "v2"]);
+
+        let mut t = make_task(1, "a", task::Status::Idle);
+        t.worktree = dir.to_string_lossy().to_string();
+        let mut app = make_app(vec![t]);
+        app.repo_root = dir.to_path_buf();
+
+        app.handle_key(KeyCode::Char('C'), KeyModifiers::SHIFT)
+            .unwrap();
+        assert_eq!(app.checkpoints.checkpoints.len(), 1);
+        app.handle_key(KeyCode::Char('R'), KeyModifiers::SHIFT)
+            .unwrap();
+        app.handle_key(KeyCode::Char('y'), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(git(&["log", "-1", "--format=%s"]), "v1");
+        assert!(app.checkpoints.can_undo);
+
+        app.handle_key(KeyCode::Char('u'), KeyModifiers::NONE)
+            .unwrap();
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap();
+        assert_eq!(git(&["log", "-1", "--format=%s"]), "v2");
+    }
 }
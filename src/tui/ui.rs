@@ -4,9 +4,11 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
 use ratatui::Frame;
 
+use crate::core::checks;
+use crate::core::fork;
 use crate::core::task::Status;
 
-use super::app::{App, ModalField, Mode, Pane, View};
+use super::app::{App, CheckpointConfirm, ModalField, Mode, Pane, View};
 
 pub fn draw(frame: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -41,6 +43,14 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
         draw_issue_picker(frame, app);
     }
 
+    if app.mode == Mode::Checkpoints {
+        draw_checkpoints(frame, app);
+    }
+
+    if app.mode == Mode::Harvest {
+        draw_harvest(frame, app);
+    }
+
     if let Some(ref err) = app.error {
         draw_error_toast(frame, err);
     }
@@ -75,13 +85,50 @@ fn draw_task_list(frame: &mut Frame, app: &App, area: Rect, focused: bool) {
             let status_style = status_color(t.status.clone());
             let icon = status_icon(&t.status);
 
-            // Compact: icon + name + short status
-            let name_width = area.width.saturating_sub(10) as usize;
+            // Compact: icon + name + commits behind base + overlap warning
+            let behind = match app.behind.get(&t.id) {
+                Some(&n) if n > 0 => format!(" ↓{}", n),
+                _ => String::new(),
+            };
+            let overlaps: Vec<_> = app
+                .overlaps
+                .iter()
+                .filter(|o| o.involves(&t.name))
+                .collect();
+            let (warning, warning_color) = if overlaps.is_empty() {
+                ("", Color::Reset)
+            } else if overlaps.iter().any(|o| !o.conflicts.is_empty()) {
+                (" ⚠", Color::Red)
+            } else {
+                (" ⚠", Color::Yellow)
+            };
+            let queued = app
+                .queue_position(t.id)
+                .map(|n| format!(" ⇡{}", n))
+                .unwrap_or_default();
+            let (tested, tested_color) = test_badge(&t.test_status);
+            // Forks are marked with their depth below the first ancestor
+            let lineage = match fork_depth(app, t) {
+                0 => String::new(),
+                depth => format!("{}↳ ", "  ".repeat(depth - 1)),
+            };
+            let name_width = area.width.saturating_sub(
+                10 + lineage.chars().count() as u16
+                    + behind.chars().count() as u16
+                    + warning.chars().count() as u16
+                    + queued.chars().count() as u16
+                    + tested.chars().count() as u16,
+            ) as usize;
             let name: String = t.name.chars().take(name_width).collect();
 
             ListItem::new(Line::from(vec![
                 Span::styled(format!(" {} ", icon), status_style),
+                Span::styled(lineage, Style::default().fg(Color::DarkGray)),
                 Span::styled(name, Style::default().fg(Color::White)),
+                Span::styled(tested, Style::default().fg(tested_color)),
+                Span::styled(behind, Style::default().fg(Color::Yellow)),
+                Span::styled(warning, Style::default().fg(warning_color)),
+                Span::styled(queued, Style::default().fg(Color::Cyan)),
             ]))
         })
         .collect();
@@ -105,6 +152,30 @@ fn draw_task_list(frame: &mut Frame, app: &App, area: Rect, focused: bool) {
     frame.render_stateful_widget(list, area, &mut state);
 }
 
+/// How many of a task's fork ancestors are still in the list.
+fn fork_depth(app: &App, task: &crate::core::task::Task) -> usize {
+    let mut depth = 0;
+    let mut parent = task.parent_id;
+    while let Some(p) = app.tasks.iter().find(|t| Some(t.id) == parent) {
+        depth += 1;
+        parent = p.parent_id;
+        if depth >= app.tasks.len() {
+            break;
+        }
+    }
+    depth
+}
+
+/// Marker and colour for a task's last test run; empty if never tested.
+fn test_badge(status: &str) -> (&'static str, Color) {
+    match status {
+        "pass" => (" ✓", Color::Green),
+        "fail" => (" ✗", Color::Red),
+        "running" => (" ⋯", Color::Yellow),
+        _ => ("", Color::Reset),
+    }
+}
+
 // ── Detail pane (right side) ────────────────────────────────────────────────
 
 fn draw_detail_pane(frame: &mut Frame, app: &mut App, area: Rect, focused: bool) {
@@ -187,6 +258,32 @@ fn draw_detail_pane(frame: &mut Frame, app: &mut App, area: Rect, focused: bool)
         ]));
     }
 
+    // Lineage: the task this one was forked from, and its own forks
+    if let Some(parent) = task.parent_id {
+        let from = app
+            .tasks
+            .iter()
+            .find(|t| t.id == parent)
+            .map_or("a deleted task", |t| t.name.as_str());
+        header_lines.push(Line::from(vec![
+            Span::styled("fork:   ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("from {} at {}", from, task.fork_point),
+                Style::default().fg(Color::Gray),
+            ),
+        ]));
+    }
+    let forks: Vec<&str> = fork::children(task, &app.tasks)
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect();
+    if !forks.is_empty() {
+        header_lines.push(Line::from(vec![
+            Span::styled("forks:  ", Style::default().fg(Color::DarkGray)),
+            Span::styled(forks.join(", "), Style::default().fg(Color::Gray)),
+        ]));
+    }
+
     // Issue URL (if set)
     if !task.issue_url.is_empty() {
         header_lines.push(Line::from(vec![
@@ -200,6 +297,134 @@ fn draw_detail_pane(frame: &mut Frame, app: &mut App, area: Rect, focused: bool)
         ]));
     }
 
+    // Pull request (if opened)
+    if !task.pr_url.is_empty() {
+        let state_color = match task.pr_state.as_str() {
+            "open" => Color::Green,
+            "merged" => Color::Magenta,
+            "closed" => Color::Red,
+            _ => Color::DarkGray,
+        };
+        header_lines.push(Line::from(vec![
+            Span::styled("pr:     ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                &task.pr_url,
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::UNDERLINED),
+            ),
+            Span::styled(
+                format!(" [{}]", task.pr_state),
+                Style::default().fg(state_color),
+            ),
+        ]));
+    }
+
+    // Drift from the base branch
+    if let Some(&behind) = app.behind.get(&task.id).filter(|&&n| n > 0) {
+        header_lines.push(Line::from(vec![
+            Span::styled("base:   ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("{} commit(s) behind base", behind),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(
+                format!("  (pit sync {})", task.name),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+
+    // TODO marker the task was harvested from
+    if let Some(&present) = app.markers_present.get(&task.id) {
+        let (text, color) = if present {
+            ("marker still there", Color::DarkGray)
+        } else {
+            ("✓ marker removed", Color::Green)
+        };
+        header_lines.push(Line::from(vec![
+            Span::styled("todo:   ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("{}:{}  ", task.source_file, task.source_line),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::styled(text, Style::default().fg(color)),
+        ]));
+    }
+
+    // Last test run
+    if !task.test_status.is_empty() {
+        let (badge, color) = test_badge(&task.test_status);
+        let text = match task.test_status.as_str() {
+            "running" => "running…".to_string(),
+            status => format!(
+                "{} in {}  ({})",
+                if status == "pass" { "passed" } else { "failed" },
+                checks::format_duration(task.test_duration_ms),
+                task.tested_at
+            ),
+        };
+        header_lines.push(Line::from(vec![
+            Span::styled("tests: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{} {}", badge, text), Style::default().fg(color)),
+        ]));
+        if task.test_status == "fail" {
+            for line in task.test_summary.lines().take(4) {
+                let line: String = line.chars().take(w.saturating_sub(8)).collect();
+                header_lines.push(Line::from(Span::styled(
+                    format!("        {}", line),
+                    Style::default().fg(Color::Red),
+                )));
+            }
+        }
+    }
+
+    // Merge queue place and the last failure
+    if let Some(pos) = app.queue_position(task.id) {
+        let entry = &app.queue[pos - 1];
+        let mut spans = vec![
+            Span::styled("queue:  ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("#{} to merge", pos),
+                Style::default().fg(Color::Cyan),
+            ),
+        ];
+        if !entry.error.is_empty() {
+            spans.push(Span::styled(
+                format!("  last try failed: {}", entry.error),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        header_lines.push(Line::from(spans));
+    }
+
+    // Other active tasks changing the same files
+    for o in app.overlaps.iter().filter(|o| o.involves(&task.name)) {
+        let (text, color) = if o.conflicts.is_empty() {
+            (
+                format!(
+                    "{} also changes {}",
+                    o.other(&task.name),
+                    o.files.join(", ")
+                ),
+                Color::Yellow,
+            )
+        } else {
+            (
+                format!(
+                    "would conflict with {} in {}",
+                    o.other(&task.name),
+                    o.conflicts.join(", ")
+                ),
+                Color::Red,
+            )
+        };
+        header_lines.push(Line::from(vec![
+            Span::styled("⚠       ", Style::default().fg(color)),
+            Span::styled(text, Style::default().fg(color)),
+        ]));
+    }
+
     // Divider between header and body
     header_lines.push(Line::from(Span::styled(
         "─".repeat(w),
@@ -315,7 +540,7 @@ fn draw_detail_pane(frame: &mut Frame, app: &mut App, area: Rect, focused: bool)
             // Checkpoints section
             if let Some(task) = app.tasks.get(app.selected) {
                 let checkpoints =
-                    crate::core::checkpoint::list(&app.repo_root, &task.name).unwrap_or_default();
+                    crate::core::checkpoint::list(&app.repo_root, task.id).unwrap_or_default();
                 if !checkpoints.is_empty() {
                     let cp_count = checkpoints.len();
                     lines.push(Line::from(vec![
@@ -842,7 +1067,11 @@ fn draw_modal(frame: &mut Frame, app: &App) {
     frame.render_widget(Clear, modal_area);
 
     let block = Block::default()
-        .title(" New Task ")
+        .title(if app.modal.editing.is_some() {
+            " Edit Task "
+        } else {
+            " New Task "
+        })
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Yellow));
     frame.render_widget(block, modal_area);
@@ -991,39 +1220,49 @@ fn draw_modal(frame: &mut Frame, app: &App) {
 
     y += prompt_visible_lines + 2 + 1; // textarea height + 1 gap
 
-    // --- Agent ---
-    draw_field_label(frame, inner.x, y, fw, "Agent", m.field == ModalField::Agent);
+    // --- Agent + Template (side by side) ---
+    let half = fw / 2;
+    draw_field_label(
+        frame,
+        inner.x,
+        y,
+        half,
+        "Agent",
+        m.field == ModalField::Agent,
+    );
+    draw_field_label(
+        frame,
+        inner.x + half,
+        y,
+        fw - half,
+        "Template",
+        m.field == ModalField::Template,
+    );
     y += 1;
-    let agent_style = if m.field == ModalField::Agent {
-        Style::default()
-            .fg(Color::Cyan)
-            .add_modifier(Modifier::BOLD)
-    } else {
-        Style::default().fg(Color::Gray)
-    };
-    let arrows = if m.field == ModalField::Agent {
-        "  ◂ "
-    } else {
-        "  "
-    };
-    let arrows_r = if m.field == ModalField::Agent {
-        " ▸"
+    draw_cycler(
+        frame,
+        inner.x,
+        y,
+        half,
+        &m.agent,
+        m.field == ModalField::Agent,
+    );
+    let template_label = if m.template.is_empty() {
+        if m.templates.is_empty() {
+            "(none — add .pit/templates/*.md)"
+        } else {
+            "(none)"
+        }
     } else {
-        ""
+        m.template.as_str()
     };
-    let agent_widget = Paragraph::new(Line::from(vec![
-        Span::styled(arrows, Style::default().fg(Color::DarkGray)),
-        Span::styled(&m.agent, agent_style),
-        Span::styled(arrows_r, Style::default().fg(Color::DarkGray)),
-    ]));
-    frame.render_widget(
-        agent_widget,
-        Rect {
-            x: inner.x,
-            y,
-            width: fw,
-            height: 1,
-        },
+    draw_cycler(
+        frame,
+        inner.x + half,
+        y,
+        fw - half,
+        template_label,
+        m.field == ModalField::Template,
     );
     y += 1;
 
@@ -1176,6 +1415,36 @@ fn draw_field_label(frame: &mut Frame, x: u16, y: u16, w: u16, label: &str, acti
     );
 }
 
+/// A `◂ value ▸` selector; arrows only show when focused.
+fn draw_cycler(frame: &mut Frame, x: u16, y: u16, w: u16, value: &str, active: bool) {
+    let style = if active {
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+    let (arrows, arrows_r) = if active {
+        ("  ◂ ", " ▸")
+    } else {
+        ("  ", "")
+    };
+    let widget = Paragraph::new(Line::from(vec![
+        Span::styled(arrows, Style::default().fg(Color::DarkGray)),
+        Span::styled(value, style),
+        Span::styled(arrows_r, Style::default().fg(Color::DarkGray)),
+    ]));
+    frame.render_widget(
+        widget,
+        Rect {
+            x,
+            y,
+            width: w,
+            height: 1,
+        },
+    );
+}
+
 fn draw_field_input(frame: &mut Frame, x: u16, y: u16, w: u16, value: &str, active: bool) {
     let style = if active {
         Style::default().fg(Color::White)
@@ -1404,6 +1673,383 @@ fn draw_issue_picker(frame: &mut Frame, app: &App) {
     }
 }
 
+// ── TODO harvest picker ─────────────────────────────────────────────────────
+
+fn draw_harvest(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    let h = &app.harvest;
+    let width = 100u16.min(area.width.saturating_sub(4));
+    let height = (h.items.len() as u16 + 4).min(area.height.saturating_sub(4));
+
+    let vert = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .split(area);
+    let horiz = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .split(vert[0]);
+    let popup = horiz[0];
+    frame.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .title(format!(" Harvest TODOs · {} ", h.pattern))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    frame.render_widget(block, popup);
+
+    let inner = Rect {
+        x: popup.x + 2,
+        y: popup.y + 1,
+        width: popup.width.saturating_sub(4),
+        height: popup.height.saturating_sub(3),
+    };
+    let fw = inner.width as usize;
+
+    let lines: Vec<Line> = h
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let bg = if i == h.cursor {
+                Style::default().bg(Color::Rgb(40, 40, 50))
+            } else {
+                Style::default()
+            };
+            let check = match (&item.task, item.picked) {
+                (Some(_), _) => "    ",
+                (None, true) => "[x] ",
+                (None, false) => "[ ] ",
+            };
+            let location = format!("{}:{}  ", item.marker.file, item.marker.line);
+            let taken = match &item.task {
+                Some(name) => format!("  ({})", name),
+                None => String::new(),
+            };
+            let room = fw.saturating_sub(4 + location.chars().count() + taken.chars().count());
+            let summary: String = item.marker.summary().chars().take(room).collect();
+            let text_color = if item.task.is_some() {
+                Color::DarkGray
+            } else {
+                Color::White
+            };
+            Line::from(vec![
+                Span::styled(check, Style::default().fg(Color::Yellow).patch(bg)),
+                Span::styled(location, Style::default().fg(Color::Cyan).patch(bg)),
+                Span::styled(summary, Style::default().fg(text_color).patch(bg)),
+                Span::styled(taken, Style::default().fg(Color::DarkGray).patch(bg)),
+            ])
+        })
+        .collect();
+    let scroll = (h.cursor as u16 + 1).saturating_sub(inner.height);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), inner);
+
+    let key = |k: &'static str| Span::styled(k, Style::default().fg(Color::Yellow));
+    let help = Line::from(vec![
+        key(" ↑/↓"),
+        Span::raw(":select  "),
+        key("Space"),
+        Span::raw(":pick  "),
+        key("a"),
+        Span::raw(":all  "),
+        key("Enter"),
+        Span::raw(":create tasks  "),
+        key("Esc"),
+        Span::raw(":close"),
+    ]);
+    frame.render_widget(
+        Paragraph::new(help),
+        Rect {
+            x: inner.x,
+            y: (popup.y + popup.height).saturating_sub(2),
+            width: inner.width,
+            height: 1,
+        },
+    );
+}
+
+// ── Checkpoint browser ──────────────────────────────────────────────────────
+
+fn draw_checkpoints(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    let width = 100u16.min(area.width.saturating_sub(4));
+    let height = area.height.saturating_sub(4);
+
+    let vert = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .split(area);
+    let horiz = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .split(vert[0]);
+    let popup = horiz[0];
+    frame.render_widget(Clear, popup);
+
+    let b = &app.checkpoints;
+    let block = Block::default()
+        .title(format!(" Checkpoints · {} ", b.task_name))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta));
+    frame.render_widget(block, popup);
+
+    let inner = Rect {
+        x: popup.x + 2,
+        y: popup.y + 1,
+        width: popup.width.saturating_sub(4),
+        height: popup.height.saturating_sub(2),
+    };
+    if inner.height < 6 {
+        return;
+    }
+    let fw = inner.width as usize;
+    let row = |y: u16| Rect {
+        x: inner.x,
+        y,
+        width: inner.width,
+        height: 1,
+    };
+
+    if b.checkpoints.is_empty() {
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                "  No checkpoints yet — press c in the detail pane to save one",
+                Style::default().fg(Color::DarkGray),
+            )),
+            row(inner.y),
+        );
+    }
+
+    // Timeline: oldest at the top, scrolled to keep the selection visible
+    let list_height = (b.checkpoints.len() as u16).clamp(1, inner.height / 3);
+    let scroll = (b.selected + 1).saturating_sub(list_height as usize);
+    let mut y = inner.y;
+    for (i, cp) in b
+        .checkpoints
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(list_height as usize)
+    {
+        let is_selected = i == b.selected;
+        let bg = if is_selected {
+            Style::default().bg(Color::Rgb(40, 40, 50))
+        } else {
+            Style::default()
+        };
+        let marker = if is_selected { "▸ " } else { "  " };
+        let mark = if b.mark == Some(i) { "◆ " } else { "  " };
+        let dirty = if cp.dirty { " +uncommitted" } else { "" };
+        let auto = if cp.auto { " auto" } else { "" };
+        let fixed = 14 + cp.commit_hash.len() + dirty.len() + auto.len() + cp.timestamp.len();
+        let message: String = cp.message.chars().take(fw.saturating_sub(fixed)).collect();
+        let line = Line::from(vec![
+            Span::styled(marker, bg),
+            Span::styled(mark, Style::default().fg(Color::Yellow).patch(bg)),
+            Span::styled(
+                format!("#{:<3}", cp.index),
+                Style::default().fg(Color::Magenta).patch(bg),
+            ),
+            Span::styled(
+                cp.commit_hash.clone(),
+                Style::default().fg(Color::Yellow).patch(bg),
+            ),
+            Span::styled(
+                format!(" {}", message),
+                Style::default().fg(Color::White).patch(bg),
+            ),
+            Span::styled(dirty, Style::default().fg(Color::Cyan).patch(bg)),
+            Span::styled(auto, Style::default().fg(Color::DarkGray).patch(bg)),
+            Span::styled(
+                format!("  {}", cp.timestamp),
+                Style::default().fg(Color::DarkGray).patch(bg),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(line), row(y));
+        y += 1;
+    }
+    y = inner.y + list_height;
+
+    // Separator with the preview heading
+    let heading = match (&b.preview, b.checkpoints.get(b.selected)) {
+        _ if b.files.is_some() => format!(
+            "── Restore files from #{} ",
+            b.checkpoints.get(b.selected).map_or(0, |c| c.index)
+        ),
+        (Some(_), _) => format!("── Diff {} ", b.preview_title),
+        (None, Some(cp)) => format!("── Checkpoint #{} ", cp.index),
+        (None, None) => String::new(),
+    };
+    let rule = "─".repeat(fw.saturating_sub(heading.chars().count()));
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(
+                heading,
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(rule, Style::default().fg(Color::DarkGray)),
+        ])),
+        row(y),
+    );
+    y += 1;
+
+    // Preview: file picker, diff or annotation
+    let lines: Vec<Line> = match (&b.preview, b.checkpoints.get(b.selected)) {
+        _ if b.files.is_some() => b
+            .files
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(i, (path, picked))| {
+                let bg = if i == b.file_cursor {
+                    Style::default().bg(Color::Rgb(40, 40, 50))
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(
+                        if *picked { "[x] " } else { "[ ] " },
+                        Style::default().fg(Color::Yellow).patch(bg),
+                    ),
+                    Span::styled(path.clone(), Style::default().fg(Color::White).patch(bg)),
+                ])
+            })
+            .collect(),
+        (Some(diff), _) => diff
+            .iter()
+            .map(|l| {
+                let style = if l.starts_with("+++") || l.starts_with("---") {
+                    Style::default().fg(Color::White)
+                } else if l.starts_with('+') {
+                    Style::default().fg(Color::Green)
+                } else if l.starts_with('-') {
+                    Style::default().fg(Color::Red)
+                } else if l.starts_with("@@") {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                Line::from(Span::styled(l.clone(), style))
+            })
+            .collect(),
+        (None, Some(cp)) => cp
+            .annotation
+            .lines()
+            .skip(1) // "[pit checkpoint] <task>"
+            .map(|l| {
+                let style = if l.starts_with("## ") {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                Line::from(Span::styled(l.to_string(), style))
+            })
+            .collect(),
+        (None, None) => vec![],
+    };
+    let help_y = (popup.y + popup.height).saturating_sub(2);
+    let preview_area = Rect {
+        x: inner.x,
+        y,
+        width: inner.width,
+        height: help_y.saturating_sub(y + 1),
+    };
+    let scroll = match b.files {
+        // Keep the file cursor in view
+        Some(_) => (b.file_cursor as u16 + 1).saturating_sub(preview_area.height),
+        None => b.scroll,
+    };
+    frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), preview_area);
+
+    // Confirmation or key help
+    let help = match b.confirm {
+        Some(confirm) => {
+            let question = match confirm {
+                CheckpointConfirm::Rollback(idx) => format!(
+                    " Roll back to checkpoint #{}? Current state is kept for undo. ",
+                    idx
+                ),
+                CheckpointConfirm::RestoreFiles(idx) => format!(
+                    " Restore {} file(s) from checkpoint #{}? Current state is kept for undo. ",
+                    b.files
+                        .as_ref()
+                        .map_or(0, |f| f.iter().filter(|f| f.1).count()),
+                    idx
+                ),
+                CheckpointConfirm::Undo => " Undo the last rollback? ".to_string(),
+            };
+            Line::from(vec![
+                Span::styled(question, Style::default().fg(Color::White).bg(Color::Red)),
+                Span::styled(
+                    "  y",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(":yes  "),
+                Span::styled(
+                    "n",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(":no"),
+            ])
+        }
+        None if b.files.is_some() => {
+            let key = |k: &'static str| Span::styled(k, Style::default().fg(Color::Yellow));
+            Line::from(vec![
+                key(" ↑/↓"),
+                Span::raw(":select  "),
+                key("Space"),
+                Span::raw(":pick  "),
+                key("a"),
+                Span::raw(":all  "),
+                Span::styled("Enter", Style::default().fg(Color::Red)),
+                Span::raw(":restore  "),
+                key("Esc"),
+                Span::raw(":back"),
+            ])
+        }
+        None => {
+            let key = |k: &'static str| Span::styled(k, Style::default().fg(Color::Yellow));
+            let mut spans = vec![
+                key(" ↑/↓"),
+                Span::raw(":select  "),
+                key("Space"),
+                Span::raw(":mark  "),
+                key("d"),
+                Span::raw(":diff  "),
+                key("PgUp/PgDn"),
+                Span::raw(":scroll  "),
+                key("f"),
+                Span::raw(":files  "),
+                Span::styled("R", Style::default().fg(Color::Red)),
+                Span::raw(":rollback  "),
+            ];
+            if b.can_undo {
+                spans.push(Span::styled("u", Style::default().fg(Color::Red)));
+                spans.push(Span::raw(":undo  "));
+            }
+            spans.push(key("Esc"));
+            spans.push(Span::raw(":close"));
+            Line::from(spans)
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+        row(help_y),
+    );
+}
+
 fn draw_error_toast(frame: &mut Frame, msg: &str) {
     let area = frame.area();
     let toast_width = (msg.len() as u16 + 6).min(area.width.saturating_sub(4));
@@ -1500,6 +2146,13 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(":issue  "),
+            Span::styled(
+                "Ctrl+E",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":editor  "),
             Span::styled(
                 "Enter",
                 Style::default()
@@ -1552,6 +2205,13 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(":new  "),
+            Span::styled(
+                "e",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":edit  "),
             Span::styled(
                 "d",
                 Style::default()
@@ -1559,6 +2219,13 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(":del  "),
+            Span::styled(
+                "A",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":archive  "),
             Span::styled(
                 "v",
                 Style::default()
@@ -1623,6 +2290,13 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ),
             Span::raw(":rollback  "),
+            Span::styled(
+                "C",
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":checkpoints  "),
             Span::styled(
                 "n",
                 Style::default()
@@ -1668,6 +2342,13 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(":new  "),
+            Span::styled(
+                "e",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":edit  "),
             Span::styled(
                 "d",
                 Style::default()
@@ -1675,6 +2356,13 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(":del  "),
+            Span::styled(
+                "A",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":archive  "),
             Span::styled(
                 "v",
                 Style::default()
@@ -1682,6 +2370,34 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(":kanban  "),
+            Span::styled(
+                "T",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":test  "),
+            Span::styled(
+                "m",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":queue  "),
+            Span::styled(
+                "M",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":land  "),
+            Span::styled(
+                "H",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(":harvest  "),
             Span::styled(
                 "r",
                 Style::default()
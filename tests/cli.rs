@@ -146,6 +146,107 @@ fn new_with_agent_flag() {
         .stdout(predicate::str::contains("prompt: refactor API"));
 }
 
+#[test]
+fn new_from_template_renders_variables() {
+    let repo = make_git_repo();
+
+    Command::cargo_bin("pit")
+        .unwrap()
+        .arg("init")
+        .current_dir(repo.path())
+        .assert()
+        .success();
+
+    let templates = repo.path().join(".pit/templates");
+    std::fs::create_dir_all(&templates).unwrap();
+    std::fs::write(
+        templates.join("bugfix.md"),
+        "+++\nagent = \"codex\"\nmodel = \"o3\"\n\n[hooks]\nsetup = \"touch .setup-ran\"\n+++\nFix {module} on {branch}\n",
+    )
+    .unwrap();
+
+    // Missing user variable is reported
+    Command::cargo_bin("pit")
+        .unwrap()
+        .args(["new", "fix-auth", "--template", "bugfix"])
+        .current_dir(repo.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--var module="));
+
+    Command::cargo_bin("pit")
+        .unwrap()
+        .args(["new", "fix-auth", "-t", "bugfix", "--var", "module=auth"])
+        .current_dir(repo.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("agent: codex"))
+        .stdout(predicate::str::contains("model: o3"))
+        .stdout(predicate::str::contains("prompt: Fix auth on pit/fix-auth"));
+
+    assert!(repo
+        .path()
+        .join(".pit/worktrees/fix-auth/.setup-ran")
+        .exists());
+}
+
+#[test]
+fn edit_updates_task_fields() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "editable");
+
+    Command::cargo_bin("pit")
+        .unwrap()
+        .args(["edit", "editable", "-p", "new prompt", "-a", "codex"])
+        .current_dir(repo.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Updated task 'editable' (agent: codex)",
+        ));
+
+    Command::cargo_bin("pit")
+        .unwrap()
+        .args(["edit", "missing", "-p", "x"])
+        .current_dir(repo.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn reassign_switches_agent_with_handoff_brief() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "handoff");
+
+    Command::cargo_bin("pit")
+        .unwrap()
+        .args(["reassign", "handoff", "-a", "codex", "--dry-run"])
+        .current_dir(repo.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# Handoff brief"))
+        .stdout(predicate::str::contains("You (codex) are taking over"));
+
+    Command::cargo_bin("pit")
+        .unwrap()
+        .args(["reassign", "handoff", "-a", "codex"])
+        .current_dir(repo.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Reassigned task 'handoff' from claude to codex",
+        ));
+
+    Command::cargo_bin("pit")
+        .unwrap()
+        .args(["reassign", "handoff", "-a", "codex"])
+        .current_dir(repo.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already uses codex"));
+}
+
 #[test]
 fn list_shows_tasks() {
     let repo = make_git_repo();
@@ -276,6 +377,659 @@ fn init_repo_with_task(repo: &TempDir, name: &str) {
         .success();
 }
 
+#[test]
+fn checkpoint_list_show_diff_and_undo() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "cp");
+    let wt = repo.path().join(".pit/worktrees/cp");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+
+    std::fs::write(wt.join("notes.txt"), "first\n").unwrap();
+    pit(&["checkpoint", "cp"]).assert().success();
+    std::fs::write(wt.join("notes.txt"), "second\n").unwrap();
+    pit(&["checkpoint", "cp"]).assert().success();
+
+    let out = pit(&["checkpoints", "cp", "--json"]).output().unwrap();
+    let list: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(list.as_array().unwrap().len(), 2);
+    assert_eq!(list[0]["ref"], "refs/pit/1/checkpoints/1");
+    assert_eq!(list[1]["dirty"], true);
+
+    pit(&["checkpoints", "cp"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#2"))
+        .stdout(predicate::str::contains("+uncommitted"));
+
+    pit(&["checkpoint", "show", "cp", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[pit checkpoint] cp"))
+        .stdout(predicate::str::contains("notes.txt"));
+
+    pit(&["checkpoint", "diff", "cp", "1", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-first"))
+        .stdout(predicate::str::contains("+second"));
+
+    pit(&["rollback", "cp", "--to", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Undo with: pit rollback cp --undo",
+        ));
+    assert_eq!(
+        std::fs::read_to_string(wt.join("notes.txt")).unwrap(),
+        "first\n"
+    );
+
+    let out = pit(&["rollback", "cp", "--undo", "--json"])
+        .output()
+        .unwrap();
+    let undo: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(undo["undone"], true);
+    assert_eq!(
+        std::fs::read_to_string(wt.join("notes.txt")).unwrap(),
+        "second\n"
+    );
+
+    pit(&["checkpoint", "show", "cp", "9"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("checkpoint 9 not found"));
+}
+
+#[test]
+fn rollback_restores_selected_paths() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "rp");
+    let wt = repo.path().join(".pit/worktrees/rp");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+
+    std::fs::write(wt.join("a.txt"), "a1\n").unwrap();
+    std::fs::write(wt.join("b.txt"), "b1\n").unwrap();
+    pit(&["checkpoint", "rp"]).assert().success();
+    std::fs::write(wt.join("a.txt"), "a2\n").unwrap();
+    std::fs::write(wt.join("b.txt"), "b2\n").unwrap();
+
+    pit(&["rollback", "rp", "--to", "1", "--", "a.txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Restored a.txt from checkpoint #1",
+        ))
+        .stdout(predicate::str::contains("saved as checkpoint #2"));
+    assert_eq!(std::fs::read_to_string(wt.join("a.txt")).unwrap(), "a1\n");
+    assert_eq!(std::fs::read_to_string(wt.join("b.txt")).unwrap(), "b2\n");
+
+    pit(&["rollback", "rp", "--undo"]).assert().success();
+    assert_eq!(std::fs::read_to_string(wt.join("a.txt")).unwrap(), "a2\n");
+}
+
+#[test]
+fn squash_folds_branch_and_undoes() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "sq");
+    let wt = repo.path().join(".pit/worktrees/sq");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    let git = |args: &[&str]| {
+        let out = process::Command::new("git")
+            .args(args)
+            .current_dir(&wt)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    };
+
+    for msg in ["first step", "second step"] {
+        std::fs::write(wt.join(format!("{}.txt", msg.replace(' ', "-"))), msg).unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", msg]);
+    }
+
+    // Declining the preview leaves the branch alone
+    pit(&["squash", "sq"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Aborted."));
+    assert_eq!(git(&["rev-list", "--count", "HEAD"]), "3");
+
+    pit(&["squash", "sq", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- first step"))
+        .stdout(predicate::str::contains("Squashed 2 commit(s)"));
+    assert_eq!(git(&["rev-list", "--count", "HEAD"]), "2");
+
+    pit(&["squash", "sq", "--undo"]).assert().success();
+    assert_eq!(git(&["rev-list", "--count", "HEAD"]), "3");
+}
+
+#[test]
+fn sync_reports_conflicts_then_rebases() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "drift");
+    let wt = repo.path().join(".pit/worktrees/drift");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    let commit = |dir: &std::path::Path, file: &str, content: &str| {
+        std::fs::write(dir.join(file), content).unwrap();
+        for args in [&["add", file][..], &["commit", "-q", "-m", file]] {
+            process::Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap();
+        }
+    };
+
+    pit(&["sync", "drift"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already up to date"));
+
+    commit(&wt, "notes.txt", "branch\n");
+    commit(repo.path(), "notes.txt", "main\n");
+    pit(&["sync", "drift"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("conflicts with master"))
+        .stdout(predicate::str::contains("notes.txt"))
+        .stdout(predicate::str::contains("--resolve"));
+    assert_eq!(
+        std::fs::read_to_string(wt.join("notes.txt")).unwrap(),
+        "branch\n"
+    );
+
+    process::Command::new("git")
+        .args(["reset", "-q", "--hard", "HEAD~1"])
+        .current_dir(&wt)
+        .output()
+        .unwrap();
+    commit(&wt, "feature.txt", "feature\n");
+    pit(&["sync", "drift"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Rebased pit/drift onto master (1 new commit(s))",
+        ));
+    assert_eq!(
+        std::fs::read_to_string(wt.join("notes.txt")).unwrap(),
+        "main\n"
+    );
+}
+
+#[test]
+fn conflicts_lists_tasks_changing_the_same_lines() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "one");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    pit(&["new", "two"]).assert().success();
+
+    pit(&["conflicts"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "No active tasks change the same files.",
+        ));
+
+    for name in ["one", "two"] {
+        let wt = repo.path().join(".pit/worktrees").join(name);
+        std::fs::write(wt.join("config.toml"), format!("owner = \"{}\"\n", name)).unwrap();
+    }
+    pit(&["conflicts"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "one ↔ two  would conflict in: config.toml",
+        ));
+    pit(&["conflicts", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"conflicts\": [\n      \"config.toml\"",
+        ));
+}
+
+#[test]
+fn queue_merge_lands_passing_tasks_and_keeps_failures() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "good");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args)
+            .current_dir(repo.path())
+            .env("TEST_COMMAND", "test ! -f bad.txt");
+        cmd
+    };
+    pit(&["new", "bad"]).assert().success();
+    for name in ["good", "bad"] {
+        let wt = repo.path().join(".pit/worktrees").join(name);
+        std::fs::write(wt.join(format!("{}.txt", name)), name).unwrap();
+        for args in [&["add", "-A"][..], &["commit", "-q", "-m", name]] {
+            process::Command::new("git")
+                .args(args)
+                .current_dir(&wt)
+                .output()
+                .unwrap();
+        }
+    }
+
+    pit(&["queue-merge", "bad", "good"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "✗ bad: 'test ! -f bad.txt' failed",
+        ))
+        .stdout(predicate::str::contains("✓ Merged pit/good into master"))
+        .stderr(predicate::str::contains("1 of 2 task(s) failed to land"));
+    assert!(repo.path().join("good.txt").exists());
+    assert!(!repo.path().join("bad.txt").exists());
+
+    pit(&["queue-merge", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1. bad  ✗ 'test ! -f bad.txt' failed",
+        ))
+        .stdout(predicate::str::contains("good").not());
+}
+
+#[test]
+fn test_runs_repo_commands_and_records_result() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "checked");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+
+    pit(&["test", "checked"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no test_command"));
+
+    pit(&[
+        "config",
+        "set",
+        "--repo",
+        "test_command",
+        "test -f ready.txt",
+    ])
+    .assert()
+    .success();
+    pit(&["config", "get", "--repo", "test_command"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("test -f ready.txt"));
+    pit(&["test", "checked"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("✗ 'checked' failed"))
+        .stdout(predicate::str::contains(
+            "'test -f ready.txt' failed (exit code 1)",
+        ));
+
+    std::fs::write(repo.path().join(".pit/worktrees/checked/ready.txt"), "").unwrap();
+    pit(&["test", "checked"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ 'checked' passed in"));
+}
+
+#[test]
+fn new_from_test_failures_creates_a_task_per_failure() {
+    let repo = make_git_repo();
+    Command::cargo_bin("pit")
+        .unwrap()
+        .arg("init")
+        .current_dir(repo.path())
+        .assert()
+        .success();
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    std::fs::write(
+        repo.path().join("output.txt"),
+        "\
+---- parser::handles_empty stdout ----
+thread 'parser::handles_empty' panicked at src/parser.rs:42:9:
+assertion failed: tokens.is_empty()
+
+---- math::adds stdout ----
+thread 'math::adds' panicked at src/math.rs:7:5:
+attempt to add with overflow
+
+failures:
+    math::adds
+    parser::handles_empty
+",
+    )
+    .unwrap();
+
+    pit(&["config", "set", "--repo", "test_command", "true"])
+        .assert()
+        .success();
+    pit(&["new", "--from-test-failures"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All tests pass"));
+
+    pit(&[
+        "config",
+        "set",
+        "--repo",
+        "test_command",
+        "cat output.txt; exit 101",
+    ])
+    .assert()
+    .success();
+    pit(&["new", "--from-test-failures"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "fix-handles-empty  parser::handles_empty (src/parser.rs:42)",
+        ))
+        .stdout(predicate::str::contains(
+            "fix-adds  math::adds (src/math.rs:7)",
+        ));
+    pit(&["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fix-handles-empty"))
+        .stdout(predicate::str::contains("fix-adds"));
+
+    // A second run doesn't clash with the tasks already there
+    pit(&["new", "--from-test-failures"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fix-adds-2"));
+}
+
+#[test]
+fn harvest_creates_tasks_from_todo_markers() {
+    let repo = make_git_repo();
+    std::fs::write(
+        repo.path().join("lib.rs"),
+        "// TODO(agent): parse the config lazily\nfn a() {}\n// TODO(agent): log retries\n",
+    )
+    .unwrap();
+    for args in [&["add", "."][..], &["commit", "-q", "-m", "todos"]] {
+        process::Command::new("git")
+            .args(args)
+            .current_dir(repo.path())
+            .output()
+            .unwrap();
+    }
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    pit(&["init"]).assert().success();
+
+    pit(&["harvest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1. lib.rs:1  parse the config lazily",
+        ))
+        .stdout(predicate::str::contains("2. lib.rs:3  log retries"))
+        .stdout(predicate::str::contains("pit harvest --pick"));
+    pit(&["harvest", "--pick", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created task 'todo-log-retries' from lib.rs:3",
+        ));
+    pit(&["harvest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "log retries  (task todo-log-retries)",
+        ));
+    pit(&["harvest", "--status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("lib.rs:3  marker still there"));
+
+    std::fs::write(
+        repo.path().join(".pit/worktrees/todo-log-retries/lib.rs"),
+        "// TODO(agent): parse the config lazily\nfn a() {}\n",
+    )
+    .unwrap();
+    pit(&["harvest", "--status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("lib.rs:3  marker removed"));
+}
+
+#[test]
+fn adopt_registers_an_existing_branch() {
+    let repo = make_git_repo();
+    process::Command::new("git")
+        .args(["branch", "feature/login"])
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    pit(&["init"]).assert().success();
+
+    pit(&["adopt", "no-such-branch"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no local branch 'no-such-branch'"));
+    pit(&["adopt", "feature/login", "-a", "codex"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Adopted branch 'feature/login' as task 'login' (agent: codex)",
+        ));
+    pit(&["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("feature/login"));
+    pit(&["delete", "login"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kept branch 'feature/login'"));
+
+    let branches = process::Command::new("git")
+        .args(["branch", "--list", "feature/login"])
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&branches.stdout).contains("feature/login"));
+}
+
+#[test]
+fn fork_starts_from_current_state_or_a_checkpoint() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "base");
+    let wt = repo.path().join(".pit/worktrees/base");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+
+    std::fs::write(wt.join("plan.md"), "v1").unwrap();
+    pit(&["checkpoint", "base"]).assert().success();
+    std::fs::write(wt.join("plan.md"), "v2").unwrap();
+
+    pit(&["fork", "base", "now"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Forked 'base' into 'now' at"))
+        .stdout(predicate::str::contains("+ uncommitted work"));
+    pit(&["fork", "base", "earlier", "--at-checkpoint", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Forked 'base' into 'earlier' at checkpoint #1",
+        ));
+    pit(&["fork", "base", "missing", "--at-checkpoint", "7"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("checkpoint 7 not found"));
+
+    let read = |task: &str| {
+        std::fs::read_to_string(
+            repo.path()
+                .join(".pit/worktrees")
+                .join(task)
+                .join("plan.md"),
+        )
+        .unwrap()
+    };
+    assert_eq!(read("now"), "v2");
+    assert_eq!(read("earlier"), "v1");
+    assert_eq!(read("base"), "v2");
+}
+
+/// Serve canned JSON responses, one per connection, on a local port.
+/// The handle yields the request lines seen ("GET /path").
+fn mock_github(responses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<String>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut seen = Vec::new();
+        for body in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(v) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = v.trim().parse().unwrap();
+                }
+            }
+            let mut req_body = vec![0; length];
+            reader.read_exact(&mut req_body).unwrap();
+            seen.push(request_line.trim().to_string());
+            write!(
+                reader.into_inner(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+        seen
+    });
+    (base, handle)
+}
+
+#[test]
+fn pr_pushes_branch_and_tracks_pull_request() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "feat");
+    let bare = tempfile::tempdir().unwrap();
+    let git = |dir: &std::path::Path, args: &[&str]| {
+        process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+    };
+    git(bare.path(), &["init", "-q", "--bare"]);
+    git(
+        repo.path(),
+        &[
+            "remote",
+            "add",
+            "origin",
+            "https://github.com/acme/widgets.git",
+        ],
+    );
+    // Pushes go to the local bare repo; the URL still names the GitHub repo
+    git(
+        repo.path(),
+        &[
+            "config",
+            &format!("url.{}.pushInsteadOf", bare.path().display()),
+            "https://github.com/acme/widgets.git",
+        ],
+    );
+    let wt = repo.path().join(".pit/worktrees/feat");
+    std::fs::write(wt.join("feature.txt"), "feature").unwrap();
+    git(&wt, &["add", "-A"]);
+    git(&wt, &["commit", "-q", "-m", "add feature"]);
+
+    let (api, server) = mock_github(vec![
+        "[]",
+        r#"{"number":9,"html_url":"https://github.com/acme/widgets/pull/9","state":"open"}"#,
+        r#"{"number":9,"html_url":"https://github.com/acme/widgets/pull/9","state":"closed","merged":true}"#,
+    ]);
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args)
+            .current_dir(repo.path())
+            .env("GITHUB_API_URL", &api)
+            .env("GITHUB_TOKEN", "test-token");
+        cmd
+    };
+
+    pit(&["pr", "feat"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pushed pit/feat to origin"))
+        .stdout(predicate::str::contains(
+            "Pull request #9: https://github.com/acme/widgets/pull/9",
+        ));
+    let pushed = git(bare.path(), &["log", "-1", "--format=%s", "pit/feat"]);
+    assert_eq!(
+        String::from_utf8_lossy(&pushed.stdout).trim(),
+        "add feature"
+    );
+
+    // Running it again pushes and refreshes the stored state
+    pit(&["pr", "feat"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pull/9 [merged]"));
+
+    let seen = server.join().unwrap();
+    assert!(seen[0].starts_with("GET /repos/acme/widgets/pulls?state=all"));
+    assert!(seen[1].starts_with("POST /repos/acme/widgets/pulls "));
+    assert!(seen[2].starts_with("GET /repos/acme/widgets/pulls/9 "));
+}
+
 #[test]
 fn status_shows_tasks() {
     let repo = make_git_repo();
@@ -601,3 +1355,197 @@ fn list_also_reaps() {
         .success()
         .stdout(predicate::str::contains("idle"));
 }
+
+#[test]
+fn archive_hides_a_task_until_it_is_restored() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "old-idea");
+    let wt = repo.path().join(".pit/worktrees/old-idea");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+
+    std::fs::write(wt.join("notes.md"), "half done").unwrap();
+    pit(&["archive", "old-idea"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Archived 'old-idea' (branch kept at refs/pit/archive/old-idea)",
+        ));
+    assert!(!wt.exists());
+    pit(&["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("old-idea").not());
+    pit(&["list", "--archived"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("old-idea"))
+        .stdout(predicate::str::contains("1 archived task(s)"));
+    pit(&["new", "old-idea"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is archived"));
+
+    pit(&["restore", "old-idea"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 'old-idea'"));
+    assert_eq!(
+        std::fs::read_to_string(wt.join("notes.md")).unwrap(),
+        "half done"
+    );
+    pit(&["restore", "old-idea"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not archived"));
+
+    pit(&["archive", "old-idea"]).assert().success();
+    pit(&["archive", "--purge-older-than", "0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Purged 1 archived task(s)"));
+    pit(&["list", "--archived"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No archived tasks."));
+}
+
+#[test]
+fn repair_rebuild_recovers_tasks_from_git() {
+    let repo = make_git_repo();
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    pit(&["init"]).assert().success();
+    pit(&["new", "login", "-p", "add a login form", "-a", "codex"])
+        .assert()
+        .success();
+
+    pit(&["repair"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to recover"));
+
+    std::fs::remove_file(repo.path().join(".pit/pit.db")).unwrap();
+    pit(&["repair", "--rebuild"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("login"))
+        .stdout(predicate::str::contains("from metadata, agent: codex"))
+        .stdout(predicate::str::contains("Recovered 1 task(s)"));
+    pit(&["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pit/login"));
+    assert!(repo.path().join(".pit/pit.db").exists());
+}
+
+#[test]
+fn doctor_reports_and_gc_prunes_drifted_state() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "keep");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    std::fs::create_dir_all(repo.path().join(".pit/worktrees/leftover")).unwrap();
+    std::fs::write(
+        repo.path().join(".pit/config.toml"),
+        "test_comand = \"cargo test\"\n",
+    )
+    .unwrap();
+
+    pit(&["doctor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unknown key 'test_comand'"))
+        .stdout(predicate::str::contains(
+            "orphaned worktree .pit/worktrees/leftover (no task)",
+        ))
+        .stdout(predicate::str::contains("1 problem(s)"));
+
+    pit(&["gc", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Disk usage by worktree:"))
+        .stdout(predicate::str::contains("keep"))
+        .stdout(predicate::str::contains(
+            "would remove orphaned worktree .pit/worktrees/leftover",
+        ));
+    assert!(repo.path().join(".pit/worktrees/leftover").exists());
+
+    pit(&["gc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fixed 1 problem(s)"));
+    assert!(!repo.path().join(".pit/worktrees/leftover").exists());
+    pit(&["gc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to clean up."));
+}
+
+#[test]
+fn worktrees_migrate_moves_worktrees_to_the_configured_root() {
+    let repo = make_git_repo();
+    init_repo_with_task(&repo, "old");
+    let pit = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("pit").unwrap();
+        cmd.args(args).current_dir(repo.path());
+        cmd
+    };
+    let outside = tempfile::tempdir().unwrap();
+    let root = outside.path().canonicalize().unwrap().join("{repo}");
+    pit(&[
+        "config",
+        "set",
+        "--repo",
+        "worktree_root",
+        root.to_str().unwrap(),
+    ])
+    .assert()
+    .success();
+
+    // New tasks go to the new root; existing ones stay until migrated
+    pit(&["new", "fresh"]).assert().success();
+    let repo_dir = std::fs::read_dir(outside.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert!(repo_dir.join("fresh").exists());
+    pit(&["worktrees"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 task(s) elsewhere"));
+
+    pit(&["worktrees", "migrate", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("would move old"));
+    assert!(repo.path().join(".pit/worktrees/old").exists());
+
+    pit(&["worktrees", "migrate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved 1 worktree(s)"));
+    assert!(!repo.path().join(".pit/worktrees").exists());
+    assert!(repo_dir.join("old").join(".git").exists());
+    pit(&["worktrees"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "All 2 task(s) have their worktree there.",
+        ));
+    pit(&["doctor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("problem(s)").not());
+}